@@ -0,0 +1,307 @@
+/*!
+ * Accounts
+ *
+ * Named EMAPTA accounts, each with its own token pair, schedule, scheduler
+ * (session state and operation history) and activity log:
+ * - The `default` account always exists and keeps the original unprefixed
+ *   storage keys, so single-account installs carry on unchanged
+ * - Every other account prefixes its keys with `account_<id>_`
+ * - Scheduler events of the default account go out as `scheduler_event`,
+ *   those of other accounts as `scheduler_event:<id>`
+ *
+ * The account list itself is persisted in storage. `AccountContext` bundles
+ * an account ID with the app handle and is what account-scoped code
+ * (token manager, scheduler, operation history) works against.
+ */
+
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::documents;
+use crate::errors::AppError;
+use crate::logging::ActivityLogger;
+use crate::scheduler::SchedulerEvent;
+use crate::storage::get_storage;
+
+/// Storage key for the persisted account list
+const ACCOUNTS_STORAGE_KEY: &str = "accounts";
+
+/// Account that owns the unprefixed storage keys
+pub const DEFAULT_ACCOUNT_ID: &str = "default";
+const DEFAULT_ACCOUNT_NAME: &str = "Default";
+
+/// Event name used for the default account's scheduler events
+const SCHEDULER_EVENT: &str = "scheduler_event";
+
+const MAX_ACCOUNT_ID_LENGTH: usize = 32;
+const MAX_ACCOUNT_NAME_LENGTH: usize = 64;
+
+/// A named EMAPTA account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    /// Stable identifier used in storage keys, event names and commands
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// When the account was added (ISO 8601 UTC)
+    pub created_at: String,
+}
+
+impl Account {
+    fn default_account() -> Self {
+        Self {
+            id: DEFAULT_ACCOUNT_ID.to_string(),
+            name: DEFAULT_ACCOUNT_NAME.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+static ACCOUNTS: RwLock<Vec<Account>> = RwLock::new(Vec::new());
+
+/// Storage key of `key` for an account
+pub fn storage_key(account_id: &str, key: &str) -> String {
+    if account_id == DEFAULT_ACCOUNT_ID {
+        key.to_string()
+    } else {
+        format!("account_{}_{}", account_id, key)
+    }
+}
+
+/// Name of the event carrying an account's scheduler events
+pub fn event_name(account_id: &str) -> String {
+    if account_id == DEFAULT_ACCOUNT_ID {
+        SCHEDULER_EVENT.to_string()
+    } else {
+        format!("{}:{}", SCHEDULER_EVENT, account_id)
+    }
+}
+
+/// Account IDs are lowercase letters, digits and `-`: they end up in file names, and
+/// without `_` no account's key prefix can be the start of another's
+fn validate_account_id(account_id: &str) -> Result<(), AppError> {
+    if account_id.is_empty() || account_id.len() > MAX_ACCOUNT_ID_LENGTH {
+        return Err(AppError::validation("accountId", format!(
+            "Account ID must be 1 to {} characters long", MAX_ACCOUNT_ID_LENGTH
+        )));
+    }
+    if !account_id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err(AppError::validation("accountId", "Account ID may only contain a-z, 0-9 and '-'"));
+    }
+    Ok(())
+}
+
+/// Check an account's ID and name (e.g. one read from a settings bundle)
+pub fn validate_account(account: &Account) -> Result<(), AppError> {
+    validate_account_id(&account.id)?;
+    validate_account_name(&account.name).map(|_| ())
+}
+
+fn validate_account_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_ACCOUNT_NAME_LENGTH {
+        return Err(AppError::validation("name", format!(
+            "Account name must be 1 to {} characters long", MAX_ACCOUNT_NAME_LENGTH
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Derive an account ID from a display name ("Test Account" -> "test-account")
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').chars().take(MAX_ACCOUNT_ID_LENGTH).collect::<String>().trim_end_matches('-').to_string()
+}
+
+/// Registered accounts, default account first
+pub fn list() -> Vec<Account> {
+    let accounts = ACCOUNTS.read().unwrap();
+    if accounts.is_empty() {
+        vec![Account::default_account()]
+    } else {
+        accounts.clone()
+    }
+}
+
+/// Whether an account is registered
+pub fn exists(account_id: &str) -> bool {
+    list().iter().any(|account| account.id == account_id)
+}
+
+/// Resolve an optional account ID from a command (the default account when `None`)
+pub fn resolve(account_id: Option<String>) -> Result<String, AppError> {
+    let account_id = account_id.unwrap_or_else(|| DEFAULT_ACCOUNT_ID.to_string());
+    if !exists(&account_id) {
+        return Err(AppError::validation("accountId", format!("Unknown account '{}'", account_id)));
+    }
+    Ok(account_id)
+}
+
+/// Load the persisted account list
+pub async fn load(app_handle: &AppHandle) -> Result<Vec<Account>, AppError> {
+    let storage = get_storage(app_handle)?;
+    let mut accounts = documents::load::<Vec<Account>>(&*storage, ACCOUNTS_STORAGE_KEY, &documents::ACCOUNTS).await?
+        .unwrap_or_default();
+
+    accounts.retain(|account| validate_account_id(&account.id).is_ok());
+    if !accounts.iter().any(|account| account.id == DEFAULT_ACCOUNT_ID) {
+        accounts.insert(0, Account::default_account());
+    }
+
+    log::info!("{} account(s) registered", accounts.len());
+    *ACCOUNTS.write().unwrap() = accounts.clone();
+    Ok(accounts)
+}
+
+/// Persist and apply a new account list
+async fn save(app_handle: &AppHandle, accounts: Vec<Account>) -> Result<(), AppError> {
+    let storage = get_storage(app_handle)?;
+    documents::save(&*storage, ACCOUNTS_STORAGE_KEY, &documents::ACCOUNTS, &accounts).await?;
+    *ACCOUNTS.write().unwrap() = accounts;
+    Ok(())
+}
+
+/// Register a new account; the ID is derived from the name when not given
+pub async fn add_account(app_handle: &AppHandle, name: &str, account_id: Option<String>) -> Result<Account, AppError> {
+    let name = validate_account_name(name)?;
+    let account_id = account_id.map(|id| id.trim().to_string()).unwrap_or_else(|| slugify(&name));
+    validate_account_id(&account_id)?;
+
+    let mut accounts = list();
+    if accounts.iter().any(|account| account.id == account_id) {
+        return Err(AppError::validation("accountId", format!("Account '{}' already exists", account_id)));
+    }
+
+    let account = Account { id: account_id, name, created_at: chrono::Utc::now().to_rfc3339() };
+    accounts.push(account.clone());
+    save(app_handle, accounts).await?;
+
+    log::info!("Added account '{}'", account.id);
+    Ok(account)
+}
+
+/// Change an account's display name
+pub async fn rename_account(app_handle: &AppHandle, account_id: &str, name: &str) -> Result<Account, AppError> {
+    let name = validate_account_name(name)?;
+
+    let mut accounts = list();
+    let account = accounts.iter_mut()
+        .find(|account| account.id == account_id)
+        .ok_or_else(|| AppError::validation("accountId", format!("Unknown account '{}'", account_id)))?;
+    account.name = name;
+    let account = account.clone();
+    save(app_handle, accounts).await?;
+
+    Ok(account)
+}
+
+/// Unregister an account and delete every key it owns.
+///
+/// The caller stops the account's scheduler and signs it out first.
+pub async fn remove_account(app_handle: &AppHandle, account_id: &str) -> Result<u32, AppError> {
+    if account_id == DEFAULT_ACCOUNT_ID {
+        return Err(AppError::validation("accountId", "The default account cannot be removed"));
+    }
+
+    let mut accounts = list();
+    let count = accounts.len();
+    accounts.retain(|account| account.id != account_id);
+    if accounts.len() == count {
+        return Err(AppError::validation("accountId", format!("Unknown account '{}'", account_id)));
+    }
+    save(app_handle, accounts).await?;
+
+    let storage = get_storage(app_handle)?;
+    let prefix = storage_key(account_id, "");
+    let mut deleted = 0;
+    for key in storage.list_keys().await? {
+        if key.starts_with(&prefix) {
+            storage.delete(&key).await?;
+            deleted += 1;
+        }
+    }
+
+    if let Ok(log_store) = crate::log_store::get_log_store(app_handle) {
+        log_store.clear(account_id)?;
+    }
+
+    log::info!("Removed account '{}' ({} stored keys deleted)", account_id, deleted);
+    Ok(deleted)
+}
+
+/// One account's scope: its storage keys, scheduler events and activity log
+#[derive(Clone)]
+pub struct AccountContext {
+    app_handle: AppHandle,
+    account_id: String,
+}
+
+impl AccountContext {
+    pub fn new(app_handle: AppHandle, account_id: impl Into<String>) -> Self {
+        Self { app_handle, account_id: account_id.into() }
+    }
+
+    /// Context of an account named by a command (the default account when `None`)
+    pub fn resolve(app_handle: AppHandle, account_id: Option<String>) -> Result<Self, AppError> {
+        Ok(Self::new(app_handle, resolve(account_id)?))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn app_handle(&self) -> &AppHandle {
+        &self.app_handle
+    }
+
+    /// This account's storage key for `key`
+    pub fn key(&self, key: &str) -> String {
+        storage_key(&self.account_id, key)
+    }
+
+    /// Send a scheduler event to the frontend on this account's event name
+    pub fn emit(&self, event: &SchedulerEvent) {
+        let _ = self.app_handle.emit(&event_name(&self.account_id), event);
+
+        #[cfg(feature = "system-tray")]
+        crate::tray::schedule_status_refresh(&self.app_handle);
+    }
+
+    /// Activity logger writing to this account's log (once logging is initialized)
+    pub fn logger(&self) -> Option<ActivityLogger> {
+        crate::logging::get_logger()?;
+        Some(ActivityLogger::for_account(self.app_handle.clone(), &self.account_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_account_keeps_legacy_keys() {
+        assert_eq!(storage_key(DEFAULT_ACCOUNT_ID, "refresh_token"), "refresh_token");
+        assert_eq!(storage_key("test", "refresh_token"), "account_test_refresh_token");
+        assert_eq!(event_name(DEFAULT_ACCOUNT_ID), "scheduler_event");
+        assert_eq!(event_name("test"), "scheduler_event:test");
+    }
+
+    #[test]
+    fn test_account_ids() {
+        assert_eq!(slugify("  Test Account #2 "), "test-account-2");
+        assert!(validate_account_id("team-lead-2").is_ok());
+        assert!(validate_account_id("team_lead").is_err());
+        assert!(validate_account_id("").is_err());
+        assert!(validate_account_id("Upper").is_err());
+        assert!(validate_account_id("../escape").is_err());
+    }
+}
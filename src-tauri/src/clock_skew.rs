@@ -0,0 +1,182 @@
+/*!
+ * Clock Skew Tracking
+ *
+ * Every EMAPTA response envelope carries a server `timestamp`. Comparing it
+ * with the local clock tells us how far this machine has drifted, which
+ * matters because clock-out deadlines are enforced by the server:
+ * - Offset samples are taken at the midpoint of each request round-trip
+ * - The reported offset is the median of the most recent samples
+ * - A warning is raised once when the offset crosses the threshold
+ */
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Number of recent samples kept for the median
+const MAX_SAMPLES: usize = 15;
+
+/// Offset (either direction) above which the user is warned
+pub const SKEW_WARNING_THRESHOLD_MS: i64 = 60_000;
+
+/// Measured offset between the EMAPTA server clock and the local clock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockSkewStatus {
+    /// Server time minus local time in milliseconds (positive: local clock is behind)
+    pub offset_ms: i64,
+    /// Number of samples the offset is based on
+    pub sample_count: usize,
+    /// When the last sample was taken (ISO 8601 UTC, local clock)
+    pub last_measured_at: String,
+    /// Whether the offset exceeds `SKEW_WARNING_THRESHOLD_MS`
+    pub exceeds_threshold: bool,
+}
+
+/// Rolling window of offset samples
+struct ClockSkewTracker {
+    samples: VecDeque<i64>,
+    last_measured_at: Option<DateTime<Utc>>,
+    warning_active: bool,
+}
+
+impl ClockSkewTracker {
+    const fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            last_measured_at: None,
+            warning_active: false,
+        }
+    }
+
+    fn add_sample(&mut self, offset_ms: i64, measured_at: DateTime<Utc>) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(offset_ms);
+        self.last_measured_at = Some(measured_at);
+    }
+
+    fn median_offset(&self) -> Option<i64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<i64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+
+    fn status(&self) -> Option<ClockSkewStatus> {
+        let offset_ms = self.median_offset()?;
+        Some(ClockSkewStatus {
+            offset_ms,
+            sample_count: self.samples.len(),
+            last_measured_at: self.last_measured_at.map(|dt| dt.to_rfc3339()).unwrap_or_default(),
+            exceeds_threshold: offset_ms.abs() > SKEW_WARNING_THRESHOLD_MS,
+        })
+    }
+}
+
+static TRACKER: Mutex<ClockSkewTracker> = Mutex::new(ClockSkewTracker::new());
+
+/// Parse an envelope timestamp. Values without an offset are treated as UTC.
+fn parse_server_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|naive| naive.and_utc())
+}
+
+/// Offset of the server clock relative to the midpoint of the round-trip
+fn compute_offset_ms(server_time: DateTime<Utc>, request_sent: DateTime<Utc>, response_received: DateTime<Utc>) -> i64 {
+    let midpoint = request_sent + (response_received - request_sent) / 2;
+    (server_time - midpoint).num_milliseconds()
+}
+
+/// Record the `timestamp` of an EMAPTA response sent at `request_sent`.
+///
+/// Logs and emits a warning the first time the median offset crosses the
+/// threshold; unparseable timestamps are ignored.
+pub async fn observe_server_timestamp(server_timestamp: &str, request_sent: DateTime<Utc>) {
+    let response_received = Utc::now();
+    let Some(server_time) = parse_server_timestamp(server_timestamp) else {
+        log::info!("Ignoring unparseable server timestamp: '{}'", server_timestamp);
+        return;
+    };
+
+    let offset_ms = compute_offset_ms(server_time, request_sent, response_received);
+
+    let newly_exceeded = {
+        let mut tracker = TRACKER.lock().unwrap();
+        tracker.add_sample(offset_ms, response_received);
+
+        let Some(status) = tracker.status() else {
+            return;
+        };
+        let crossed = status.exceeds_threshold && !tracker.warning_active;
+        tracker.warning_active = status.exceeds_threshold;
+        crossed.then_some(status)
+    };
+
+    if let Some(status) = newly_exceeded {
+        log::warn!(
+            "Local clock differs from EMAPTA server by {} seconds",
+            status.offset_ms / 1000
+        );
+
+        if let Some(logger) = crate::logging::get_logger() {
+            let _ = logger.log_clock_skew(status.offset_ms, status.sample_count).await;
+        }
+
+        for scheduler in crate::scheduler::all_schedulers() {
+            scheduler.notify_clock_skew(status.clone());
+        }
+    }
+}
+
+/// Current skew measurement, if any response has been observed yet
+pub fn current_status() -> Option<ClockSkewStatus> {
+    TRACKER.lock().unwrap().status()
+}
+
+/// Best estimate of the server's current time (local time when unmeasured)
+pub fn server_now() -> DateTime<Utc> {
+    let offset_ms = TRACKER.lock().unwrap().median_offset().unwrap_or(0);
+    Utc::now() + chrono::Duration::milliseconds(offset_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_uses_round_trip_midpoint() {
+        let sent = parse_server_timestamp("2025-10-09T01:00:00Z").unwrap();
+        let received = parse_server_timestamp("2025-10-09T01:00:02Z").unwrap();
+        let server = parse_server_timestamp("2025-10-09T01:05:01Z").unwrap();
+
+        assert_eq!(compute_offset_ms(server, sent, received), 300_000);
+    }
+
+    #[test]
+    fn test_median_ignores_outliers() {
+        let mut tracker = ClockSkewTracker::new();
+        for offset in [1_000, 1_200, 90_000, 900, 1_100] {
+            tracker.add_sample(offset, Utc::now());
+        }
+
+        let status = tracker.status().unwrap();
+        assert_eq!(status.offset_ms, 1_100);
+        assert!(!status.exceeds_threshold);
+    }
+
+    #[test]
+    fn test_parse_naive_timestamp_as_utc() {
+        let parsed = parse_server_timestamp("2025-10-09 01:00:00.250").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2025-10-09T01:00:00.250+00:00");
+    }
+}
@@ -1,18 +1,16 @@
-use crate::errors::AppError;
-use crate::storage::create_storage_backend;
-use crate::scheduler::{get_scheduler, WorkSchedule as SchedulerWorkSchedule, SchedulerState};
+use crate::accounts::AccountContext;
+use crate::errors::{AppError, EmaptaApiError};
+use crate::storage::get_storage;
+use crate::scheduler::{get_scheduler, BackendScheduler, WorkSchedule as SchedulerWorkSchedule, SchedulerState};
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager};
 use reqwest;
 use chrono;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+use crate::redact::{redact, token_fingerprint};
 
-// EMAPTA API constants
-const EMAPTA_TOKEN_ENDPOINT: &str = "https://api.platform.emapta.com/auth/v1/auth/protocol/openid-connect/token";
-const EMAPTA_LOGIN_ENDPOINT: &str = "https://api.platform.emapta.com/time-and-attendance/ta/v1/dtr/attendance/login";
-const EMAPTA_LOGOUT_ENDPOINT: &str = "https://api.platform.emapta.com/time-and-attendance/ta/v1/dtr/attendance/logout";
-const EMAPTA_ATTENDANCE_ENDPOINT: &str = "https://api.platform.emapta.com/time-and-attendance/ta/v1/dtr/attendance";
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct TokenResponse {
     pub access_token: String,
     pub refresh_token: String,
@@ -29,7 +27,7 @@ pub struct EmaptaApiResponse {
     pub result: TokenResponse,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttendanceItem {
     pub work_date: String,
     pub attendance_status: String,
@@ -51,7 +49,7 @@ pub struct AttendanceApiResponse {
     pub data: AttendanceData,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct TokenRequest {
     pub grant_type: String,
     pub client_id: String,
@@ -59,6 +57,13 @@ pub struct TokenRequest {
     pub scope: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct RevocationRequest {
+    pub client_id: String,
+    pub token: String,
+    pub token_type_hint: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StorageResult {
     pub success: bool,
@@ -81,129 +86,194 @@ fn validate_storage_key(key: &str) -> Result<(), StorageError> {
     Ok(())
 }
 
+/// Account a command targets (the default account when `account_id` is omitted)
+fn account_context(app_handle: &AppHandle, account_id: Option<String>) -> Result<AccountContext, String> {
+    AccountContext::resolve(app_handle.clone(), account_id).map_err(|e| e.to_string())
+}
+
+/// Scheduler of the account a command targets
+fn account_scheduler(account_id: Option<String>) -> Result<Arc<BackendScheduler>, String> {
+    let account_id = crate::accounts::resolve(account_id).map_err(|e| e.to_string())?;
+    get_scheduler(&account_id).ok_or_else(|| "Scheduler not initialized".to_string())
+}
+
 // ============================================================================
 // BACKEND API CLIENT FUNCTIONS
 // ============================================================================
 
+/// Turn a non-success response into a typed error
+async fn error_from_response(response: reqwest::Response) -> EmaptaApiError {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    EmaptaApiError::from_response(status, &body)
+}
+
+/// Feed the envelope `timestamp` of a response body (if any) to the skew tracker
+async fn observe_envelope_timestamp(response: reqwest::Response, sent_at: chrono::DateTime<chrono::Utc>) {
+    let Ok(body) = response.json::<serde_json::Value>().await else {
+        return;
+    };
+    if let Some(timestamp) = body.get("timestamp").and_then(|t| t.as_str()) {
+        crate::clock_skew::observe_server_timestamp(timestamp, sent_at).await;
+    }
+}
+
 /// Exchange refresh token for access token using EMAPTA API
-pub async fn exchange_refresh_token_api(refresh_token: &str) -> Result<TokenResponse, String> {
-    let client = reqwest::Client::new();
+pub async fn exchange_refresh_token_api(refresh_token: &str) -> Result<TokenResponse, EmaptaApiError> {
+    let client = crate::http_client::emapta_client();
+    let endpoints = crate::endpoints::active_profile();
     
     let request_body = TokenRequest {
         grant_type: "refresh_token".to_string(),
-        client_id: "EMAPTA-MYEMAPTAWEB".to_string(),
+        client_id: endpoints.auth_client_id.clone(),
         refresh_token: refresh_token.to_string(),
         scope: "openid".to_string(),
     };
 
+    let sent_at = chrono::Utc::now();
     let response = client
-        .post(EMAPTA_TOKEN_ENDPOINT)
+        .post(endpoints.token_url())
         .header("content-type", "application/json")
         .json(&request_body)
         .send()
         .await
-        .map_err(|e| format!("Token exchange request failed: {}", e))?;
+        .map_err(EmaptaApiError::from_reqwest)?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(format!("Token exchange failed: {} - {}", status, error_text));
+        return Err(error_from_response(response).await);
     }
 
-    // Debug: log the response text before trying to parse it
-    let response_text = response.text().await
-        .map_err(|e| format!("Failed to get response text: {}", e))?;
+    // Debug: log the response text (secrets masked) before trying to parse it
+    let response_text = Zeroizing::new(response.text().await
+        .map_err(EmaptaApiError::from_reqwest)?);
     
-    println!("EMAPTA API Response: {}", response_text);
+    log::debug!("EMAPTA API Response: {}", redact(&response_text));
     
     let api_response: EmaptaApiResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse API response: {} - Response was: {}", e, response_text))?;
+        .map_err(|e| EmaptaApiError::parse(format!("{} - Response was: {}", e, redact(&response_text))))?;
+
+    crate::clock_skew::observe_server_timestamp(&api_response.timestamp, sent_at).await;
 
     Ok(api_response.result)
 }
 
+/// Revoke a refresh token (and the Keycloak session behind it)
+pub async fn revoke_refresh_token_api(refresh_token: &str) -> Result<(), EmaptaApiError> {
+    let client = crate::http_client::emapta_client();
+    let endpoints = crate::endpoints::active_profile();
+
+    let request_body = RevocationRequest {
+        client_id: endpoints.auth_client_id.clone(),
+        token: refresh_token.to_string(),
+        token_type_hint: "refresh_token".to_string(),
+    };
+
+    // Revocation requests are form-encoded (RFC 7009 section 2.1), like the code exchange in `oidc.rs`
+    let response = client
+        .post(endpoints.revoke_url())
+        .form(&request_body)
+        .send()
+        .await
+        .map_err(EmaptaApiError::from_reqwest)?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    Ok(())
+}
+
 /// Perform clock in operation using EMAPTA API
-pub async fn clock_in_api(access_token: &str) -> Result<bool, String> {
-    println!("[API] Clock-in API called with token: {}", access_token);
-    let client = reqwest::Client::new();
+pub async fn clock_in_api(access_token: &str) -> Result<bool, EmaptaApiError> {
+    log::info!("Clock-in API called with token: {}", token_fingerprint(access_token));
+    let client = crate::http_client::emapta_client();
+    let endpoints = crate::endpoints::active_profile();
 
+    let sent_at = chrono::Utc::now();
     let response = client
-        .post(EMAPTA_LOGIN_ENDPOINT)
+        .post(endpoints.login_url())
         .header("application-type", "KEYCLOAK")
-        .header("client-code", "EMAPTA-MYEMAPTA")
-        .header("authorization", format!("Bearer {}", access_token))
+        .header("client-code", &endpoints.client_code)
+        .bearer_auth(access_token)
         .header("content-type", "application/json")
         .json(&serde_json::json!({}))
         .send()
         .await
-        .map_err(|e| format!("Clock in request failed: {}", e))?;
+        .map_err(EmaptaApiError::from_reqwest)?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        println!("[API] Clock-in failed with token: {}, status: {}, error: {}", access_token, status, error_text);
-        return Err(format!("Clock in failed: {} - {}", status, error_text));
+        let error = error_from_response(response).await;
+        log::warn!("Clock-in failed with token: {}, error: {}", token_fingerprint(access_token), error);
+        return Err(error);
     }
 
+    observe_envelope_timestamp(response, sent_at).await;
+
     Ok(true)
 }
 
 /// Perform clock out operation using EMAPTA API
-pub async fn clock_out_api(access_token: &str) -> Result<bool, String> {
-    println!("[API] Clock-out API called with token: {}", access_token);
-    let client = reqwest::Client::new();
+pub async fn clock_out_api(access_token: &str) -> Result<bool, EmaptaApiError> {
+    log::info!("Clock-out API called with token: {}", token_fingerprint(access_token));
+    let client = crate::http_client::emapta_client();
+    let endpoints = crate::endpoints::active_profile();
 
+    let sent_at = chrono::Utc::now();
     let response = client
-        .post(EMAPTA_LOGOUT_ENDPOINT)
+        .post(endpoints.logout_url())
         .header("application-type", "KEYCLOAK")
-        .header("client-code", "EMAPTA-MYEMAPTA")
-        .header("authorization", format!("Bearer {}", access_token))
+        .header("client-code", &endpoints.client_code)
+        .bearer_auth(access_token)
         .header("content-type", "application/json")
         .json(&serde_json::json!({}))
         .send()
         .await
-        .map_err(|e| format!("Clock out request failed: {}", e))?;
+        .map_err(EmaptaApiError::from_reqwest)?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        println!("[API] Clock-out failed with token: {}, status: {}, error: {}", access_token, status, error_text);
-        return Err(format!("Clock out failed: {} - {}", status, error_text));
+        let error = error_from_response(response).await;
+        log::warn!("Clock-out failed with token: {}, error: {}", token_fingerprint(access_token), error);
+        return Err(error);
     }
 
+    observe_envelope_timestamp(response, sent_at).await;
+
     Ok(true)
 }
 
 /// Fetch current attendance status from EMAPTA API
-pub async fn get_attendance_status_api(access_token: &str) -> Result<Option<AttendanceItem>, String> {
-    println!("[API] Attendance status API called with token: {}", access_token);
-    let client = reqwest::Client::new();
+pub async fn get_attendance_status_api(access_token: &str) -> Result<Option<AttendanceItem>, EmaptaApiError> {
+    log::info!("Attendance status API called with token: {}", token_fingerprint(access_token));
+    let client = crate::http_client::emapta_client();
+    let endpoints = crate::endpoints::active_profile();
 
     // Get today's date in local timezone
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
 
+    let sent_at = chrono::Utc::now();
     let response = client
-        .get(EMAPTA_ATTENDANCE_ENDPOINT)
+        .get(endpoints.attendance_url())
         .header("application-type", "KEYCLOAK")
-        .header("client-code", "EMAPTA-MYEMAPTA")
-        .header("authorization", format!("Bearer {}", access_token))
+        .header("client-code", &endpoints.client_code)
+        .bearer_auth(access_token)
         .header("content-type", "application/json")
         .query(&[("date_from", &today), ("date_to", &today)])
         .send()
         .await
-        .map_err(|e| format!("Attendance status request failed: {}", e))?;
+        .map_err(EmaptaApiError::from_reqwest)?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        println!("[API] Attendance status failed with token: {}, status: {}, error: {}", access_token, status, error_text);
-        return Err(format!("Attendance status failed: {} - {}", status, error_text));
+        let error = error_from_response(response).await;
+        log::warn!("Attendance status failed with token: {}, error: {}", token_fingerprint(access_token), error);
+        return Err(error);
     }
 
     let attendance_response: AttendanceApiResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse attendance response: {}", e))?;
+        .map_err(EmaptaApiError::from_reqwest)?;
+
+    crate::clock_skew::observe_server_timestamp(&attendance_response.timestamp, sent_at).await;
 
     // Find today's attendance record
     Ok(attendance_response.data.items.into_iter()
@@ -220,7 +290,7 @@ pub async fn store_encrypted_data(
     if encrypted_data.is_empty() {
         return Err("Encrypted data cannot be empty".to_string());
     }
-    let storage = create_storage_backend(app_handle).map_err(|e| format!("Failed to create storage backend: {}", e))?;
+    let storage = get_storage(&app_handle).map_err(|e| format!("Storage unavailable: {}", e))?;
     storage.store(&key, &encrypted_data).await.map_err(|e| format!("Storage operation failed: {}", e))
 }
 
@@ -229,11 +299,16 @@ pub async fn retrieve_encrypted_data(
     app_handle: AppHandle,
     key: String,
 ) -> Result<Option<String>, String> {
-    println!("[Storage] Frontend requesting retrieval for key: '{}'", key);
+    log::info!("Frontend requesting retrieval for key: '{}'", key);
     validate_storage_key(&key).map_err(|e| format!("Key validation failed: {}", e))?;
-    let storage = create_storage_backend(app_handle).map_err(|e| format!("Failed to create storage backend: {}", e))?;
-    let result = storage.retrieve(&key).await.map_err(|e| format!("Retrieval operation failed: {}", e))?;
-    println!("[Storage] Retrieved value for key '{}': {}", key, if result.is_some() { "found" } else { "not found" });
+    let storage = get_storage(&app_handle).map_err(|e| format!("Storage unavailable: {}", e))?;
+    // Backend tokens are stored as documents; hand out the token itself
+    let result = if crate::keyring_store::is_keyring_key(&key) {
+        crate::documents::load::<String>(&*storage, &key, &crate::documents::TOKEN).await
+    } else {
+        storage.retrieve(&key).await
+    }.map_err(|e| format!("Retrieval operation failed: {}", e))?;
+    log::info!("Retrieved value for key '{}': {}", key, if result.is_some() { "found" } else { "not found" });
     Ok(result)
 }
 
@@ -243,39 +318,202 @@ pub async fn delete_encrypted_data(
     key: String,
 ) -> Result<StorageResult, String> {
     validate_storage_key(&key).map_err(|e| format!("Key validation failed: {}", e))?;
-    let storage = create_storage_backend(app_handle).map_err(|e| format!("Failed to create storage backend: {}", e))?;
+    let storage = get_storage(&app_handle).map_err(|e| format!("Storage unavailable: {}", e))?;
     storage.delete(&key).await.map_err(|e| format!("Delete operation failed: {}", e))
 }
 
 #[tauri::command]
 pub async fn list_storage_keys(app_handle: AppHandle) -> Result<Vec<String>, String> {
-    let storage = create_storage_backend(app_handle).map_err(|e| format!("Failed to create storage backend: {}", e))?;
+    let storage = get_storage(&app_handle).map_err(|e| format!("Storage unavailable: {}", e))?;
     storage.list_keys().await.map_err(|e| format!("List operation failed: {}", e))
 }
 
-/// Set the user's schedule configuration as a JSON string
+/// Whether the storage is open, and why not (a passphrase-protected launch without its passphrase)
+#[tauri::command]
+pub async fn get_storage_status(app_handle: AppHandle) -> Result<crate::storage::StorageStatus, String> {
+    crate::storage::storage_status(&app_handle).map_err(|e| e.to_string())
+}
+
+/// Unlock passphrase-protected storage and finish the startup that waited for it
+#[tauri::command]
+pub async fn unlock_storage(app_handle: AppHandle, passphrase: String) -> Result<crate::storage::StorageStatus, String> {
+    let passphrase = Zeroizing::new(passphrase);
+    if crate::storage::unlock_storage(&app_handle, &passphrase).await.map_err(|e| e.to_string())? {
+        crate::initialize_from_storage(app_handle.clone()).await;
+
+        #[cfg(feature = "system-tray")]
+        crate::tray::schedule_status_refresh(&app_handle);
+    }
+    crate::storage::storage_status(&app_handle).map_err(|e| e.to_string())
+}
+
+/// Validate and save the account's schedule, and apply it to its scheduler if that is running.
+///
+/// Errors are returned as typed `AppError`s (e.g. `Validation` naming the field).
 #[tauri::command]
 pub async fn set_schedule(
     app_handle: AppHandle,
-    schedule_json: String,
-) -> Result<StorageResult, String> {
-    if schedule_json.is_empty() {
-        return Err("Schedule JSON cannot be empty".to_string());
-    }
-    
-    // Validate JSON format
-    serde_json::from_str::<serde_json::Value>(&schedule_json)
-        .map_err(|e| format!("Invalid JSON format: {}", e))?;
-    
-    let storage = create_storage_backend(app_handle).map_err(|e| format!("Failed to create storage backend: {}", e))?;
-    storage.store("user_schedule", &schedule_json).await.map_err(|e| format!("Failed to store schedule: {}", e))
+    schedule: SchedulerWorkSchedule,
+    account_id: Option<String>,
+) -> Result<SchedulerWorkSchedule, AppError> {
+    let account = AccountContext::resolve(app_handle, account_id)?;
+    let schedule = crate::scheduler::save_schedule(&account, schedule).await?;
+
+    let scheduler = get_scheduler(account.id())
+        .ok_or_else(|| AppError::schedule("Scheduler not initialized"))?;
+    scheduler.apply_schedule(schedule.clone()).await?;
+    Ok(schedule)
+}
+
+/// Get the account's saved schedule
+#[tauri::command]
+pub async fn get_schedule(app_handle: AppHandle, account_id: Option<String>) -> Result<Option<SchedulerWorkSchedule>, AppError> {
+    let account = AccountContext::resolve(app_handle, account_id)?;
+    crate::scheduler::load_schedule(&account).await
+}
+
+// ============================================================================
+// NETWORK CONFIGURATION COMMANDS
+// ============================================================================
+
+/// Get the HTTP client settings used for EMAPTA requests
+#[tauri::command]
+pub async fn get_http_client_config() -> Result<crate::http_client::HttpClientConfig, String> {
+    Ok(crate::http_client::current_config())
+}
+
+/// Update the HTTP client settings (timeouts, proxy, extra CA certificates)
+#[tauri::command]
+pub async fn set_http_client_config(
+    app_handle: AppHandle,
+    config: crate::http_client::HttpClientConfig,
+) -> Result<String, String> {
+    crate::http_client::update_config(&app_handle, config).await
+        .map_err(|e| format!("Failed to update HTTP client configuration: {}", e))?;
+    Ok("HTTP client configuration updated".to_string())
+}
+
+/// Get the endpoint profiles and which one is active
+#[tauri::command]
+pub async fn get_endpoint_config() -> Result<crate::endpoints::EndpointConfig, String> {
+    Ok(crate::endpoints::current_config())
+}
+
+/// Save the endpoint profiles (staging/custom base URLs and client IDs)
+#[tauri::command]
+pub async fn set_endpoint_config(
+    app_handle: AppHandle,
+    config: crate::endpoints::EndpointConfig,
+) -> Result<crate::endpoints::EndpointConfig, String> {
+    crate::endpoints::update_config(&app_handle, config).await
+        .map_err(|e| format!("Failed to update endpoint configuration: {}", e))
+}
+
+/// Switch the endpoint profile used for all EMAPTA requests
+#[tauri::command]
+pub async fn select_endpoint_profile(
+    app_handle: AppHandle,
+    profile: crate::endpoints::EndpointProfileKind,
+) -> Result<crate::endpoints::EndpointConfig, String> {
+    crate::endpoints::select_profile(&app_handle, profile).await
+        .map_err(|e| format!("Failed to select endpoint profile: {}", e))
+}
+
+/// Check that an endpoint profile (the active one by default) is reachable
+#[tauri::command]
+pub async fn check_endpoint_connectivity(
+    profile: Option<crate::endpoints::EndpointProfileKind>,
+) -> Result<crate::endpoints::ConnectivityReport, String> {
+    crate::endpoints::check_connectivity(profile).await
+        .map_err(|e| format!("Connectivity check failed: {}", e))
+}
+
+// ============================================================================
+// DIAGNOSTIC LOGGING COMMANDS
+// ============================================================================
+
+/// Get the diagnostic log levels and the active log file location
+#[tauri::command]
+pub async fn get_diagnostic_log_settings(app_handle: AppHandle) -> Result<crate::diagnostics::DiagnosticLogSettings, String> {
+    crate::diagnostics::current_settings(&app_handle).await
+        .map_err(|e| format!("Failed to get diagnostic log settings: {}", e))
+}
+
+/// Set the default diagnostic log level, or one module's level when `module` is given
+/// (`level: null` clears that module's override)
+#[tauri::command]
+pub async fn set_diagnostic_log_level(
+    app_handle: AppHandle,
+    level: Option<String>,
+    module: Option<String>,
+) -> Result<crate::diagnostics::DiagnosticLogSettings, String> {
+    crate::diagnostics::set_level(&app_handle, module, level).await
+        .map_err(|e| format!("Failed to set diagnostic log level: {}", e))
+}
+
+// ============================================================================
+// ACCOUNT COMMANDS
+// ============================================================================
+
+/// List the registered EMAPTA accounts
+#[tauri::command]
+pub async fn list_accounts() -> Result<Vec<crate::accounts::Account>, String> {
+    Ok(crate::accounts::list())
+}
+
+/// Register an account and create its scheduler (the ID is derived from the name when omitted)
+#[tauri::command]
+pub async fn add_account(
+    app_handle: AppHandle,
+    name: String,
+    account_id: Option<String>,
+) -> Result<crate::accounts::Account, String> {
+    let account = crate::accounts::add_account(&app_handle, &name, account_id).await
+        .map_err(|e| format!("Failed to add account: {}", e))?;
+    crate::scheduler::add_scheduler(
+        AccountContext::new(app_handle.clone(), account.id.clone()),
+        crate::provider::startup_provider(),
+    );
+
+    #[cfg(feature = "system-tray")]
+    crate::tray::schedule_status_refresh(&app_handle);
+
+    Ok(account)
+}
+
+/// Change an account's display name
+#[tauri::command]
+pub async fn rename_account(
+    app_handle: AppHandle,
+    account_id: String,
+    name: String,
+) -> Result<crate::accounts::Account, String> {
+    let account = crate::accounts::rename_account(&app_handle, &account_id, &name).await
+        .map_err(|e| format!("Failed to rename account: {}", e))?;
+
+    #[cfg(feature = "system-tray")]
+    crate::tray::schedule_status_refresh(&app_handle);
+
+    Ok(account)
 }
 
-/// Get the user's schedule configuration as a JSON string
+/// Remove an account: sign it out, drop its scheduler and delete everything it stored
 #[tauri::command]
-pub async fn get_schedule(app_handle: AppHandle) -> Result<Option<String>, String> {
-    let storage = create_storage_backend(app_handle).map_err(|e| format!("Failed to create storage backend: {}", e))?;
-    storage.retrieve("user_schedule").await.map_err(|e| format!("Failed to retrieve schedule: {}", e))
+pub async fn remove_account(app_handle: AppHandle, account_id: String) -> Result<String, String> {
+    if account_id == crate::accounts::DEFAULT_ACCOUNT_ID {
+        return Err("The default account cannot be removed".to_string());
+    }
+    let account = account_context(&app_handle, Some(account_id))?;
+
+    sign_out_account(&account).await?;
+    crate::scheduler::remove_scheduler(account.id());
+    let deleted = crate::accounts::remove_account(&app_handle, account.id()).await
+        .map_err(|e| format!("Failed to remove account: {}", e))?;
+
+    #[cfg(feature = "system-tray")]
+    crate::tray::schedule_status_refresh(&app_handle);
+
+    Ok(format!("Account removed ({} stored entries deleted)", deleted))
 }
 
 // ============================================================================
@@ -284,8 +522,9 @@ pub async fn get_schedule(app_handle: AppHandle) -> Result<Option<String>, Strin
 
 /// Start the backend scheduler with the given schedule
 #[tauri::command]
-pub async fn start_scheduler(schedule: SchedulerWorkSchedule) -> Result<String, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn start_scheduler(schedule: SchedulerWorkSchedule, account_id: Option<String>) -> Result<String, String> {
+    let scheduler = account_scheduler(account_id)?;
+    let schedule = schedule.validate().map_err(|e| e.to_string())?;
     
     scheduler.start_scheduler(schedule).await
         .map_err(|e| format!("Failed to start scheduler: {}", e))?;
@@ -295,8 +534,8 @@ pub async fn start_scheduler(schedule: SchedulerWorkSchedule) -> Result<String,
 
 /// Stop the backend scheduler
 #[tauri::command]
-pub async fn stop_scheduler() -> Result<String, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn stop_scheduler(account_id: Option<String>) -> Result<String, String> {
+    let scheduler = account_scheduler(account_id)?;
     
     scheduler.stop_scheduler().await
         .map_err(|e| format!("Failed to stop scheduler: {}", e))?;
@@ -306,11 +545,22 @@ pub async fn stop_scheduler() -> Result<String, String> {
 
 /// Get current scheduler state
 #[tauri::command]
-pub async fn get_scheduler_state() -> Result<SchedulerState, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn get_scheduler_state(account_id: Option<String>) -> Result<SchedulerState, String> {
+    let scheduler = account_scheduler(account_id)?;
     Ok(scheduler.get_state())
 }
 
+/// Get a page of finished scheduler operations (newest first)
+#[tauri::command]
+pub async fn get_operation_history(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    account_id: Option<String>,
+) -> Result<crate::operation_history::OperationHistoryPage, String> {
+    let scheduler = account_scheduler(account_id)?;
+    Ok(scheduler.get_operation_history(offset, limit))
+}
+
 /// Set access token for the scheduler (deprecated - tokens are now managed in storage)
 #[tauri::command]
 pub async fn set_scheduler_access_token(_access_token: Option<String>) -> Result<String, String> {
@@ -321,31 +571,31 @@ pub async fn set_scheduler_access_token(_access_token: Option<String>) -> Result
 
 /// Manual clock-in through backend scheduler
 #[tauri::command]
-pub async fn scheduler_manual_clock_in() -> Result<bool, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn scheduler_manual_clock_in(account_id: Option<String>) -> Result<bool, String> {
+    let scheduler = account_scheduler(account_id)?;
     scheduler.manual_clock_in().await
         .map_err(|e| format!("Manual clock-in failed: {}", e))
 }
 
 /// Manual clock-out through backend scheduler
 #[tauri::command]
-pub async fn scheduler_manual_clock_out(bypass_minimum: Option<bool>) -> Result<bool, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn scheduler_manual_clock_out(bypass_minimum: Option<bool>, account_id: Option<String>) -> Result<bool, String> {
+    let scheduler = account_scheduler(account_id)?;
     scheduler.manual_clock_out(bypass_minimum.unwrap_or(false)).await
         .map_err(|e| format!("Manual clock-out failed: {}", e))
 }
 
 /// Check if user can clock out (minimum duration check)
 #[tauri::command]
-pub async fn scheduler_can_clock_out() -> Result<bool, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn scheduler_can_clock_out(account_id: Option<String>) -> Result<bool, String> {
+    let scheduler = account_scheduler(account_id)?;
     Ok(scheduler.can_clock_out())
 }
 
 /// Check and perform auto clock-in on app startup
 #[tauri::command]
-pub async fn scheduler_check_auto_startup() -> Result<bool, String> {
-    let scheduler = get_scheduler().ok_or("Scheduler not initialized")?;
+pub async fn scheduler_check_auto_startup(account_id: Option<String>) -> Result<bool, String> {
+    let scheduler = account_scheduler(account_id)?;
     scheduler.check_auto_startup().await
         .map_err(|e| format!("Auto startup check failed: {}", e))
 }
@@ -359,33 +609,60 @@ pub async fn scheduler_check_auto_startup() -> Result<bool, String> {
 pub async fn api_exchange_refresh_token(
     app_handle: AppHandle,
     refresh_token: String,
+    account_id: Option<String>,
+    confirm_identity_change: Option<bool>,
 ) -> Result<TokenResponse, String> {
+    let refresh_token = Zeroizing::new(refresh_token);
+    let account = account_context(&app_handle, account_id)?;
+
     // Exchange token via backend API
-    let token_response = exchange_refresh_token_api(&refresh_token).await
+    let token_response = crate::provider::account_provider(account.id()).exchange_refresh_token(&refresh_token).await
         .map_err(|e| format!("Token exchange failed: {}", e))?;
 
     // Save both tokens using shared token manager
     crate::token_manager::save_initial_tokens(
-        &app_handle,
+        &account,
         &token_response.refresh_token,
         &token_response.access_token,
+        confirm_identity_change.unwrap_or(false),
     ).await
         .map_err(|e| format!("Failed to save tokens: {}", e))?;
 
     Ok(token_response)
 }
 
+/// Report access and refresh token lifetimes
+#[tauri::command]
+pub async fn token_status(app_handle: AppHandle, account_id: Option<String>) -> Result<crate::token_manager::TokenStatus, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::token_manager::get_token_status(&account).await
+        .map_err(|e| format!("Failed to read token status: {}", e))
+}
+
+/// Report who the stored access token belongs to (decoded locally, no network call)
+#[tauri::command]
+pub async fn token_identity(
+    app_handle: AppHandle,
+    account_id: Option<String>,
+) -> Result<Option<crate::token_manager::TokenIdentity>, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::token_manager::get_token_identity(&account).await
+        .map_err(|e| format!("Failed to read token identity: {}", e))
+}
+
 /// Manual clock in operation using shared token logic
 #[tauri::command]
-pub async fn api_manual_clock_in(app_handle: AppHandle) -> Result<bool, String> {
-    crate::token_manager::clock_in_with_shared_tokens(&app_handle).await
+pub async fn api_manual_clock_in(app_handle: AppHandle, account_id: Option<String>) -> Result<bool, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::token_manager::clock_in_with_shared_tokens(&account, crate::provider::account_provider(account.id()).as_ref()).await
         .map_err(|e| format!("Manual clock-in failed: {}", e))
 }
 
 /// Manual clock out operation using shared token logic
 #[tauri::command]
-pub async fn api_manual_clock_out(app_handle: AppHandle) -> Result<bool, String> {
-    crate::token_manager::clock_out_with_shared_tokens(&app_handle).await
+pub async fn api_manual_clock_out(app_handle: AppHandle, account_id: Option<String>) -> Result<bool, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::token_manager::clock_out_with_shared_tokens(&account, crate::provider::account_provider(account.id()).as_ref()).await
         .map_err(|e| format!("Manual clock-out failed: {}", e))
 }
 
@@ -401,72 +678,66 @@ pub async fn initialize_background_monitoring(app_handle: AppHandle) -> Result<S
 }
 
 /// Shared implementation for background monitoring initialization
-async fn initialize_background_monitoring_impl(app_handle: AppHandle) -> Result<String, String> {
-    println!("[Background] Initializing background monitoring and sleep/wake detection...");
-
-    // Clone app_handle for use in the spawned task
-    let app_handle_clone = app_handle.clone();
+async fn initialize_background_monitoring_impl(_app_handle: AppHandle) -> Result<String, String> {
+    log::info!("Initializing background monitoring and sleep/wake detection...");
 
     // Perform initial auto-startup check
     tokio::spawn(async move {
-        // Wait for scheduler to be initialized with retries
+        // Wait for schedulers to be initialized with retries
         let mut retry_count = 0;
         let max_retries = 10; // Up to 5 seconds with 500ms intervals
 
         while retry_count < max_retries {
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-            if get_scheduler().is_some() {
-                println!("[Background] Scheduler initialized, proceeding with auto-startup check");
+            if !crate::scheduler::all_schedulers().is_empty() {
+                log::info!("Scheduler initialized, proceeding with auto-startup check");
                 break;
             }
 
             retry_count += 1;
-            println!("[Background] Waiting for scheduler initialization... ({}/{})", retry_count, max_retries);
+            log::info!("Waiting for scheduler initialization... ({}/{})", retry_count, max_retries);
         }
 
         if retry_count >= max_retries {
-            println!("[Background] WARNING: Scheduler not initialized after {} attempts, proceeding anyway", max_retries);
+            log::warn!("Scheduler not initialized after {} attempts, proceeding anyway", max_retries);
         }
 
-        println!("Running initial auto-startup check...");
-        
-        // Check if we have valid tokens before attempting auto-startup
-        match crate::token_manager::get_saved_access_token(&app_handle_clone).await {
-            Ok(_) => {
-                println!("[Background] Access token found, proceeding with auto-startup check");
-
-                // Get the scheduler instance
-                if let Some(scheduler) = get_scheduler() {
-                    // Run initial auto-startup check
-                    match scheduler.check_auto_startup().await {
-                        Ok(clocked_in) => {
-                            if clocked_in {
-                                println!("Initial auto clock-in completed successfully");
-                            } else {
-                                println!("Initial auto clock-in skipped (already clocked in or conditions not met)");
-                            }
-                        }
-                        Err(e) => {
-                            println!("Initial auto clock-in failed: {:?}", e);
+        log::info!("Running initial auto-startup check...");
+        for scheduler in crate::scheduler::all_schedulers() {
+            let account = scheduler.account();
+            if crate::token_manager::is_signed_out(account.id()) {
+                continue;
+            }
 
-                            // Log the error for debugging
-                            if let Some(logger) = crate::logging::get_logger() {
-                                let _ = logger.log_clock_in(false, "startup_auto", None, Some(&format!("Auto clock-in startup failed: {}", e))).await;
-                            }
-                        }
+            // Check if we have valid tokens before attempting auto-startup
+            if let Err(e) = crate::token_manager::get_saved_access_token(account).await {
+                log::info!("No access token found for account '{}', skipping auto-startup: {}", account.id(), e);
+                continue;
+            }
+
+            log::info!("Access token found for account '{}', proceeding with auto-startup check", account.id());
+            match scheduler.check_auto_startup().await {
+                Ok(clocked_in) => {
+                    if clocked_in {
+                        log::info!("Initial auto clock-in completed successfully ({})", account.id());
+                    } else {
+                        log::info!("Initial auto clock-in skipped for '{}' (already clocked in or conditions not met)", account.id());
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Initial auto clock-in failed for '{}': {:?}", account.id(), e);
+
+                    // Log the error for debugging
+                    if let Some(logger) = account.logger() {
+                        let _ = logger.log_clock_in(false, "startup_auto", None, Some(&format!("Auto clock-in startup failed: {}", e))).await;
                     }
-                } else {
-                    println!("Error: Could not get scheduler instance for auto-startup check");
                 }
-            }
-            Err(e) => {
-                println!("[Background] No access token found, skipping auto-startup: {}", e);
             }
         }
         
         // Set up gap detection for sleep/wake monitoring
-        println!("Starting sleep/wake gap detection monitoring...");
+        log::info!("Starting sleep/wake gap detection monitoring...");
         let mut last_check = std::time::SystemTime::now();
         
         loop {
@@ -480,7 +751,7 @@ async fn initialize_background_monitoring_impl(app_handle: AppHandle) -> Result<
             if seconds_since_last <= 90 {
                 // Normal operation - log less frequently to avoid spam
                 if seconds_since_last % 300 == 0 { // Every 5 minutes during normal operation
-                    println!("[Background] Sleep/wake monitoring active - running normally");
+                    log::info!("Sleep/wake monitoring active - running normally");
                 }
             }
 
@@ -494,45 +765,47 @@ async fn initialize_background_monitoring_impl(app_handle: AppHandle) -> Result<
 
                 if duration_since_last.as_secs() > gap_threshold {
                     let gap_seconds = duration_since_last.as_secs();
-                    println!("Detected potential system wake (gap of {} seconds), checking auto clock-in...", gap_seconds);
+                    log::info!("Detected potential system wake (gap of {} seconds), checking auto clock-in...", gap_seconds);
 
                     // Log wake detection event
                     if let Some(logger) = crate::logging::get_logger() {
                         let _ = logger.log_wake_detected(gap_seconds).await;
                     }
 
-                    // Check if we still have valid tokens before attempting wake clock-in
-                    match crate::token_manager::get_saved_access_token(&app_handle_clone).await {
-                        Ok(_) => {
-                            if let Some(scheduler) = get_scheduler() {
-                                match scheduler.check_auto_startup().await {
-                                    Ok(clocked_in) => {
-                                        if clocked_in {
-                                            println!("Post-wake auto clock-in completed successfully");
-
-                                            // Log successful wake clock-in
-                                            if let Some(logger) = crate::logging::get_logger() {
-                                                let _ = logger.log_clock_in(true, "wake_auto", None, None).await;
-                                            }
-                                        } else {
-                                            println!("Post-wake auto clock-in skipped (conditions not met)");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        println!("Post-wake auto clock-in check failed: {:?}", e);
+                    for scheduler in crate::scheduler::all_schedulers() {
+                        let account = scheduler.account();
+                        if crate::token_manager::is_signed_out(account.id()) {
+                            log::info!("Account '{}' is signed out, skipping post-wake clock-in", account.id());
+                            continue;
+                        }
+
+                        // Check if we still have valid tokens before attempting wake clock-in
+                        if let Err(e) = crate::token_manager::get_saved_access_token(account).await {
+                            log::info!("No access token found for post-wake clock-in of '{}': {}", account.id(), e);
+                            continue;
+                        }
+
+                        match scheduler.check_auto_startup().await {
+                            Ok(clocked_in) => {
+                                if clocked_in {
+                                    log::info!("Post-wake auto clock-in completed successfully ({})", account.id());
 
-                                        // Log the error
-                                        if let Some(logger) = crate::logging::get_logger() {
-                                            let _ = logger.log_clock_in(false, "wake_auto", None, Some(&format!("Post-wake auto clock-in failed: {}", e))).await;
-                                        }
+                                    // Log successful wake clock-in
+                                    if let Some(logger) = account.logger() {
+                                        let _ = logger.log_clock_in(true, "wake_auto", None, None).await;
                                     }
+                                } else {
+                                    log::info!("Post-wake auto clock-in skipped for '{}' (conditions not met)", account.id());
+                                }
+                            }
+                            Err(e) => {
+                                log::warn!("Post-wake auto clock-in check failed for '{}': {:?}", account.id(), e);
+
+                                // Log the error
+                                if let Some(logger) = account.logger() {
+                                    let _ = logger.log_clock_in(false, "wake_auto", None, Some(&format!("Post-wake auto clock-in failed: {}", e))).await;
                                 }
-                            } else {
-                                println!("Error: Could not get scheduler instance for post-wake check");
                             }
-                        }
-                        Err(e) => {
-                            println!("[Background] No access token found for post-wake clock-in: {}", e);
                         }
                     }
                 }
@@ -547,8 +820,9 @@ async fn initialize_background_monitoring_impl(app_handle: AppHandle) -> Result<
 
 /// Get current attendance status using shared token logic
 #[tauri::command]
-pub async fn api_get_attendance_status(app_handle: AppHandle) -> Result<Option<AttendanceItem>, String> {
-    crate::token_manager::attendance_check_with_shared_tokens(&app_handle).await
+pub async fn api_get_attendance_status(app_handle: AppHandle, account_id: Option<String>) -> Result<Option<AttendanceItem>, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::token_manager::attendance_check_with_shared_tokens(&account, crate::provider::account_provider(account.id()).as_ref()).await
         .map_err(|e| format!("Attendance status check failed: {}", e))
 }
 
@@ -558,7 +832,13 @@ pub async fn api_setup_dual_tokens(
     app_handle: AppHandle,
     refresh_token: String,
     access_token: String,
+    account_id: Option<String>,
+    confirm_identity_change: Option<bool>,
 ) -> Result<String, String> {
+    let refresh_token = Zeroizing::new(refresh_token);
+    let access_token = Zeroizing::new(access_token);
+    let account = account_context(&app_handle, account_id)?;
+
     // Validate token format
     if refresh_token.trim().is_empty() {
         return Err("Refresh token cannot be empty".to_string());
@@ -579,14 +859,15 @@ pub async fn api_setup_dual_tokens(
 
     // Save both tokens using the shared token manager
     crate::token_manager::save_initial_tokens(
-        &app_handle,
+        &account,
         &refresh_token,
         &access_token,
+        confirm_identity_change.unwrap_or(false),
     ).await
         .map_err(|e| format!("Failed to save tokens: {}", e))?;
 
     // Validate tokens by trying an API call
-    match crate::token_manager::attendance_check_with_shared_tokens(&app_handle).await {
+    match crate::token_manager::attendance_check_with_shared_tokens(&account, crate::provider::account_provider(account.id()).as_ref()).await {
         Ok(_) => {
             // Tokens are valid - setup complete
             Ok("Tokens validated and saved successfully! Setup complete.".to_string())
@@ -599,6 +880,67 @@ pub async fn api_setup_dual_tokens(
     }
 }
 
+/// Sign out of an account: stop its scheduler, revoke the refresh token and wipe both tokens
+#[tauri::command]
+pub async fn api_logout(app_handle: AppHandle, account_id: Option<String>) -> Result<crate::token_manager::LogoutReport, String> {
+    let account = account_context(&app_handle, account_id)?;
+    sign_out_account(&account).await
+}
+
+/// Stop an account's scheduler, then revoke and wipe its tokens
+async fn sign_out_account(account: &AccountContext) -> Result<crate::token_manager::LogoutReport, String> {
+    // Stop automation first so no scheduled operation reaches for the tokens mid-logout
+    if let Some(scheduler) = get_scheduler(account.id()) {
+        scheduler.stop_scheduler().await
+            .map_err(|e| format!("Failed to stop scheduler: {}", e))?;
+    }
+
+    crate::token_manager::logout(account, crate::provider::account_provider(account.id()).as_ref()).await
+        .map_err(|e| format!("Logout failed: {}", e))
+}
+
+/// Sign in through the system browser (OIDC authorization code + PKCE) and save the tokens.
+///
+/// Tokens of a different user fail with the identity change error and are kept
+/// pending: answer with `api_confirm_browser_login` or `api_discard_browser_login`.
+#[tauri::command]
+pub async fn api_login_with_browser(app_handle: AppHandle, account_id: Option<String>) -> Result<String, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::oidc::login(&account).await
+        .map_err(|e| format!("Sign-in failed: {}", e))?;
+    verify_browser_login(&account).await
+}
+
+/// Replace the signed-in user with the pending browser sign-in
+#[tauri::command]
+pub async fn api_confirm_browser_login(app_handle: AppHandle, account_id: Option<String>) -> Result<String, String> {
+    let account = account_context(&app_handle, account_id)?;
+    crate::oidc::confirm_pending_login(&account).await
+        .map_err(|e| format!("Sign-in failed: {}", e))?;
+    verify_browser_login(&account).await
+}
+
+/// Drop the pending browser sign-in and revoke its tokens
+#[tauri::command]
+pub async fn api_discard_browser_login(app_handle: AppHandle, account_id: Option<String>) -> Result<String, String> {
+    let account = account_context(&app_handle, account_id)?;
+    let revoked = crate::oidc::discard_pending_login(&account, crate::provider::account_provider(account.id()).as_ref()).await
+        .map_err(|e| e.to_string())?;
+    Ok(if revoked {
+        "Sign-in discarded".to_string()
+    } else {
+        "Sign-in discarded, but its session could not be revoked on the server".to_string()
+    })
+}
+
+/// Confirm the tokens of a browser sign-in work, as manual setup does
+async fn verify_browser_login(account: &AccountContext) -> Result<String, String> {
+    crate::token_manager::attendance_check_with_shared_tokens(account, crate::provider::account_provider(account.id()).as_ref()).await
+        .map_err(|e| format!("Signed in, but the EMAPTA API rejected the new tokens: {}", e))?;
+
+    Ok("Signed in successfully! Setup complete.".to_string())
+}
+
 // ============================================================================
 // AUTOSTART COMMANDS (Phase 3 Enhancement)
 // ============================================================================
@@ -636,14 +978,92 @@ pub async fn is_autostart_enabled(app_handle: AppHandle) -> Result<bool, String>
     }
 }
 
+// ============================================================================
+// SETTINGS BACKUP COMMANDS
+// ============================================================================
+
+/// Export settings, schedules and optionally activity logs to a bundle file
+///
+/// Tokens are included only when a `passphrase` is given. Without a `path`
+/// the user picks the file; `None` means the dialog was cancelled.
+#[tauri::command]
+pub async fn export_settings(
+    app_handle: AppHandle,
+    path: Option<String>,
+    include_logs: Option<bool>,
+    passphrase: Option<String>,
+) -> Result<Option<crate::settings_bundle::SettingsExportReport>, String> {
+    let passphrase = passphrase.map(zeroize::Zeroizing::new);
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => match crate::settings_bundle::pick_path(&app_handle, true).await.map_err(|e| e.to_string())? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    crate::settings_bundle::export(&app_handle, &path, include_logs.unwrap_or(false), passphrase.as_deref().map(String::as_str)).await
+        .map(Some)
+        .map_err(|e| format!("Failed to export settings: {}", e))
+}
+
+/// Validate and apply a settings bundle; tokens are restored only with the export passphrase
+///
+/// Without a `path` the user picks the file; `None` means the dialog was cancelled.
+#[tauri::command]
+pub async fn import_settings(
+    app_handle: AppHandle,
+    path: Option<String>,
+    passphrase: Option<String>,
+) -> Result<Option<crate::settings_bundle::SettingsImportReport>, String> {
+    let passphrase = passphrase.map(zeroize::Zeroizing::new);
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => match crate::settings_bundle::pick_path(&app_handle, false).await.map_err(|e| e.to_string())? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    let report = crate::settings_bundle::import(&app_handle, &path, passphrase.as_deref().map(String::as_str)).await
+        .map_err(|e| format!("Failed to import settings: {}", e))?;
+
+    #[cfg(feature = "system-tray")]
+    crate::tray::schedule_status_refresh(&app_handle);
+
+    Ok(Some(report))
+}
+
+// ============================================================================
+// SHUTDOWN COMMANDS
+// ============================================================================
+
+/// Quit the application through the graceful shutdown workflow
+///
+/// With a `choice` the active session is handled without prompting;
+/// otherwise the user is asked when a session is active.
+#[tauri::command]
+pub async fn quit_application(
+    app_handle: AppHandle,
+    choice: Option<crate::shutdown::ShutdownChoice>,
+) -> Result<(), String> {
+    match choice {
+        Some(choice) => crate::shutdown::perform_shutdown(app_handle, Some(choice)).await,
+        None => crate::shutdown::request_shutdown(&app_handle),
+    }
+    Ok(())
+}
+
 // ============================================================================
 // ACTIVITY LOGGING COMMANDS (Phase 4 Feature)
 // ============================================================================
 
 /// Get recent activity log entries
 #[tauri::command]
-pub async fn get_activity_logs(limit: Option<usize>) -> Result<Vec<crate::logging::LogEntry>, String> {
-    let logger = crate::logging::get_logger().ok_or("Activity logger not initialized")?;
+pub async fn get_activity_logs(
+    app_handle: AppHandle,
+    limit: Option<usize>,
+    account_id: Option<String>,
+) -> Result<Vec<crate::logging::LogEntry>, String> {
+    let logger = account_context(&app_handle, account_id)?.logger().ok_or("Activity logger not initialized")?;
     logger.get_recent_entries(limit).await
         .map_err(|e| format!("Failed to get activity logs: {}", e))
 }
@@ -651,11 +1071,13 @@ pub async fn get_activity_logs(limit: Option<usize>) -> Result<Vec<crate::loggin
 /// Get filtered activity log entries
 #[tauri::command]
 pub async fn get_filtered_activity_logs(
+    app_handle: AppHandle,
     action_filter: Option<String>,
     status_filter: Option<String>,
-    limit: Option<usize>
+    limit: Option<usize>,
+    account_id: Option<String>,
 ) -> Result<Vec<crate::logging::LogEntry>, String> {
-    let logger = crate::logging::get_logger().ok_or("Activity logger not initialized")?;
+    let logger = account_context(&app_handle, account_id)?.logger().ok_or("Activity logger not initialized")?;
 
     // Parse filter strings to enums
     let action = match action_filter.as_deref() {
@@ -666,6 +1088,9 @@ pub async fn get_filtered_activity_logs(
         Some("wake_detected") => Some(crate::logging::LogAction::WakeDetected),
         Some("schedule_updated") => Some(crate::logging::LogAction::ScheduleUpdated),
         Some("app_startup") => Some(crate::logging::LogAction::AppStartup),
+        Some("app_shutdown") => Some(crate::logging::LogAction::AppShutdown),
+        Some("clock_skew") => Some(crate::logging::LogAction::ClockSkew),
+        Some("logout") => Some(crate::logging::LogAction::Logout),
         Some("error") => Some(crate::logging::LogAction::Error),
         _ => None,
     };
@@ -682,25 +1107,37 @@ pub async fn get_filtered_activity_logs(
         .map_err(|e| format!("Failed to get filtered activity logs: {}", e))
 }
 
-/// Clear all activity logs
+/// Clear all of an account's activity logs
 #[tauri::command]
-pub async fn clear_activity_logs() -> Result<u32, String> {
-    let logger = crate::logging::get_logger().ok_or("Activity logger not initialized")?;
+pub async fn clear_activity_logs(app_handle: AppHandle, account_id: Option<String>) -> Result<u32, String> {
+    let logger = account_context(&app_handle, account_id)?.logger().ok_or("Activity logger not initialized")?;
     logger.clear_all_logs().await
         .map_err(|e| format!("Failed to clear activity logs: {}", e))
 }
 
+/// Query activity log entries by action, status, date range and text, one page at a time
+#[tauri::command]
+pub async fn query_activity_logs(
+    app_handle: AppHandle,
+    query: crate::log_store::LogQuery,
+    account_id: Option<String>,
+) -> Result<crate::log_store::LogPage, String> {
+    let logger = account_context(&app_handle, account_id)?.logger().ok_or("Activity logger not initialized")?;
+    logger.query(&query).await
+        .map_err(|e| format!("Failed to query activity logs: {}", e))
+}
+
 /// Force re-initialize the logging system (for debugging Windows issues)
 #[tauri::command]
 pub async fn reinitialize_logger(app_handle: tauri::AppHandle) -> Result<String, String> {
-    println!("[DEBUG] Force re-initializing logger...");
+    log::debug!("Force re-initializing logger...");
 
     // Force re-initialization
     crate::logging::force_reinitialize_logger(app_handle.clone());
 
     // Check if it worked
     if crate::logging::get_logger().is_some() {
-        println!("[DEBUG] Logger re-initialized successfully");
+        log::debug!("Logger re-initialized successfully");
 
         // Try to create a test log entry
         if let Some(logger) = crate::logging::get_logger() {
@@ -764,18 +1201,18 @@ pub async fn debug_logging_status(app_handle: tauri::AppHandle) -> Result<serde_
     }
 
     // Try to get storage backend and list keys
-    let storage_keys = match crate::storage::create_storage_backend(app_handle.clone()) {
+    let storage_keys = match crate::storage::get_storage(&app_handle) {
         Ok(storage) => {
             match storage.list_keys().await {
                 Ok(keys) => Some(keys),
                 Err(e) => {
-                    println!("[DEBUG] Failed to list storage keys: {}", e);
+                    log::debug!("Failed to list storage keys: {}", e);
                     None
                 }
             }
         },
         Err(e) => {
-            println!("[DEBUG] Failed to create storage backend: {}", e);
+            log::debug!("Failed to create storage backend: {}", e);
             None
         }
     };
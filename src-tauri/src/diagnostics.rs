@@ -0,0 +1,355 @@
+/*!
+ * Diagnostic Logging
+ *
+ * Backend logger for the `log` facade, separate from the user-facing
+ * activity log in `logging.rs`:
+ * - Leveled records with per-module targets (`scheduler`, `token_manager`, ...)
+ * - A default level plus per-module overrides, adjustable at runtime
+ * - Console output and a diagnostic file in `<app data>/logs`, rotated by
+ *   size and age so it also works in release builds without a console
+ * - Every line passes through `redact` before it is written
+ */
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::documents;
+use crate::errors::AppError;
+use crate::redact::redact;
+use crate::storage::get_storage;
+
+/// Storage key for the persisted level settings
+const SETTINGS_STORAGE_KEY: &str = "diagnostic_log_settings";
+
+const LOG_DIR_NAME: &str = "logs";
+const LOG_FILE_STEM: &str = "diagnostic";
+/// Rotate once the active file reaches this size
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotate once the active file is older than this
+const MAX_FILE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Rotated files kept (oldest deleted first)
+const MAX_ROTATED_FILES: usize = 5;
+/// Rotated files older than this are deleted regardless of count
+const ROTATED_FILE_RETENTION: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Prefix of this crate's module paths, stripped to get short targets
+const CRATE_TARGET_PREFIX: &str = concat!(env!("CARGO_CRATE_NAME"), "::");
+
+/// Persisted level configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticLogSettings {
+    /// Level for targets without an override (`error`, `warn`, `info`, `debug`, `trace`, `off`)
+    pub level: String,
+    /// Per-module overrides keyed by short target (e.g. `scheduler`)
+    #[serde(default)]
+    pub module_levels: BTreeMap<String, String>,
+    /// Location of the active diagnostic file, once attached
+    #[serde(default, skip_deserializing)]
+    pub file_path: Option<String>,
+}
+
+impl Default for DiagnosticLogSettings {
+    fn default() -> Self {
+        Self {
+            level: default_level().to_string(),
+            module_levels: BTreeMap::new(),
+            file_path: None,
+        }
+    }
+}
+
+fn default_level() -> LevelFilter {
+    if cfg!(debug_assertions) { LevelFilter::Debug } else { LevelFilter::Info }
+}
+
+fn parse_level(field: &str, value: &str) -> Result<LevelFilter, AppError> {
+    LevelFilter::from_str(value.trim())
+        .map_err(|_| AppError::validation(field, format!("Unknown log level '{}'", value)))
+}
+
+/// Resolved level filters
+struct Levels {
+    default: LevelFilter,
+    modules: BTreeMap<String, LevelFilter>,
+}
+
+impl Levels {
+    /// Most specific override wins (`scheduler` also covers `scheduler::tasks`).
+    /// Other crates (reqwest, tauri, ...) are capped at `warn` unless overridden.
+    fn for_target(&self, target: &str, own_crate: bool) -> LevelFilter {
+        let fallback = if own_crate { self.default } else { self.default.min(LevelFilter::Warn) };
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module.as_str()
+                    || target.strip_prefix(module.as_str()).is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(fallback)
+    }
+
+    /// Most verbose level anywhere, used as the facade's global filter
+    fn max(&self) -> LevelFilter {
+        self.modules.values().copied().fold(self.default, Ord::max)
+    }
+}
+
+/// Size- and age-rotated log file
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    opened_at: SystemTime,
+}
+
+impl RotatingFile {
+    fn open(dir: PathBuf) -> std::io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let path = active_path(&dir);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let opened_at = metadata.created().or_else(|_| metadata.modified()).unwrap_or_else(|_| SystemTime::now());
+        Ok(Self { dir, file, size: metadata.len(), opened_at })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let age = SystemTime::now().duration_since(self.opened_at).unwrap_or_default();
+        if self.size > 0 && (self.size + line.len() as u64 > MAX_FILE_BYTES || age > MAX_FILE_AGE) {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let rotated = self.dir.join(format!("{}.{}.log", LOG_FILE_STEM, stamp));
+        fs::rename(active_path(&self.dir), rotated)?;
+        prune_rotated_files(&self.dir);
+
+        *self = Self::open(self.dir.clone())?;
+        Ok(())
+    }
+}
+
+fn active_path(dir: &Path) -> PathBuf {
+    dir.join(format!("{}.log", LOG_FILE_STEM))
+}
+
+/// Delete rotated files beyond the count or past the retention period
+fn prune_rotated_files(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let active = active_path(dir);
+    let mut rotated: Vec<(PathBuf, SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| *path != active)
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&format!("{}.", LOG_FILE_STEM)) && name.ends_with(".log"))
+        })
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect();
+
+    // Newest first
+    rotated.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    let now = SystemTime::now();
+    for (index, (path, modified)) in rotated.iter().enumerate() {
+        let expired = now.duration_since(*modified).unwrap_or_default() > ROTATED_FILE_RETENTION;
+        if index >= MAX_ROTATED_FILES || expired {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+struct DiagnosticLogger {
+    levels: RwLock<Option<Levels>>,
+    file: Mutex<Option<RotatingFile>>,
+}
+
+static LOGGER: DiagnosticLogger = DiagnosticLogger {
+    levels: RwLock::new(None),
+    file: Mutex::new(None),
+};
+
+/// Short target for display and level lookup (`black_bird_lib::scheduler` -> `scheduler`)
+fn short_target(target: &str) -> &str {
+    target.strip_prefix(CRATE_TARGET_PREFIX).unwrap_or(target)
+}
+
+impl Log for DiagnosticLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let own_crate = metadata.target().starts_with(CRATE_TARGET_PREFIX)
+            || metadata.target() == env!("CARGO_CRATE_NAME");
+        let level = match self.levels.read().unwrap().as_ref() {
+            Some(levels) => levels.for_target(short_target(metadata.target()), own_crate),
+            None => default_level(),
+        };
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} {}: {}\n",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+            record.level(),
+            short_target(record.target()),
+            redact(&record.args().to_string())
+        );
+
+        print!("{}", line);
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            // Nowhere left to report a failing log file; keep the console output
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Install the logger (console only until `attach_file`); call once at startup
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        apply_levels(Levels { default: default_level(), modules: BTreeMap::new() });
+    }
+}
+
+fn apply_levels(levels: Levels) {
+    log::set_max_level(levels.max());
+    *LOGGER.levels.write().unwrap() = Some(levels);
+}
+
+fn resolve_levels(settings: &DiagnosticLogSettings) -> Result<Levels, AppError> {
+    let default = parse_level("level", &settings.level)?;
+    let modules = settings.module_levels
+        .iter()
+        .map(|(module, level)| Ok((module.trim().to_string(), parse_level(module, level)?)))
+        .collect::<Result<_, AppError>>()?;
+    Ok(Levels { default, modules })
+}
+
+/// Start writing to the rotating file in the app data directory
+pub fn attach_file(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app_handle.path().app_data_dir()?.join(LOG_DIR_NAME);
+    let file = RotatingFile::open(dir.clone())?;
+    prune_rotated_files(&dir);
+    *LOGGER.file.lock().unwrap() = Some(file);
+    Ok(active_path(&dir))
+}
+
+/// Current settings, including the active file location
+pub async fn current_settings(app_handle: &AppHandle) -> Result<DiagnosticLogSettings, AppError> {
+    let mut settings = load_settings(app_handle).await?;
+    if LOGGER.file.lock().unwrap().is_some() {
+        settings.file_path = Some(active_path(&app_handle.path().app_data_dir()?.join(LOG_DIR_NAME)).display().to_string());
+    }
+    Ok(settings)
+}
+
+async fn load_settings(app_handle: &AppHandle) -> Result<DiagnosticLogSettings, AppError> {
+    let storage = get_storage(app_handle)?;
+    Ok(documents::load(&*storage, SETTINGS_STORAGE_KEY, &documents::DIAGNOSTIC_SETTINGS).await?.unwrap_or_default())
+}
+
+/// Check that every level in `settings` is known
+pub fn validate_settings(settings: &DiagnosticLogSettings) -> Result<(), AppError> {
+    resolve_levels(settings).map(|_| ())
+}
+
+/// Apply and persist complete settings (used by settings import)
+pub async fn replace_settings(app_handle: &AppHandle, settings: DiagnosticLogSettings) -> Result<(), AppError> {
+    apply_levels(resolve_levels(&settings)?);
+
+    let storage = get_storage(app_handle)?;
+    documents::save(&*storage, SETTINGS_STORAGE_KEY, &documents::DIAGNOSTIC_SETTINGS, &settings).await?;
+    Ok(())
+}
+
+/// Apply the persisted levels (used at startup)
+pub async fn load_levels(app_handle: &AppHandle) -> Result<(), AppError> {
+    let settings = load_settings(app_handle).await?;
+    apply_levels(resolve_levels(&settings)?);
+    Ok(())
+}
+
+/// Change the default level, or a module's level when `module` is given.
+///
+/// `level: None` removes the module override (the default level cannot be removed).
+pub async fn set_level(app_handle: &AppHandle, module: Option<String>, level: Option<String>) -> Result<DiagnosticLogSettings, AppError> {
+    let mut settings = load_settings(app_handle).await?;
+    match (module.map(|m| m.trim().to_string()).filter(|m| !m.is_empty()), level) {
+        (None, Some(level)) => settings.level = level,
+        (None, None) => return Err(AppError::validation("level", "A default level is required")),
+        (Some(module), Some(level)) => {
+            settings.module_levels.insert(module, level);
+        }
+        (Some(module), None) => {
+            settings.module_levels.remove(&module);
+        }
+    }
+
+    apply_levels(resolve_levels(&settings)?);
+
+    let storage = get_storage(app_handle)?;
+    documents::save(&*storage, SETTINGS_STORAGE_KEY, &documents::DIAGNOSTIC_SETTINGS, &settings).await?;
+    log::info!("Diagnostic log levels updated: default={}, modules={:?}", settings.level, settings.module_levels);
+
+    current_settings(app_handle).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_override_is_most_specific_match() {
+        let levels = Levels {
+            default: LevelFilter::Info,
+            modules: BTreeMap::from([
+                ("scheduler".to_string(), LevelFilter::Trace),
+                ("token_manager".to_string(), LevelFilter::Warn),
+            ]),
+        };
+
+        assert_eq!(levels.for_target("scheduler", true), LevelFilter::Trace);
+        assert_eq!(levels.for_target("scheduler::tasks", true), LevelFilter::Trace);
+        assert_eq!(levels.for_target("scheduler_extra", true), LevelFilter::Info);
+        assert_eq!(levels.for_target("token_manager", true), LevelFilter::Warn);
+        assert_eq!(levels.for_target("hyper::client", false), LevelFilter::Warn);
+        assert_eq!(levels.max(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn test_short_target_strips_crate_prefix() {
+        assert_eq!(short_target(&format!("{}scheduler", CRATE_TARGET_PREFIX)), "scheduler");
+        assert_eq!(short_target("reqwest::connect"), "reqwest::connect");
+    }
+}
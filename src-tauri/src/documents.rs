@@ -0,0 +1,252 @@
+/*!
+ * Persisted Documents
+ *
+ * Every document the backend keeps in storage (schedule, settings, history,
+ * tokens) is written inside a versioned envelope:
+ * `{"schemaVersion": 1, "kind": "schedule", "savedAt": "...", "data": ...}`.
+ *
+ * Each `DocumentKind` lists the migrations from one schema version to the
+ * next; version 0 is the unversioned format of earlier releases. Loading an
+ * older document runs the missing migrations, backs up the stored original
+ * under `<key>.v<version>.bak` and writes the upgraded envelope back (tokens
+ * are only upgraded in memory, see `TOKEN`).
+ * Documents from a newer release are refused rather than misread.
+ */
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::StorageResult;
+use crate::errors::AppError;
+use crate::storage::Storage;
+
+/// Upgrade of a document's data from one schema version to the next
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// A kind of persisted document and the migrations of its schema
+pub struct DocumentKind {
+    /// Name recorded in the envelope
+    pub name: &'static str,
+    /// `migrations[n]` upgrades version `n` to `n + 1`, so the current version is their count
+    migrations: &'static [Migration],
+    /// Whether version 0 is plain text rather than JSON
+    legacy_text: bool,
+    /// Whether the original is backed up before migrating
+    backup: bool,
+    /// Whether `load` writes the upgraded document back to storage
+    write_back: bool,
+}
+
+impl DocumentKind {
+    /// Schema version written by this build
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+}
+
+/// Version 0 to 1 of every kind: the data itself is unchanged, only the envelope is added
+fn add_envelope(data: Value) -> Result<Value, String> {
+    Ok(data)
+}
+
+pub const SCHEDULE: DocumentKind = DocumentKind { name: "schedule", migrations: &[add_envelope], legacy_text: false, backup: true, write_back: true };
+/// Whether the user left an account's scheduler running (`scheduler.rs`)
+pub const SCHEDULER_ENABLED: DocumentKind = DocumentKind { name: "scheduler_enabled", migrations: &[add_envelope], legacy_text: false, backup: false, write_back: true };
+/// Session an account's scheduler had open at shutdown (`scheduler.rs`)
+pub const SCHEDULER_STATE: DocumentKind = DocumentKind { name: "scheduler_state", migrations: &[add_envelope], legacy_text: false, backup: false, write_back: true };
+pub const OPERATION_HISTORY: DocumentKind = DocumentKind { name: "operation_history", migrations: &[add_envelope], legacy_text: false, backup: true, write_back: true };
+pub const ACCOUNTS: DocumentKind = DocumentKind { name: "accounts", migrations: &[add_envelope], legacy_text: false, backup: true, write_back: true };
+pub const HTTP_CONFIG: DocumentKind = DocumentKind { name: "http_client_config", migrations: &[add_envelope], legacy_text: false, backup: true, write_back: true };
+pub const ENDPOINT_CONFIG: DocumentKind = DocumentKind { name: "endpoint_config", migrations: &[add_envelope], legacy_text: false, backup: true, write_back: true };
+pub const DIAGNOSTIC_SETTINGS: DocumentKind = DocumentKind { name: "diagnostic_settings", migrations: &[add_envelope], legacy_text: false, backup: true, write_back: true };
+/// Settings export bundles (`settings_bundle.rs`); there is no unversioned format
+pub const SETTINGS_BUNDLE: DocumentKind = DocumentKind { name: "settings_bundle", migrations: &[unversioned_bundle], legacy_text: false, backup: false, write_back: true };
+/// Refresh and access tokens. Not backed up: a backup copy would sit outside
+/// the keyring, and the original is kept verbatim inside the envelope anyway.
+/// Not written back either: token keys are only written under the account's
+/// refresh lock (`token_manager`), so a legacy token is upgraded by the next
+/// refresh or sign-in rather than by whichever reader loads it first.
+pub const TOKEN: DocumentKind = DocumentKind { name: "token", migrations: &[add_envelope], legacy_text: true, backup: false, write_back: false };
+
+fn unversioned_bundle(_: Value) -> Result<Value, String> {
+    Err("not a Black Bird settings bundle".to_string())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    schema_version: u32,
+    kind: String,
+    saved_at: String,
+    data: Value,
+}
+
+/// A stored document upgraded to the current schema
+struct Decoded<T> {
+    value: T,
+    /// Current-version data, for writing the upgrade back
+    data: Value,
+    /// Schema version it was stored with
+    stored_version: u32,
+}
+
+/// Storage key of the backup taken before migrating `key` from `version`
+pub fn backup_key(key: &str, version: u32) -> String {
+    format!("{}.v{}.bak", key, version)
+}
+
+/// Serialize `value` as a current-version document of `kind`
+pub fn encode<T: Serialize>(kind: &DocumentKind, value: &T) -> Result<String, AppError> {
+    let data = serde_json::to_value(value)
+        .map_err(|e| AppError::storage(format!("Failed to serialize {} document: {}", kind.name, e)))?;
+    encode_data(kind, data)
+}
+
+fn encode_data(kind: &DocumentKind, data: Value) -> Result<String, AppError> {
+    let envelope = Envelope {
+        schema_version: kind.version(),
+        kind: kind.name.to_string(),
+        saved_at: chrono::Utc::now().to_rfc3339(),
+        data,
+    };
+    serde_json::to_string(&envelope)
+        .map_err(|e| AppError::storage(format!("Failed to serialize {} document: {}", kind.name, e)))
+}
+
+/// Parse the document stored under `key`, running any migrations it needs
+fn decode<T: DeserializeOwned>(kind: &DocumentKind, key: &str, stored: &str) -> Result<Decoded<T>, AppError> {
+    let envelope = match serde_json::from_str::<Value>(stored) {
+        Ok(value) if value.get("schemaVersion").is_some() => Some(serde_json::from_value::<Envelope>(value)
+            .map_err(|e| AppError::storage(format!("'{}' has a malformed document envelope: {}", key, e)))?),
+        _ => None,
+    };
+
+    let (stored_version, mut data) = match envelope {
+        Some(envelope) if envelope.kind != kind.name => {
+            return Err(AppError::storage(format!(
+                "'{}' holds a {} document, expected {}", key, envelope.kind, kind.name
+            )));
+        }
+        Some(envelope) => (envelope.schema_version, envelope.data),
+        None if kind.legacy_text => (0, Value::String(stored.to_string())),
+        None => (0, serde_json::from_str(stored)
+            .map_err(|e| AppError::storage(format!("'{}' is not valid JSON: {}", key, e)))?),
+    };
+
+    if stored_version > kind.version() {
+        return Err(AppError::storage(format!(
+            "'{}' was written with {} schema version {}, but this version only supports up to {}",
+            key, kind.name, stored_version, kind.version()
+        )));
+    }
+    for (version, migrate) in kind.migrations.iter().enumerate().skip(stored_version as usize) {
+        data = migrate(data).map_err(|e| AppError::storage(format!(
+            "Failed to migrate '{}' from {} schema version {} to {}: {}", key, kind.name, version, version + 1, e
+        )))?;
+    }
+
+    let value = serde_json::from_value(data.clone()).map_err(|e| AppError::storage(format!(
+        "'{}' does not match {} schema version {}: {}", key, kind.name, kind.version(), e
+    )))?;
+    Ok(Decoded { value, data, stored_version })
+}
+
+/// Parse a document of `kind` that does not live in storage (`name` is used in errors)
+pub fn parse<T: DeserializeOwned>(kind: &DocumentKind, name: &str, text: &str) -> Result<T, AppError> {
+    decode(kind, name, text).map(|decoded| decoded.value)
+}
+
+/// Load the document of `kind` stored under `key`.
+///
+/// A document stored with an older schema is backed up, migrated and saved
+/// in the current version before it is returned (unless its kind opts out of
+/// writing back).
+pub async fn load<T: DeserializeOwned>(storage: &dyn Storage, key: &str, kind: &DocumentKind) -> Result<Option<T>, AppError> {
+    let Some(stored) = storage.retrieve(key).await? else {
+        return Ok(None);
+    };
+
+    let decoded = decode::<T>(kind, key, &stored)?;
+    if decoded.stored_version < kind.version() && kind.write_back {
+        if kind.backup {
+            storage.store(&backup_key(key, decoded.stored_version), &stored).await.map_err(|e| AppError::storage(format!(
+                "Failed to back up '{}' before migrating it: {}", key, e
+            )))?;
+        }
+        storage.store(key, &encode_data(kind, decoded.data)?).await.map_err(|e| AppError::storage(format!(
+            "Failed to save migrated '{}': {}", key, e
+        )))?;
+        log::info!("Migrated '{}' from {} schema version {} to {}", key, kind.name, decoded.stored_version, kind.version());
+    }
+    Ok(Some(decoded.value))
+}
+
+/// Store `value` under `key` as a current-version document of `kind`
+pub async fn save<T: Serialize>(storage: &dyn Storage, key: &str, kind: &DocumentKind, value: &T) -> Result<StorageResult, AppError> {
+    storage.store(key, &encode(kind, value)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_storage::MemoryStorage;
+
+    /// Version 2 renamed `start` to `startTime`
+    fn rename_start(mut data: Value) -> Result<Value, String> {
+        let object = data.as_object_mut().ok_or("expected an object")?;
+        let start = object.remove("start").ok_or("missing 'start'")?;
+        object.insert("startTime".to_string(), start);
+        Ok(data)
+    }
+
+    const RENAMED: DocumentKind = DocumentKind { name: "test", migrations: &[add_envelope, rename_start], legacy_text: false, backup: true, write_back: true };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Shift {
+        start_time: String,
+    }
+
+    #[tokio::test]
+    async fn test_migrates_and_backs_up_legacy_documents() {
+        let storage = MemoryStorage::new();
+        storage.store("shift", r#"{"start":"09:00"}"#).await.unwrap();
+
+        let shift: Shift = load(&storage, "shift", &RENAMED).await.unwrap().unwrap();
+        assert_eq!(shift.start_time, "09:00");
+        assert_eq!(storage.retrieve("shift.v0.bak").await.unwrap().as_deref(), Some(r#"{"start":"09:00"}"#));
+
+        let stored: Value = serde_json::from_str(&storage.retrieve("shift").await.unwrap().unwrap()).unwrap();
+        assert_eq!(stored["schemaVersion"], 2);
+        assert_eq!(stored["data"]["startTime"], "09:00");
+        // Already current: loads as-is, no further backups
+        assert_eq!(load::<Shift>(&storage, "shift", &RENAMED).await.unwrap(), Some(shift));
+        assert_eq!(storage.list_keys().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_tokens() {
+        let storage = MemoryStorage::new();
+        storage.store("refresh_token", "eyJ.legacy.token").await.unwrap();
+
+        let token: String = load(&storage, "refresh_token", &TOKEN).await.unwrap().unwrap();
+        assert_eq!(token, "eyJ.legacy.token");
+        // Read-only: the next token write stores the envelope
+        assert_eq!(storage.list_keys().await.unwrap(), vec!["refresh_token"]);
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap().as_deref(), Some("eyJ.legacy.token"));
+    }
+
+    #[test]
+    fn test_rejects_unusable_documents() {
+        let newer = encode_data(&RENAMED, serde_json::json!({"startTime": "09:00"})).unwrap()
+            .replace("\"schemaVersion\":2", "\"schemaVersion\":3");
+        let error = decode::<Shift>(&RENAMED, "shift", &newer).err().unwrap().to_string();
+        assert!(error.contains("schema version 3"), "{}", error);
+
+        let other_kind = encode(&SCHEDULE, &serde_json::json!({})).unwrap();
+        assert!(decode::<Shift>(&RENAMED, "shift", &other_kind).is_err());
+        assert!(decode::<Shift>(&RENAMED, "shift", r#"{"begin":"09:00"}"#).is_err());
+        assert!(decode::<Shift>(&RENAMED, "shift", "not json").is_err());
+    }
+}
@@ -0,0 +1,350 @@
+/*!
+ * Storage Encryption
+ *
+ * Authenticated encryption (AES-256-GCM) for every value written by
+ * the file and SQLite storage backends:
+ * - The key lives in a per-install key file (`storage.key`, readable by the
+ *   owner only) created on first use, or is derived with Argon2id from the
+ *   passphrase in `BLACK_BIRD_PASSPHRASE` and a per-install salt
+ * - Every value gets a fresh random nonce, and the storage key it is stored
+ *   under is bound as associated data, so encrypted files cannot be swapped
+ *   between keys without failing authentication
+ * - Encrypted values are written as `bbenc1:` + base64(nonce || ciphertext);
+ *   anything without that prefix is legacy plaintext, which the storage
+ *   backend re-encrypts on first read
+ *
+ * Values written with the key file cannot be read with a passphrase and vice
+ * versa: switching modes means signing in again. The mode chosen on first
+ * use is recorded in `storage.mode`, so a launch that lacks the passphrase
+ * (tray, autostart) is refused as locked rather than falling back to a new
+ * key file; such storage is opened with `unlock` instead. Passphrase installs
+ * also keep a known value sealed with the key (`storage.check`), so a wrong
+ * passphrase is reported as such.
+ */
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::errors::AppError;
+
+/// Environment variable holding the optional storage passphrase
+const PASSPHRASE_ENV_VAR: &str = "BLACK_BIRD_PASSPHRASE";
+/// Random per-install key (base64), used when no passphrase is set
+const KEY_FILE: &str = "storage.key";
+/// Random per-install salt (base64) for passphrase key derivation
+const SALT_FILE: &str = "storage.salt";
+/// Which kind of key protects the storage (`key_file` or `passphrase`)
+const MODE_FILE: &str = "storage.mode";
+/// Known value sealed with the passphrase-derived key, to recognize a wrong passphrase
+const CHECK_FILE: &str = "storage.check";
+const CHECK_VALUE: &str = "black-bird-storage";
+
+/// Prefix marking an encrypted value (and its format version)
+const ENCRYPTED_PREFIX: &str = "bbenc1:";
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const SALT_LENGTH: usize = 16;
+
+/// Cipher of the storage directory in use (the key is loaded or derived once)
+static CIPHER: Mutex<Option<(PathBuf, Arc<StorageCipher>)>> = Mutex::new(None);
+
+/// How the storage key of an install is obtained
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyMode {
+    KeyFile,
+    Passphrase,
+}
+
+impl KeyMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::KeyFile => "key_file",
+            Self::Passphrase => "passphrase",
+        }
+    }
+}
+
+/// AES-256-GCM with the install's storage key
+pub struct StorageCipher {
+    key: Zeroizing<[u8; KEY_LENGTH]>,
+}
+
+impl StorageCipher {
+    /// Cipher for the storage directory `dir`, loading or creating its key material
+    pub fn for_dir(dir: &Path) -> Result<Arc<Self>, AppError> {
+        let mut cached = CIPHER.lock().unwrap();
+        if let Some((cached_dir, cipher)) = cached.as_ref() {
+            if cached_dir == dir {
+                return Ok(cipher.clone());
+            }
+        }
+
+        let passphrase = std::env::var(PASSPHRASE_ENV_VAR).ok()
+            .filter(|passphrase| !passphrase.is_empty())
+            .map(Zeroizing::new);
+        let key = match (key_mode(dir, passphrase.is_some())?, passphrase) {
+            (KeyMode::KeyFile, None) => {
+                let secret = load_or_create_secret(&dir.join(KEY_FILE), KEY_LENGTH)?;
+                let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+                key.copy_from_slice(&secret);
+                key
+            }
+            (KeyMode::KeyFile, Some(_)) => {
+                return Err(AppError::encryption(format!(
+                    "This storage is protected by its key file ({}), but {} is set; unset it to open the storage",
+                    KEY_FILE, PASSPHRASE_ENV_VAR
+                )));
+            }
+            (KeyMode::Passphrase, Some(passphrase)) => {
+                log::info!("Deriving storage key from passphrase");
+                passphrase_key(dir, &passphrase)?
+            }
+            (KeyMode::Passphrase, None) => {
+                return Err(AppError::encryption(format!(
+                    "Storage is locked: it is protected by a passphrase; unlock it in the app or set {}",
+                    PASSPHRASE_ENV_VAR
+                )));
+            }
+        };
+
+        let cipher = Arc::new(Self { key });
+        *cached = Some((dir.to_path_buf(), cipher.clone()));
+        Ok(cipher)
+    }
+
+    /// Unlock the passphrase-protected storage in `dir` for this process, as
+    /// `BLACK_BIRD_PASSPHRASE` would; `for_dir` then returns this cipher
+    pub fn unlock(dir: &Path, passphrase: &str) -> Result<(), AppError> {
+        if passphrase.is_empty() {
+            return Err(AppError::validation("passphrase", "The passphrase cannot be empty"));
+        }
+        if key_mode(dir, true)? != KeyMode::Passphrase {
+            return Err(AppError::encryption(format!(
+                "This storage is protected by its key file ({}), not a passphrase", KEY_FILE
+            )));
+        }
+
+        let cipher = Arc::new(Self { key: passphrase_key(dir, passphrase)? });
+        *CIPHER.lock().unwrap() = Some((dir.to_path_buf(), cipher));
+        log::info!("Storage unlocked with passphrase");
+        Ok(())
+    }
+
+    /// Cipher keyed by `passphrase` and `salt` alone, independent of the install (for exports)
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, AppError> {
+        Ok(Self { key: derive_key(passphrase, salt)? })
+    }
+
+    fn aead(&self) -> Aes256Gcm {
+        Aes256Gcm::new(self.key.as_ref().into())
+    }
+
+    /// Encrypt the value stored under `name`
+    pub fn encrypt(&self, name: &str, plaintext: &str) -> Result<String, AppError> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self.aead()
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext.as_bytes(), aad: name.as_bytes() })
+            .map_err(|_| AppError::encryption(format!("Failed to encrypt '{}'", name)))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(sealed)))
+    }
+
+    /// Decrypt the value stored under `name`
+    pub fn decrypt(&self, name: &str, stored: &str) -> Result<Zeroizing<String>, AppError> {
+        let encoded = stored.strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| AppError::encryption(format!("'{}' is not encrypted", name)))?;
+        let sealed = STANDARD.decode(encoded.trim())
+            .map_err(|e| AppError::encryption(format!("Corrupted value for '{}': {}", name, e)))?;
+        if sealed.len() < NONCE_LENGTH {
+            return Err(AppError::encryption(format!("Corrupted value for '{}': too short", name)));
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let plaintext = Zeroizing::new(self.aead()
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: name.as_bytes() })
+            .map_err(|_| AppError::encryption(format!(
+                "Failed to decrypt '{}': wrong passphrase or key file, or the file was modified", name
+            )))?);
+
+        String::from_utf8(plaintext.to_vec())
+            .map(Zeroizing::new)
+            .map_err(|_| AppError::encryption(format!("Decrypted value for '{}' is not UTF-8", name)))
+    }
+}
+
+/// Whether a stored value is in the encrypted format (otherwise it is legacy plaintext)
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Fresh random salt for `StorageCipher::from_passphrase`
+pub fn new_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Key mode recorded for the storage in `dir` (`None` before first use)
+pub fn recorded_key_mode(dir: &Path) -> Result<Option<KeyMode>, AppError> {
+    let path = dir.join(MODE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    match fs::read_to_string(&path)?.trim() {
+        "key_file" => Ok(Some(KeyMode::KeyFile)),
+        "passphrase" => Ok(Some(KeyMode::Passphrase)),
+        other => Err(AppError::encryption(format!("Invalid {}: unknown mode '{}'", path.display(), other))),
+    }
+}
+
+/// Key mode of the storage in `dir`, recording it on first use.
+///
+/// Installs from before the mode file are recognized by their salt or key file;
+/// a new install uses a passphrase when one is given.
+fn key_mode(dir: &Path, passphrase_given: bool) -> Result<KeyMode, AppError> {
+    if let Some(mode) = recorded_key_mode(dir)? {
+        return Ok(mode);
+    }
+
+    let has_salt = dir.join(SALT_FILE).exists();
+    let has_key = dir.join(KEY_FILE).exists();
+    let mode = match (has_salt, has_key) {
+        (true, false) => KeyMode::Passphrase,
+        (false, true) => KeyMode::KeyFile,
+        _ if passphrase_given => KeyMode::Passphrase,
+        _ => KeyMode::KeyFile,
+    };
+    fs::write(dir.join(MODE_FILE), mode.as_str())?;
+    log::info!("Storage key mode: {}", mode.as_str());
+    Ok(mode)
+}
+
+/// Derive the key of the storage in `dir` from `passphrase`, refusing a wrong one
+fn passphrase_key(dir: &Path, passphrase: &str) -> Result<Zeroizing<[u8; KEY_LENGTH]>, AppError> {
+    let salt_path = dir.join(SALT_FILE);
+    let new_install = !salt_path.exists();
+    let cipher = StorageCipher { key: derive_key(passphrase, &load_or_create_secret(&salt_path, SALT_LENGTH)?)? };
+
+    let check_path = dir.join(CHECK_FILE);
+    if check_path.exists() {
+        let matches = cipher.decrypt(CHECK_FILE, fs::read_to_string(&check_path)?.trim())
+            .is_ok_and(|value| value.as_str() == CHECK_VALUE);
+        if !matches {
+            return Err(AppError::encryption("Wrong storage passphrase"));
+        }
+    } else if new_install {
+        fs::write(&check_path, cipher.encrypt(CHECK_FILE, CHECK_VALUE)?)?;
+    } else {
+        // Installs from before the check value: a wrong passphrase shows up as failed reads
+        log::warn!("No {} to verify the storage passphrase against", CHECK_FILE);
+    }
+    Ok(cipher.key)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; KEY_LENGTH]>, AppError> {
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| AppError::encryption(format!("Failed to derive storage key: {}", e)))?;
+    Ok(key)
+}
+
+/// Read a random secret of `length` bytes, creating it (owner-only) on first use
+fn load_or_create_secret(path: &Path, length: usize) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    if path.exists() {
+        let encoded = Zeroizing::new(fs::read_to_string(path)?);
+        let secret = Zeroizing::new(STANDARD.decode(encoded.trim())
+            .map_err(|e| AppError::encryption(format!("Invalid {}: {}", path.display(), e)))?);
+        if secret.len() != length {
+            return Err(AppError::encryption(format!("Invalid {}: expected {} bytes", path.display(), length)));
+        }
+        return Ok(secret);
+    }
+
+    let mut secret = Zeroizing::new(vec![0u8; length]);
+    rand::thread_rng().fill_bytes(&mut secret);
+    let encoded = Zeroizing::new(STANDARD.encode(&*secret));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(encoded.as_bytes())?;
+    file.sync_all()?;
+
+    log::info!("Created {}", path.display());
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> StorageCipher {
+        StorageCipher { key: Zeroizing::new([7u8; KEY_LENGTH]) }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = test_cipher();
+        let sealed = cipher.encrypt("refresh_token", "secret-value").unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("secret-value"));
+        assert_eq!(cipher.decrypt("refresh_token", &sealed).unwrap().as_str(), "secret-value");
+        // Fresh nonce per write
+        assert_ne!(sealed, cipher.encrypt("refresh_token", "secret-value").unwrap());
+    }
+
+    #[test]
+    fn test_rejects_tampering_and_swapped_keys() {
+        let cipher = test_cipher();
+        let sealed = cipher.encrypt("refresh_token", "secret-value").unwrap();
+
+        assert!(cipher.decrypt("access_token", &sealed).is_err());
+        let mut tampered = sealed.clone().into_bytes();
+        let last = tampered.len() - 3;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert!(cipher.decrypt("refresh_token", &String::from_utf8(tampered).unwrap()).is_err());
+
+        let other = StorageCipher { key: Zeroizing::new([8u8; KEY_LENGTH]) };
+        assert!(other.decrypt("refresh_token", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_key_mode_is_recorded_and_enforced() {
+        let key_file_dir = crate::storage::tests::temp_dir("mode-key-file");
+        assert_eq!(key_mode(&key_file_dir, false).unwrap(), KeyMode::KeyFile);
+        // A later passphrase does not switch the recorded mode
+        assert_eq!(key_mode(&key_file_dir, true).unwrap(), KeyMode::KeyFile);
+        assert!(StorageCipher::unlock(&key_file_dir, "a passphrase").is_err());
+
+        let passphrase_dir = crate::storage::tests::temp_dir("mode-passphrase");
+        assert_eq!(key_mode(&passphrase_dir, true).unwrap(), KeyMode::Passphrase);
+        StorageCipher::unlock(&passphrase_dir, "a passphrase").unwrap();
+        let error = StorageCipher::unlock(&passphrase_dir, "another passphrase").err().unwrap();
+        assert!(error.to_string().contains("Wrong storage passphrase"), "{}", error);
+        StorageCipher::unlock(&passphrase_dir, "a passphrase").unwrap();
+
+        let _ = fs::remove_dir_all(key_file_dir);
+        let _ = fs::remove_dir_all(passphrase_dir);
+    }
+}
@@ -0,0 +1,276 @@
+/*!
+ * EMAPTA Endpoint Profiles
+ *
+ * Where EMAPTA requests are sent, and which client identifiers they carry,
+ * is selected from a profile instead of being hard-coded:
+ * - `production`: the public EMAPTA platform (built in)
+ * - `staging`: a staging tenant, configured by the user
+ * - `custom`: any other base URL, e.g. a local mock server
+ *
+ * The configuration is persisted in storage and the active profile can be
+ * switched at runtime and checked for connectivity.
+ */
+
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::documents;
+use crate::errors::AppError;
+use crate::storage::get_storage;
+
+/// Storage key for the persisted endpoint configuration
+const ENDPOINT_CONFIG_STORAGE_KEY: &str = "endpoint_config";
+
+const PRODUCTION_BASE_URL: &str = "https://api.platform.emapta.com";
+const PRODUCTION_AUTH_CLIENT_ID: &str = "EMAPTA-MYEMAPTAWEB";
+const PRODUCTION_CLIENT_CODE: &str = "EMAPTA-MYEMAPTA";
+
+const TOKEN_PATH: &str = "/auth/v1/auth/protocol/openid-connect/token";
+const AUTHORIZE_PATH: &str = "/auth/v1/auth/protocol/openid-connect/auth";
+const REVOKE_PATH: &str = "/auth/v1/auth/protocol/openid-connect/revoke";
+const LOGIN_PATH: &str = "/time-and-attendance/ta/v1/dtr/attendance/login";
+const LOGOUT_PATH: &str = "/time-and-attendance/ta/v1/dtr/attendance/logout";
+const ATTENDANCE_PATH: &str = "/time-and-attendance/ta/v1/dtr/attendance";
+
+/// Which endpoint profile to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointProfileKind {
+    #[default]
+    Production,
+    Staging,
+    Custom,
+}
+
+/// Base URL and client identifiers for one EMAPTA environment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointProfile {
+    /// Scheme and host (optionally with a path prefix), without a trailing slash
+    pub base_url: String,
+    /// `client_id` sent with token requests
+    pub auth_client_id: String,
+    /// `client-code` header sent with attendance requests
+    pub client_code: String,
+}
+
+impl EndpointProfile {
+    /// The built-in production profile
+    pub fn production() -> Self {
+        Self {
+            base_url: PRODUCTION_BASE_URL.to_string(),
+            auth_client_id: PRODUCTION_AUTH_CLIENT_ID.to_string(),
+            client_code: PRODUCTION_CLIENT_CODE.to_string(),
+        }
+    }
+
+    pub fn token_url(&self) -> String {
+        format!("{}{}", self.base_url, TOKEN_PATH)
+    }
+
+    /// Keycloak authorization endpoint used by the browser login
+    pub fn authorize_url(&self) -> String {
+        format!("{}{}", self.base_url, AUTHORIZE_PATH)
+    }
+
+    /// Keycloak token revocation endpoint (RFC 7009)
+    pub fn revoke_url(&self) -> String {
+        format!("{}{}", self.base_url, REVOKE_PATH)
+    }
+
+    pub fn login_url(&self) -> String {
+        format!("{}{}", self.base_url, LOGIN_PATH)
+    }
+
+    pub fn logout_url(&self) -> String {
+        format!("{}{}", self.base_url, LOGOUT_PATH)
+    }
+
+    pub fn attendance_url(&self) -> String {
+        format!("{}{}", self.base_url, ATTENDANCE_PATH)
+    }
+
+    /// Check the profile and normalize its base URL
+    fn validate(mut self, field: &str) -> Result<Self, AppError> {
+        self.base_url = self.base_url.trim().trim_end_matches('/').to_string();
+        let url = reqwest::Url::parse(&self.base_url)
+            .map_err(|e| AppError::validation(format!("{}.baseUrl", field), format!("Invalid base URL: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::validation(format!("{}.baseUrl", field), "Base URL must use http or https"));
+        }
+        if self.auth_client_id.trim().is_empty() {
+            return Err(AppError::validation(format!("{}.authClientId", field), "Client ID cannot be empty"));
+        }
+        if self.client_code.trim().is_empty() {
+            return Err(AppError::validation(format!("{}.clientCode", field), "Client code cannot be empty"));
+        }
+        Ok(self)
+    }
+}
+
+/// Persisted endpoint configuration
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointConfig {
+    #[serde(default)]
+    pub active: EndpointProfileKind,
+    #[serde(default)]
+    pub staging: Option<EndpointProfile>,
+    #[serde(default)]
+    pub custom: Option<EndpointProfile>,
+}
+
+impl EndpointConfig {
+    /// Resolve a profile; staging and custom must be configured first
+    pub fn profile(&self, kind: EndpointProfileKind) -> Result<EndpointProfile, AppError> {
+        match kind {
+            EndpointProfileKind::Production => Ok(EndpointProfile::production()),
+            EndpointProfileKind::Staging => self.staging.clone()
+                .ok_or_else(|| AppError::configuration("Staging endpoint profile is not configured")),
+            EndpointProfileKind::Custom => self.custom.clone()
+                .ok_or_else(|| AppError::configuration("Custom endpoint profile is not configured")),
+        }
+    }
+
+    /// Validate every configured profile and the active selection
+    pub fn validate(mut self) -> Result<Self, AppError> {
+        self.staging = self.staging.map(|p| p.validate("staging")).transpose()?;
+        self.custom = self.custom.map(|p| p.validate("custom")).transpose()?;
+        self.profile(self.active)?;
+        Ok(self)
+    }
+}
+
+/// Result of probing an endpoint profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityReport {
+    pub profile: EndpointProfileKind,
+    pub base_url: String,
+    /// The host answered over HTTP(S)
+    pub reachable: bool,
+    /// The attendance endpoint exists (it answered with anything but 404)
+    pub api_detected: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+static CONFIG: RwLock<Option<EndpointConfig>> = RwLock::new(None);
+
+/// Current endpoint configuration (production only until loaded)
+pub fn current_config() -> EndpointConfig {
+    CONFIG.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Profile all EMAPTA requests should use
+pub fn active_profile() -> EndpointProfile {
+    let config = current_config();
+    config.profile(config.active).unwrap_or_else(|_| EndpointProfile::production())
+}
+
+/// Load the persisted configuration
+pub async fn load_config(app_handle: &AppHandle) -> Result<EndpointConfig, AppError> {
+    let storage = get_storage(app_handle)?;
+    let config = match documents::load::<EndpointConfig>(&*storage, ENDPOINT_CONFIG_STORAGE_KEY, &documents::ENDPOINT_CONFIG).await? {
+        Some(config) => config.validate()?,
+        None => EndpointConfig::default(),
+    };
+
+    log::info!("Active profile: {:?} ({})", config.active, config.profile(config.active)?.base_url);
+    *CONFIG.write().unwrap() = Some(config.clone());
+    Ok(config)
+}
+
+/// Validate, apply and persist a new configuration
+pub async fn update_config(app_handle: &AppHandle, config: EndpointConfig) -> Result<EndpointConfig, AppError> {
+    let config = config.validate()?;
+
+    let storage = get_storage(app_handle)?;
+    documents::save(&*storage, ENDPOINT_CONFIG_STORAGE_KEY, &documents::ENDPOINT_CONFIG, &config).await?;
+
+    log::info!("Configuration updated, active profile: {:?}", config.active);
+    *CONFIG.write().unwrap() = Some(config.clone());
+    Ok(config)
+}
+
+/// Switch the active profile
+pub async fn select_profile(app_handle: &AppHandle, kind: EndpointProfileKind) -> Result<EndpointConfig, AppError> {
+    let mut config = current_config();
+    config.active = kind;
+    update_config(app_handle, config).await
+}
+
+/// Probe a profile (the active one by default) without sending credentials
+pub async fn check_connectivity(kind: Option<EndpointProfileKind>) -> Result<ConnectivityReport, AppError> {
+    let config = current_config();
+    let kind = kind.unwrap_or(config.active);
+    let profile = config.profile(kind)?;
+
+    let start_time = std::time::Instant::now();
+    let result = crate::http_client::emapta_client()
+        .get(profile.attendance_url())
+        .header("application-type", "KEYCLOAK")
+        .header("client-code", &profile.client_code)
+        .send()
+        .await;
+    let latency_ms = start_time.elapsed().as_millis() as u64;
+
+    let report = match result {
+        Ok(response) => {
+            let status = response.status();
+            ConnectivityReport {
+                profile: kind,
+                base_url: profile.base_url,
+                reachable: true,
+                api_detected: status != reqwest::StatusCode::NOT_FOUND,
+                status_code: Some(status.as_u16()),
+                latency_ms,
+                error: None,
+            }
+        }
+        Err(e) => ConnectivityReport {
+            profile: kind,
+            base_url: profile.base_url,
+            reachable: false,
+            api_detected: false,
+            status_code: None,
+            latency_ms,
+            error: Some(e.to_string()),
+        },
+    };
+
+    log::info!("Connectivity check for {:?}: reachable={}, status={:?}, {}ms",
+             report.profile, report.reachable, report.status_code, report.latency_ms);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_profile_is_normalized() {
+        let config = EndpointConfig {
+            active: EndpointProfileKind::Custom,
+            staging: None,
+            custom: Some(EndpointProfile {
+                base_url: " http://localhost:8080/ ".to_string(),
+                auth_client_id: "MOCK-WEB".to_string(),
+                client_code: "MOCK".to_string(),
+            }),
+        }
+        .validate()
+        .unwrap();
+
+        let profile = config.profile(EndpointProfileKind::Custom).unwrap();
+        assert_eq!(profile.attendance_url(), "http://localhost:8080/time-and-attendance/ta/v1/dtr/attendance");
+    }
+
+    #[test]
+    fn test_unconfigured_profile_cannot_be_active() {
+        let config = EndpointConfig { active: EndpointProfileKind::Staging, ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+}
@@ -76,6 +76,139 @@ impl AppError {
     pub fn unknown(message: impl Into<String>) -> Self {
         Self::Unknown { message: message.into() }
     }
+
+    /// Whether the failed operation may succeed if simply attempted again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network { .. } => true,
+            Self::Api { status_code: Some(status), .. } => is_retryable_status(*status),
+            _ => false,
+        }
+    }
+}
+
+/// Failure of a single EMAPTA API request
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EmaptaApiError {
+    /// The server answered with a non-success status
+    #[error("HTTP {status}{}", format_messages(.messages))]
+    Http { status: u16, messages: Vec<String> },
+
+    #[error("Request timed out: {message}")]
+    Timeout { message: String },
+
+    #[error("Network error: {message}")]
+    Network { message: String },
+
+    /// The response body was not in the expected shape
+    #[error("Failed to parse response: {message}")]
+    Parse { message: String },
+}
+
+fn format_messages(messages: &[String]) -> String {
+    if messages.is_empty() {
+        String::new()
+    } else {
+        format!(" - {}", messages.join("; "))
+    }
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    status == 408 || status == 429 || (500..600).contains(&status)
+}
+
+impl EmaptaApiError {
+    /// Classify a transport-level reqwest failure
+    pub fn from_reqwest(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout { message: err.to_string() }
+        } else if err.is_decode() {
+            Self::Parse { message: err.to_string() }
+        } else {
+            Self::Network { message: err.to_string() }
+        }
+    }
+
+    /// Build an HTTP error from a status and response body.
+    ///
+    /// EMAPTA envelopes carry `message` as a string or an array of strings;
+    /// Keycloak errors use `error` / `error_description`. Anything else keeps
+    /// the (truncated) raw body.
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let mut messages = Vec::new();
+
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
+            match json.get("message") {
+                Some(serde_json::Value::String(message)) => messages.push(message.clone()),
+                Some(serde_json::Value::Array(items)) => {
+                    messages.extend(items.iter().filter_map(|m| m.as_str()).map(str::to_string));
+                }
+                _ => {}
+            }
+            for key in ["error", "error_description"] {
+                if let Some(message) = json.get(key).and_then(|m| m.as_str()) {
+                    messages.push(message.to_string());
+                }
+            }
+        }
+
+        let body = body.trim();
+        if messages.is_empty() && !body.is_empty() {
+            messages.push(body.chars().take(200).collect());
+        }
+
+        // Bodies are echoed into logs and UI errors; never let token material through
+        let messages = messages.iter().map(|message| crate::redact::redact(message)).collect();
+
+        Self::Http { status, messages }
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::Parse { message: message.into() }
+    }
+
+    /// Whether the access token was rejected and a refresh may fix the call
+    pub fn requires_token_refresh(&self) -> bool {
+        match self {
+            Self::Http { status: 401, .. } => true,
+            Self::Http { messages, .. } => messages.iter().any(|message| {
+                let message = message.to_lowercase();
+                message.contains("invalid_token")
+                    || message.contains("token_expired")
+                    || (message.contains("token") && message.contains("expired"))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Convert into an `AppError`, prefixing the message with `context`.
+    ///
+    /// Timeouts and connection failures become `Network` and 408/429/5xx keep
+    /// their status, so `AppError::is_retryable` can tell transient failures apart.
+    pub fn into_app_error(self, context: &str) -> AppError {
+        let message = format!("{}: {}", context, self);
+        match self {
+            Self::Http { status: 401 | 403, .. } => AppError::authentication(message),
+            Self::Http { status, .. } => AppError::api(message, Some(status)),
+            Self::Timeout { .. } | Self::Network { .. } => AppError::network(message),
+            Self::Parse { .. } => AppError::api(message, None),
+        }
+    }
+
+    /// Like `into_app_error`, for token endpoint grants: a 400 (`invalid_grant`)
+    /// also means the refresh token or code is unusable. 408/429/5xx stay retryable.
+    pub fn into_grant_error(self, context: &str) -> AppError {
+        match self {
+            Self::Http { status: 400, .. } => AppError::authentication(format!("{}: {}", context, self)),
+            other => other.into_app_error(context),
+        }
+    }
+}
+
+impl From<EmaptaApiError> for AppError {
+    fn from(err: EmaptaApiError) -> Self {
+        err.into_app_error("EMAPTA request failed")
+    }
 }
 
 impl From<std::io::Error> for AppError {
@@ -98,7 +231,50 @@ impl From<tauri::Error> for AppError {
 
 pub fn setup_error_handler() {
     std::panic::set_hook(Box::new(|panic_info| {
-        let error = AppError::system(format!("Panic occurred: {}", panic_info));
-        println!("CRITICAL ERROR: {}", error);
+        let error = AppError::system(crate::redact::redact(&format!("Panic occurred: {}", panic_info)));
+        log::error!("{}", error);
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires_token_refresh() {
+        assert!(EmaptaApiError::from_response(401, "").requires_token_refresh());
+        assert!(EmaptaApiError::from_response(400, r#"{"message":["Token expired"]}"#).requires_token_refresh());
+        assert!(EmaptaApiError::from_response(403, r#"{"error":"invalid_token"}"#).requires_token_refresh());
+
+        assert!(!EmaptaApiError::from_response(500, "Internal Server Error").requires_token_refresh());
+        assert!(!EmaptaApiError::Network { message: "connection refused".to_string() }.requires_token_refresh());
+        assert!(!EmaptaApiError::parse("missing field `result`").requires_token_refresh());
+    }
+
+    #[test]
+    fn test_error_mapping() {
+        let unauthorized = EmaptaApiError::from_response(401, r#"{"message":"Unauthorized"}"#);
+        assert!(matches!(unauthorized.into_app_error("clock_in failed"), AppError::Authentication { .. }));
+
+        let server_error = EmaptaApiError::from_response(503, "").into_app_error("clock_in failed");
+        assert!(matches!(server_error, AppError::Api { status_code: Some(503), .. }));
+        assert!(server_error.is_retryable());
+
+        let timeout = EmaptaApiError::Timeout { message: "timed out".to_string() }.into_app_error("clock_in failed");
+        assert!(matches!(timeout, AppError::Network { .. }));
+        assert!(timeout.is_retryable());
+    }
+
+    #[test]
+    fn test_grant_error_mapping() {
+        let rejected = EmaptaApiError::from_response(400, r#"{"error":"invalid_grant"}"#).into_grant_error("Token refresh failed");
+        assert!(matches!(rejected, AppError::Authentication { .. }));
+        assert!(matches!(EmaptaApiError::from_response(401, "").into_grant_error("Token refresh failed"), AppError::Authentication { .. }));
+
+        // A gateway outage is not a dead refresh token
+        let outage = EmaptaApiError::from_response(503, "Service Unavailable").into_grant_error("Token refresh failed");
+        assert!(matches!(outage, AppError::Api { status_code: Some(503), .. }));
+        assert!(outage.is_retryable());
+        assert!(EmaptaApiError::from_response(429, "").into_grant_error("Token refresh failed").is_retryable());
+    }
+}
@@ -0,0 +1,508 @@
+/*!
+ * File Storage
+ *
+ * File-per-key storage in the app data directory. Every write goes to a
+ * synced temp file that is renamed over the target, and the value it
+ * replaces is kept as `<file>.prev`. Reads that find the current file
+ * missing, empty or failing authentication fall back to that previous
+ * generation and repair the current file, with a logged warning.
+ */
+
+use async_trait::async_trait;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+use crate::commands::{StorageError, StorageResult};
+use crate::encryption::{is_encrypted, StorageCipher};
+use crate::errors::AppError;
+use crate::storage::Storage;
+
+/// Journal listing the keys of a committed but not yet applied batch write
+const BATCH_JOURNAL_FILE: &str = "pending_batch.journal";
+/// Suffix of staged values waiting to replace their key's file
+const STAGED_SUFFIX: &str = ".tmp";
+/// Suffix of the previous generation of a key's file
+const PREVIOUS_SUFFIX: &str = ".prev";
+
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub app_data_dir: PathBuf,
+    pub encryption_enabled: bool,
+}
+
+pub struct FileStorage {
+    config: StorageConfig,
+    /// Set when `encryption_enabled`
+    cipher: Option<Arc<StorageCipher>>,
+    /// Serializes writes, which share staging paths and the batch journal
+    write_lock: Mutex<()>,
+}
+
+impl FileStorage {
+    /// Open the store in `app_data_dir`, finishing any batch write interrupted by a crash.
+    ///
+    /// Values are encrypted when a cipher is given.
+    pub fn open(app_data_dir: PathBuf, cipher: Option<Arc<StorageCipher>>) -> Result<Self, StorageError> {
+        fs::create_dir_all(&app_data_dir)?;
+
+        let config = StorageConfig {
+            app_data_dir,
+            encryption_enabled: cipher.is_some(),
+        };
+        let storage = Self { config, cipher, write_lock: Mutex::new(()) };
+
+        match storage.recover_pending_writes()? {
+            0 => {}
+            count => log::info!("Recovered {} keys from an interrupted storage write", count),
+        }
+        Ok(storage)
+    }
+
+    /// Encode a value for the file of `key` (encrypted when enabled)
+    fn seal(&self, key: &str, data: &str) -> Result<String, StorageError> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(key, data),
+            None => Ok(data.to_string()),
+        }
+    }
+
+    fn get_file_path(&self, key: &str) -> PathBuf {
+        let extension = if self.config.encryption_enabled { "enc" } else { "json" };
+        self.config.app_data_dir.join(format!("{}.{}", key, extension))
+    }
+
+    fn get_staged_path(&self, key: &str) -> PathBuf {
+        let mut path = self.get_file_path(key).into_os_string();
+        path.push(STAGED_SUFFIX);
+        PathBuf::from(path)
+    }
+
+    fn get_previous_path(&self, key: &str) -> PathBuf {
+        let mut path = self.get_file_path(key).into_os_string();
+        path.push(PREVIOUS_SUFFIX);
+        PathBuf::from(path)
+    }
+
+    fn get_journal_path(&self) -> PathBuf {
+        self.config.app_data_dir.join(BATCH_JOURNAL_FILE)
+    }
+
+    fn get_staged_journal_path(&self) -> PathBuf {
+        self.config.app_data_dir.join(format!("{}{}", BATCH_JOURNAL_FILE, STAGED_SUFFIX))
+    }
+
+    /// Whether `path` is a value staged by this backend (`<key>.<extension>.tmp`)
+    fn is_staged_value(&self, path: &Path) -> bool {
+        let extension = if self.config.encryption_enabled { "enc" } else { "json" };
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(&format!(".{}{}", extension, STAGED_SUFFIX)))
+    }
+
+    /// Remove every file generation of `key`.
+    ///
+    /// The previous generation goes first: a crash in between must not leave
+    /// only `.prev`, which `retrieve` would restore as if the current file were lost.
+    fn remove_files(&self, key: &str) -> Result<(), StorageError> {
+        remove_if_exists(&self.get_previous_path(key))?;
+        remove_if_exists(&self.get_file_path(key))
+    }
+
+    /// Move a staged value into place, keeping the current file as the previous generation.
+    ///
+    /// Safe to repeat: without a staged file nothing moves, so a retry never
+    /// rotates the new value out. Returns whether a staged value was moved.
+    fn commit_staged(&self, key: &str, keep_previous: bool) -> Result<bool, StorageError> {
+        let staged_path = self.get_staged_path(key);
+        if !staged_path.exists() {
+            return Ok(false);
+        }
+
+        let file_path = self.get_file_path(key);
+        if keep_previous {
+            self.rotate_previous(key, &file_path)?;
+        }
+        fs::rename(&staged_path, &file_path)?;
+        sync_dir(&self.config.app_data_dir)?;
+        Ok(true)
+    }
+
+    /// Keep the current file of `key` as its previous generation.
+    ///
+    /// A legacy plaintext value is encrypted into the previous slot rather than
+    /// moved, so no plaintext copy outlives the write replacing it.
+    fn rotate_previous(&self, key: &str, file_path: &Path) -> Result<(), StorageError> {
+        let previous_path = self.get_previous_path(key);
+        if let Some(cipher) = &self.cipher {
+            match fs::read_to_string(file_path) {
+                Ok(data) if !data.is_empty() && !is_encrypted(&data) => {
+                    let data = Zeroizing::new(data);
+                    return write_synced(&previous_path, &cipher.encrypt(key, &data)?);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(e.into()),
+                Ok(_) => {}
+            }
+        }
+        match fs::rename(file_path, previous_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Atomically replace the file of `key` with `contents`
+    fn write_file(&self, key: &str, contents: &str, keep_previous: bool) -> Result<(), StorageError> {
+        let _write = self.write_lock.lock().unwrap();
+        write_synced(&self.get_staged_path(key), contents)?;
+        if !self.commit_staged(key, keep_previous)? {
+            return Err(AppError::storage(format!("Staged value of '{}' disappeared before commit", key)));
+        }
+        Ok(())
+    }
+
+    /// Move staged values into place and drop the journal.
+    ///
+    /// With `require_staged`, a key without a staged value fails the batch;
+    /// recovery repeats a partly applied batch, where moved keys are expected.
+    fn apply_batch(&self, keys: &[&str], require_staged: bool) -> Result<(), StorageError> {
+        for key in keys {
+            if !self.commit_staged(key, true)? && require_staged {
+                return Err(AppError::storage(format!("Staged value of '{}' is missing from the batch", key)));
+            }
+        }
+        remove_if_exists(&self.get_journal_path())
+    }
+
+    /// Finish a committed batch interrupted by a crash and discard uncommitted staged files.
+    ///
+    /// Only call before any batch can be in flight. Returns the number of keys
+    /// rolled forward.
+    fn recover_pending_writes(&self) -> Result<usize, StorageError> {
+        let journal_path = self.get_journal_path();
+        let mut recovered = 0;
+        if journal_path.exists() {
+            let keys: Vec<String> = serde_json::from_str(&fs::read_to_string(&journal_path)?)?;
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            self.apply_batch(&keys, false)?;
+            recovered = keys.len();
+        }
+
+        // Only this backend's staged files; other `.tmp` files in the directory are not ours
+        remove_if_exists(&self.get_staged_journal_path())?;
+        for entry in fs::read_dir(&self.config.app_data_dir)? {
+            let path = entry?.path();
+            if self.is_staged_value(&path) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    /// Read and decrypt one file; empty files and values failing authentication are errors
+    fn read_file(&self, key: &str, path: &Path) -> Result<Option<StoredValue>, StorageError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read_to_string(path)?;
+        if data.is_empty() {
+            return Err(AppError::storage(format!("{} is empty", path.display())));
+        }
+
+        let Some(cipher) = &self.cipher else {
+            return Ok(Some(StoredValue { data, legacy_plaintext: false }));
+        };
+        if is_encrypted(&data) {
+            let mut plaintext = cipher.decrypt(key, &data)?;
+            return Ok(Some(StoredValue { data: std::mem::take(&mut *plaintext), legacy_plaintext: false }));
+        }
+        Ok(Some(StoredValue { data, legacy_plaintext: true }))
+    }
+
+    /// Re-encrypt a legacy plaintext value in place (the next read retries if this fails)
+    fn encrypt_legacy(&self, key: &str, data: &str) {
+        // No previous generation, so no plaintext copy stays behind
+        let migrated = self.seal(key, data)
+            .and_then(|sealed| self.write_file(key, &sealed, false));
+        match migrated {
+            Ok(()) => log::info!("Encrypted legacy plaintext value of '{}'", key),
+            Err(e) => log::warn!("Failed to encrypt legacy plaintext value of '{}': {}", key, e),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn store(&self, key: &str, data: &str) -> Result<StorageResult, StorageError> {
+        let file_path = self.get_file_path(key);
+        self.write_file(key, &self.seal(key, data)?, true)?;
+        Ok(StorageResult {
+            success: true,
+            message: format!("Data stored successfully: {:?}", file_path),
+        })
+    }
+
+    /// Values are staged next to their targets, then a journal naming the keys
+    /// is committed with an atomic rename before the staged files replace the
+    /// originals. `recover_pending_writes` finishes an interrupted batch.
+    /// Writes share staging paths and the journal, so they run one at a time under `write_lock`.
+    async fn store_batch(&self, entries: &[(&str, &str)]) -> Result<StorageResult, StorageError> {
+        let _write = self.write_lock.lock().unwrap();
+        for (key, data) in entries {
+            write_synced(&self.get_staged_path(key), &self.seal(key, data)?)?;
+        }
+
+        let keys: Vec<&str> = entries.iter().map(|(key, _)| *key).collect();
+        let journal_path = self.get_journal_path();
+        let staged_journal = self.get_staged_journal_path();
+        write_synced(&staged_journal, &serde_json::to_string(&keys)?)?;
+        fs::rename(&staged_journal, &journal_path)?; // Commit point
+
+        self.apply_batch(&keys, true)?;
+        Ok(StorageResult {
+            success: true,
+            message: format!("Stored {} keys atomically", keys.len()),
+        })
+    }
+
+    /// Falls back to the previous generation when the current file is missing or corrupt
+    async fn retrieve(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let current = self.read_file(key, &self.get_file_path(key));
+        if let Ok(Some(value)) = current {
+            if value.legacy_plaintext {
+                self.encrypt_legacy(key, &value.data);
+            }
+            return Ok(Some(value.data));
+        }
+
+        let previous = match self.read_file(key, &self.get_previous_path(key)) {
+            Ok(Some(previous)) => previous,
+            // Nothing to fall back to: report the current file's state
+            _ => return current.map(|_| None),
+        };
+        match &current {
+            Err(e) => log::warn!("Stored value of '{}' is corrupt ({}), using the previous copy", key, e),
+            Ok(_) => log::warn!("Stored value of '{}' is missing, using the previous copy", key),
+        }
+
+        // Repair the current file without rotating the bad one into the previous slot
+        let repaired = self.seal(key, &previous.data)
+            .and_then(|sealed| self.write_file(key, &sealed, false));
+        if let Err(e) = repaired {
+            log::warn!("Failed to restore '{}' from its previous copy: {}", key, e);
+        }
+        Ok(Some(previous.data))
+    }
+
+    async fn delete(&self, key: &str) -> Result<StorageResult, StorageError> {
+        let file_path = self.get_file_path(key);
+        if file_path.exists() || self.get_previous_path(key).exists() {
+            self.remove_files(key)?;
+            Ok(StorageResult {
+                success: true,
+                message: format!("Data deleted: {:?}", file_path),
+            })
+        } else {
+            Ok(StorageResult {
+                success: true,
+                message: "File not found (already deleted)".to_string(),
+            })
+        }
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>, StorageError> {
+        let mut keys = Vec::new();
+        if !self.config.app_data_dir.exists() {
+            return Ok(keys);
+        }
+
+        for entry in fs::read_dir(&self.config.app_data_dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+
+            if let Some(name_str) = file_name.to_str() {
+                let extension = if self.config.encryption_enabled { ".enc" } else { ".json" };
+                if name_str.ends_with(extension) {
+                    let key = name_str.trim_end_matches(extension);
+                    keys.push(key.to_string());
+                }
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Contents of one stored file
+struct StoredValue {
+    data: String,
+    /// Written before encryption at rest and still unencrypted
+    legacy_plaintext: bool,
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), StorageError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Write a file and flush it to disk before returning
+fn write_synced(path: &Path, data: &str) -> Result<(), StorageError> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(data.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Persist renames in `dir` (directory entries are not synced on Windows)
+fn sync_dir(dir: &Path) -> Result<(), StorageError> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::{exercise_storage, temp_dir};
+
+    #[tokio::test]
+    async fn test_file_storage() {
+        let dir = temp_dir("file");
+        exercise_storage(&FileStorage::open(dir.clone(), None).unwrap()).await;
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_recovers_previous_generation() {
+        let dir = temp_dir("file-recovery");
+        let storage = FileStorage::open(dir.clone(), None).unwrap();
+        storage.store("refresh_token", "first").await.unwrap();
+        storage.store("refresh_token", "second").await.unwrap();
+
+        // A truncated write leaves an empty file behind
+        fs::write(storage.get_file_path("refresh_token"), "").unwrap();
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap().as_deref(), Some("first"));
+        // The current file was repaired from the previous copy
+        assert_eq!(fs::read_to_string(storage.get_file_path("refresh_token")).unwrap(), "first");
+
+        storage.delete("refresh_token").await.unwrap();
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap(), None);
+        assert!(!storage.get_previous_path("refresh_token").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_write() {
+        let dir = temp_dir("file-interrupted");
+        let storage = FileStorage::open(dir.clone(), None).unwrap();
+        storage.store("refresh_token", "first").await.unwrap();
+
+        // Crash after the current file moved to the previous slot, before the staged one replaced it
+        fs::rename(storage.get_file_path("refresh_token"), storage.get_previous_path("refresh_token")).unwrap();
+        fs::write(storage.get_staged_path("refresh_token"), "second").unwrap();
+
+        // Staged writes without a journal are discarded, other `.tmp` files are left alone;
+        // the previous copy is served and restored
+        fs::write(dir.join("download.tmp"), "not ours").unwrap();
+        let storage = FileStorage::open(dir.clone(), None).unwrap();
+        assert!(!storage.get_staged_path("refresh_token").exists());
+        assert!(dir.join("download.tmp").exists());
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap().as_deref(), Some("first"));
+        assert_eq!(fs::read_to_string(storage.get_file_path("refresh_token")).unwrap(), "first");
+
+        // Repeating a commit never rotates the new value out
+        fs::write(storage.get_staged_path("refresh_token"), "second").unwrap();
+        storage.commit_staged("refresh_token", true).unwrap();
+        storage.commit_staged("refresh_token", true).unwrap();
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap().as_deref(), Some("second"));
+        assert_eq!(fs::read_to_string(storage.get_previous_path("refresh_token")).unwrap(), "first");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_rolls_forward_committed_batch() {
+        let dir = temp_dir("file-journal");
+        let storage = FileStorage::open(dir.clone(), None).unwrap();
+        storage.store_batch(&[("refresh_token", "refresh-1"), ("access_token", "access-1")]).await.unwrap();
+        assert!(!storage.get_journal_path().exists());
+
+        // Crash after the journal was committed and only the first key was moved into place
+        fs::write(storage.get_staged_path("refresh_token"), "refresh-2").unwrap();
+        fs::write(storage.get_staged_path("access_token"), "access-2").unwrap();
+        fs::write(storage.get_journal_path(), r#"["refresh_token","access_token"]"#).unwrap();
+        storage.commit_staged("refresh_token", true).unwrap();
+
+        // Reopening finishes the batch: both keys hold the new pair and the journal is gone
+        let storage = FileStorage::open(dir.clone(), None).unwrap();
+        assert!(!storage.get_journal_path().exists());
+        assert!(!storage.get_staged_path("access_token").exists());
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap().as_deref(), Some("refresh-2"));
+        assert_eq!(storage.retrieve("access_token").await.unwrap().as_deref(), Some("access-2"));
+        assert_eq!(fs::read_to_string(storage.get_previous_path("refresh_token")).unwrap(), "refresh-1");
+        assert_eq!(fs::read_to_string(storage.get_previous_path("access_token")).unwrap(), "access-1");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_batches() {
+        let dir = temp_dir("file-concurrent");
+        let storage = Arc::new(FileStorage::open(dir.clone(), None).unwrap());
+
+        // Token refreshes of different accounts share the one journal and staging paths
+        let tasks: Vec<_> = (0..8).map(|account| {
+            let storage = Arc::clone(&storage);
+            tokio::spawn(async move {
+                for round in 0..20 {
+                    let refresh_key = format!("account{}_refresh_token", account);
+                    let access_key = format!("account{}_access_token", account);
+                    let refresh = format!("refresh-{}", round);
+                    let access = format!("access-{}", round);
+                    storage.store_batch(&[(refresh_key.as_str(), refresh.as_str()), (access_key.as_str(), access.as_str()), ("shared", "batch")]).await.unwrap();
+                    // Plain writes stage to the same paths as batches
+                    storage.store("shared", "single").await.unwrap();
+                }
+            })
+        }).collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert!(!storage.get_journal_path().exists());
+        for account in 0..8 {
+            let refresh = storage.retrieve(&format!("account{}_refresh_token", account)).await.unwrap();
+            assert_eq!(refresh.as_deref(), Some("refresh-19"));
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_recovery_and_legacy_plaintext() {
+        let dir = temp_dir("file-encrypted");
+        let cipher = Arc::new(StorageCipher::from_passphrase("test passphrase", &[1u8; 16]).unwrap());
+        let storage = FileStorage::open(dir.clone(), Some(cipher.clone())).unwrap();
+
+        // A legacy plaintext value is encrypted on its way into the previous slot
+        fs::write(storage.get_file_path("refresh_token"), "legacy-secret").unwrap();
+        storage.store("refresh_token", "first").await.unwrap();
+        let previous = fs::read_to_string(storage.get_previous_path("refresh_token")).unwrap();
+        assert!(is_encrypted(&previous) && !previous.contains("legacy-secret"));
+
+        // A value that fails authentication falls back to the previous generation
+        storage.store("refresh_token", "second").await.unwrap();
+        fs::write(storage.get_file_path("refresh_token"), cipher.encrypt("access_token", "swapped").unwrap()).unwrap();
+        assert_eq!(storage.retrieve("refresh_token").await.unwrap().as_deref(), Some("first"));
+        assert_eq!(cipher.decrypt("refresh_token", &fs::read_to_string(storage.get_file_path("refresh_token")).unwrap()).unwrap().as_str(), "first");
+        let _ = fs::remove_dir_all(dir);
+    }
+}
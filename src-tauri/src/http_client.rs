@@ -0,0 +1,210 @@
+/*!
+ * EMAPTA HTTP Client
+ *
+ * One long-lived `reqwest::Client` shared by every EMAPTA call, so requests
+ * reuse pooled connections and can never hang indefinitely:
+ * - Connect and overall request timeouts
+ * - Connection pooling with TCP keep-alive
+ * - Identifying User-Agent and gzip response decoding
+ * - Optional proxy and extra CA certificates for corporate networks
+ *
+ * The configuration is persisted in storage and the client is rebuilt
+ * whenever it changes.
+ */
+
+use std::sync::RwLock;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::documents;
+use crate::errors::AppError;
+use crate::storage::get_storage;
+
+/// Storage key for the persisted client configuration
+const HTTP_CONFIG_STORAGE_KEY: &str = "http_client_config";
+
+const USER_AGENT: &str = concat!("BlackBird/", env!("CARGO_PKG_VERSION"));
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const POOL_MAX_IDLE_PER_HOST: usize = 4;
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// User-adjustable HTTP client settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpClientConfig {
+    /// Time allowed to establish a TCP/TLS connection (seconds)
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Time allowed for a whole request, including reading the body (seconds)
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Proxy URL for all requests (e.g. `http://proxy.corp:8080`); system proxy settings apply when unset
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// PEM files with additional root certificates to trust
+    #[serde(default)]
+    pub extra_ca_cert_paths: Vec<String>,
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_connect_timeout_secs(),
+            request_timeout_secs: default_request_timeout_secs(),
+            proxy_url: None,
+            extra_ca_cert_paths: Vec::new(),
+        }
+    }
+}
+
+/// Active client and the configuration it was built from
+struct ClientState {
+    client: reqwest::Client,
+    config: HttpClientConfig,
+}
+
+static CLIENT: RwLock<Option<ClientState>> = RwLock::new(None);
+
+/// Build a client from `config`, validating every setting
+pub fn build_client(config: &HttpClientConfig) -> Result<reqwest::Client, AppError> {
+    if config.connect_timeout_secs == 0 {
+        return Err(AppError::validation("connectTimeoutSecs", "Connect timeout must be greater than zero"));
+    }
+    if config.request_timeout_secs == 0 {
+        return Err(AppError::validation("requestTimeoutSecs", "Request timeout must be greater than zero"));
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .timeout(Duration::from_secs(config.request_timeout_secs))
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(TCP_KEEPALIVE)
+        .gzip(true);
+
+    if let Some(proxy_url) = config.proxy_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy_url.trim())
+            .map_err(|e| AppError::validation("proxyUrl", format!("Invalid proxy URL: {}", e)))?;
+        builder = builder.proxy(proxy);
+    }
+
+    for path in &config.extra_ca_cert_paths {
+        let pem = std::fs::read(path)
+            .map_err(|e| AppError::configuration(format!("Failed to read CA certificate '{}': {}", path, e)))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| AppError::configuration(format!("Invalid CA certificate '{}': {}", path, e)))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| AppError::configuration(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Shared EMAPTA client (built with defaults on first use if not configured)
+pub fn emapta_client() -> reqwest::Client {
+    if let Some(state) = CLIENT.read().unwrap().as_ref() {
+        return state.client.clone();
+    }
+
+    let mut guard = CLIENT.write().unwrap();
+    let state = guard.get_or_insert_with(|| {
+        let config = HttpClientConfig::default();
+        let client = build_client(&config).unwrap_or_else(|e| {
+            log::warn!("Falling back to a plain client: {}", e);
+            reqwest::Client::new()
+        });
+        ClientState { client, config }
+    });
+    state.client.clone()
+}
+
+/// Configuration of the active client
+pub fn current_config() -> HttpClientConfig {
+    CLIENT
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|state| state.config.clone())
+        .unwrap_or_default()
+}
+
+/// Replace the active client; the old one is dropped once in-flight requests finish
+fn install(config: HttpClientConfig) -> Result<(), AppError> {
+    let client = build_client(&config)?;
+    *CLIENT.write().unwrap() = Some(ClientState { client, config });
+    Ok(())
+}
+
+/// Load the persisted configuration and build the shared client
+pub async fn load_config(app_handle: &AppHandle) -> Result<HttpClientConfig, AppError> {
+    let storage = get_storage(app_handle)?;
+    let config: HttpClientConfig = documents::load(&*storage, HTTP_CONFIG_STORAGE_KEY, &documents::HTTP_CONFIG).await?
+        .unwrap_or_default();
+
+    install(config.clone())?;
+    log::info!(
+        "Configured (connect {}s, request {}s, proxy: {}, extra CAs: {})",
+        config.connect_timeout_secs,
+        config.request_timeout_secs,
+        config.proxy_url.as_deref().unwrap_or("system"),
+        config.extra_ca_cert_paths.len()
+    );
+    Ok(config)
+}
+
+/// Validate, apply and persist a new configuration
+pub async fn update_config(app_handle: &AppHandle, config: HttpClientConfig) -> Result<(), AppError> {
+    // Build first so an invalid configuration is neither applied nor saved
+    install(config.clone())?;
+
+    let storage = get_storage(app_handle)?;
+    documents::save(&*storage, HTTP_CONFIG_STORAGE_KEY, &documents::HTTP_CONFIG, &config).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_invalid_settings() {
+        let zero_connect = HttpClientConfig { connect_timeout_secs: 0, ..Default::default() };
+        assert!(matches!(build_client(&zero_connect), Err(AppError::Validation { field, .. }) if field == "connectTimeoutSecs"));
+
+        let zero_request = HttpClientConfig { request_timeout_secs: 0, ..Default::default() };
+        assert!(matches!(build_client(&zero_request), Err(AppError::Validation { field, .. }) if field == "requestTimeoutSecs"));
+
+        let bad_proxy = HttpClientConfig { proxy_url: Some("http://proxy corp:8080".to_string()), ..Default::default() };
+        assert!(matches!(build_client(&bad_proxy), Err(AppError::Validation { field, .. }) if field == "proxyUrl"));
+
+        // A blank proxy falls back to the system settings
+        let blank_proxy = HttpClientConfig { proxy_url: Some("  ".to_string()), ..Default::default() };
+        assert!(build_client(&blank_proxy).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unusable_ca_certificates() {
+        let dir = crate::storage::tests::temp_dir("http-ca");
+        let invalid = dir.join("invalid.pem");
+        std::fs::write(&invalid, "-----BEGIN CERTIFICATE-----\n!!! not base64 !!!\n-----END CERTIFICATE-----\n").unwrap();
+
+        for path in [invalid, dir.join("missing.pem")] {
+            let config = HttpClientConfig { extra_ca_cert_paths: vec![path.display().to_string()], ..Default::default() };
+            assert!(matches!(build_client(&config), Err(AppError::Configuration { .. })));
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,87 @@
+/*!
+ * JWT Claims
+ *
+ * Reads the payload of the Keycloak-issued access and refresh tokens so
+ * their lifetimes and owner can be tracked. Signatures are NOT verified: the
+ * tokens come straight from the token endpoint over TLS and the claims are
+ * only used for scheduling refreshes, display and setup sanity checks.
+ */
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::errors::AppError;
+
+/// Claims used by the app (all optional; offline tokens may omit `exp`)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JwtClaims {
+    /// Expiry (seconds since the Unix epoch)
+    pub exp: Option<i64>,
+    /// Issued at (seconds since the Unix epoch)
+    pub iat: Option<i64>,
+    /// Subject: the stable ID of the signed-in user
+    pub sub: Option<String>,
+    /// Issuer (the Keycloak realm URL)
+    pub iss: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+impl JwtClaims {
+    /// Seconds left until `exp` at `now` (negative once expired)
+    pub fn expires_in(&self, now: i64) -> Option<i64> {
+        self.exp.map(|exp| exp - now)
+    }
+
+    /// Total lifetime from `iat` to `exp`
+    pub fn lifetime(&self) -> Option<i64> {
+        Some(self.exp? - self.iat?)
+    }
+}
+
+/// Decode the claims of a compact JWT without verifying its signature
+pub fn decode_claims(token: &str) -> Result<JwtClaims, AppError> {
+    let mut segments = token.split('.');
+    let (Some(_header), Some(payload), Some(_signature), None) =
+        (segments.next(), segments.next(), segments.next(), segments.next())
+    else {
+        return Err(AppError::authentication("Token is not a JWT"));
+    };
+
+    // Tolerate encoders that keep the padding
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| AppError::authentication(format!("Invalid JWT payload encoding: {}", e)))?;
+
+    serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::authentication(format!("Invalid JWT claims: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_with_payload(payload: &str) -> String {
+        format!("eyJhbGciOiJIUzI1NiJ9.{}.c2lnbmF0dXJl", URL_SAFE_NO_PAD.encode(payload))
+    }
+
+    #[test]
+    fn test_decode_claims() {
+        let token = token_with_payload(r#"{"exp":1760000300,"iat":1760000000,"sub":"user-1","typ":"Bearer"}"#);
+        let claims = decode_claims(&token).unwrap();
+
+        assert_eq!(claims.exp, Some(1_760_000_300));
+        assert_eq!(claims.lifetime(), Some(300));
+        assert_eq!(claims.expires_in(1_760_000_100), Some(200));
+        assert_eq!(claims.sub.as_deref(), Some("user-1"));
+        assert_eq!(claims.email, None);
+    }
+
+    #[test]
+    fn test_rejects_opaque_tokens() {
+        assert!(decode_claims("opaque-refresh-token").is_err());
+        assert!(decode_claims("a.b.c.d").is_err());
+    }
+}
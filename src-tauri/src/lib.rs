@@ -8,39 +8,99 @@
 // Module declarations
 mod commands;
 mod storage;
+mod documents;
+mod settings_bundle;
+mod file_storage;
+mod sqlite_storage;
+#[cfg(test)]
+mod memory_storage;
+mod encryption;
+mod keyring_store;
 mod errors;
 mod scheduler;
 mod token_manager;
 mod logging;
+mod log_store;
+mod clock_skew;
+mod shutdown;
+mod operation_history;
+mod http_client;
+mod endpoints;
+mod jwt;
+mod redact;
+mod diagnostics;
+mod provider;
+mod mock_provider;
+mod oidc;
+mod accounts;
 #[cfg(feature = "system-tray")]
 mod tray;
 
 use crate::commands::*;
 use crate::errors::setup_error_handler;
+use tauri::{AppHandle, Manager};
 
-/**
- * Application initialization and setup
- */
-fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    // Setup error handling
-    setup_error_handler();
-    
-    // Log application startup
-    println!("Black Bird Clock Automation starting...");
-    
-    // Initialize storage backend (will be available for commands)
-    let app_handle = app.handle().clone();
-    match crate::storage::create_storage_backend(app_handle.clone()) {
-        Ok(_) => println!("Storage backend initialized successfully"),
+/// Everything that needs the storage: run during setup, or by `unlock_storage`
+/// when the storage was locked at startup
+pub(crate) async fn initialize_from_storage(app_handle: AppHandle) {
+    let app_data_dir = match app_handle.path().app_data_dir() {
+        Ok(dir) => dir,
         Err(e) => {
-            println!("ERROR: Failed to initialize storage: {}", e);
-            return Err(format!("Failed to initialize storage: {}", e).into());
+            log::error!("App data directory unavailable: {}", e);
+            return;
         }
+    };
+
+    // Apply saved diagnostic log levels
+    if let Err(e) = crate::diagnostics::load_levels(&app_handle).await {
+        log::warn!("Failed to load diagnostic log levels, using defaults: {}", e);
     }
     
-    // Initialize scheduler
+    // Build the shared EMAPTA HTTP client from the saved configuration
+    if let Err(e) = crate::http_client::load_config(&app_handle).await {
+        log::warn!("Failed to load HTTP client configuration, using defaults: {}", e);
+    }
+
+    // Select the EMAPTA endpoint profile
+    if let Err(e) = crate::endpoints::load_config(&app_handle).await {
+        log::warn!("Failed to load endpoint configuration, using production: {}", e);
+    }
+
+    // Load the registered accounts (only the default account on first run)
+    if let Err(e) = crate::accounts::load(&app_handle).await {
+        log::warn!("Failed to load accounts, using the default account only: {}", e);
+    }
+
+    // Open the activity log and import the monthly log containers of earlier versions
+    let log_store = crate::encryption::StorageCipher::for_dir(&app_data_dir)
+        .and_then(|cipher| crate::log_store::LogStore::open(&app_data_dir.join(crate::log_store::LOG_DATABASE_FILE), cipher));
+    match log_store {
+        Ok(store) => {
+            crate::log_store::manage_log_store(&app_handle, store);
+            match crate::log_store::import_legacy_logs(&app_handle).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Imported {} activity log entries into the log database", count),
+                Err(e) => log::warn!("Failed to import earlier activity logs: {}", e),
+            }
+        }
+        Err(e) => log::warn!("Activity log unavailable: {}", e),
+    }
+
+    // Initialize one scheduler per account
     crate::scheduler::initialize_scheduler(app_handle.clone());
-    println!("Scheduler initialized successfully");
+    for scheduler in crate::scheduler::all_schedulers() {
+        log::info!("Scheduler for account '{}' initialized successfully (provider: {})",
+                 scheduler.account().id(), scheduler.provider().name());
+
+        // Restore finished operations from previous runs
+        match scheduler.load_history().await {
+            Ok(count) => log::info!("Operation history of '{}' restored ({} entries)", scheduler.account().id(), count),
+            Err(e) => log::warn!("Failed to restore operation history of '{}': {}", scheduler.account().id(), e),
+        }
+    }
+
+    // Refresh tokens ahead of expiry in the background
+    crate::token_manager::start_proactive_refresh(app_handle.clone());
 
     // Schedule automatic startup check and background monitoring initialization
     // This runs after Tauri async runtime is available
@@ -54,25 +114,72 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         let rt = match tokio::runtime::Runtime::new() {
             Ok(rt) => rt,
             Err(e) => {
-                println!("[Startup] Failed to create Tokio runtime for background initialization: {}", e);
+                log::warn!("Failed to create Tokio runtime for background initialization: {}", e);
                 return;
             }
         };
 
         rt.block_on(async {
-            println!("[Startup] Running automatic startup checks...");
+            log::info!("Running automatic startup checks...");
 
             // Initialize background monitoring first
             match crate::commands::initialize_background_monitoring_internal(startup_handle.clone()).await {
-                Ok(_) => println!("[Startup] Background monitoring initialized successfully"),
-                Err(e) => println!("[Startup] WARNING: Background monitoring failed to initialize: {}", e),
+                Ok(_) => log::info!("Background monitoring initialized successfully"),
+                Err(e) => log::warn!("Background monitoring failed to initialize: {}", e),
             }
         });
     });
 
     // Initialize activity logger
     crate::logging::initialize_logger(app_handle.clone());
-    println!("Activity logger initialized successfully");
+    log::info!("Activity logger initialized successfully");
+
+    // Start schedulers with their saved schedule, so a hidden autostart needs no UI
+    for scheduler in crate::scheduler::all_schedulers() {
+        match scheduler.restore_schedule().await {
+            Ok(true) => log::info!("Scheduler of '{}' started with its saved schedule", scheduler.account().id()),
+            Ok(false) => log::info!("Scheduler of '{}' left stopped", scheduler.account().id()),
+            Err(e) => log::warn!("Failed to restore the schedule of '{}': {}", scheduler.account().id(), e),
+        }
+    }
+}
+
+/**
+ * Application initialization and setup
+ */
+fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    // Setup error handling
+    setup_error_handler();
+    
+    // Log application startup
+    log::info!("Black Bird Clock Automation starting...");
+    
+    let app_handle = app.handle().clone();
+
+    // Mirror diagnostics into the rotating log file
+    match crate::diagnostics::attach_file(&app_handle) {
+        Ok(path) => log::info!("Diagnostic log file: {}", path.display()),
+        Err(e) => log::warn!("Diagnostic log file unavailable, console only: {}", e),
+    }
+
+    // Open the storage backend once and share it with every command
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    match tauri::async_runtime::block_on(crate::storage::open_storage(app_data_dir.clone())) {
+        Ok(storage) => {
+            crate::storage::manage_storage(&app_handle, storage);
+            log::info!("Storage backend initialized successfully");
+            tauri::async_runtime::block_on(initialize_from_storage(app_handle.clone()));
+        }
+        // Locked or mismatched key: wait for `unlock_storage` rather than use another key
+        Err(e @ crate::errors::AppError::Encryption { .. }) => {
+            log::error!("Storage is locked, waiting for it to be unlocked: {}", e);
+            crate::storage::set_locked(e.to_string());
+        }
+        Err(e) => {
+            log::error!("Failed to initialize storage: {}", e);
+            return Err(format!("Failed to initialize storage: {}", e).into());
+        }
+    }
 
     // Note: Background monitoring will be initialized automatically after Tauri starts
     // This avoids the Tokio runtime issue during synchronous setup.
@@ -82,19 +189,19 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     {
         match crate::tray::create_system_tray(&app_handle) {
             Ok(_) => {
-                println!("System tray initialized successfully");
+                log::info!("System tray initialized successfully");
             }
             Err(e) => {
-                println!("WARNING: System tray failed to initialize: {}", e);
-                println!("INFO: App will run normally without system tray. Window management still works.");
+                log::warn!("System tray failed to initialize: {}", e);
+                log::info!("App will run normally without system tray. Window management still works.");
             }
         }
     }
 
     #[cfg(not(feature = "system-tray"))]
     {
-        println!("INFO: System tray feature disabled (normal for development in WSL/headless environments)");
-        println!("INFO: Window close behavior and all other functionality works normally.");
+        log::info!("System tray feature disabled (normal for development in WSL/headless environments)");
+        log::info!("Window close behavior and all other functionality works normally.");
     }
 
     Ok(())
@@ -105,8 +212,11 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
  */
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    crate::diagnostics::init();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(vec!["--minimized"])))
         .setup(setup_app)
         .on_window_event(|window, event| {
@@ -116,7 +226,7 @@ pub fn run() {
                     // Prevent window from closing, hide it instead (minimize to tray)
                     let _ = window.hide();
                     api.prevent_close();
-                    println!("[Window] Close requested - minimized to tray instead");
+                    log::info!("Close requested - minimized to tray instead");
                 }
                 _ => {}
             }
@@ -127,15 +237,36 @@ pub fn run() {
             retrieve_encrypted_data,
             delete_encrypted_data,
             list_storage_keys,
+            get_storage_status,
+            unlock_storage,
             
             // Schedule commands
             set_schedule,
             get_schedule,
+
+            // Network configuration commands
+            get_http_client_config,
+            set_http_client_config,
+            get_endpoint_config,
+            set_endpoint_config,
+            select_endpoint_profile,
+            check_endpoint_connectivity,
+
+            // Diagnostic logging commands
+            get_diagnostic_log_settings,
+            set_diagnostic_log_level,
             
+            // Account commands
+            list_accounts,
+            add_account,
+            rename_account,
+            remove_account,
+
             // Scheduler commands
             start_scheduler,
             stop_scheduler,
             get_scheduler_state,
+            get_operation_history,
             set_scheduler_access_token,
             scheduler_manual_clock_in,
             scheduler_manual_clock_out,
@@ -149,16 +280,28 @@ pub fn run() {
             api_manual_clock_out,
             api_get_attendance_status,
             api_setup_dual_tokens,
+            api_login_with_browser,
+            api_confirm_browser_login,
+            api_discard_browser_login,
+            api_logout,
+            token_status,
+            token_identity,
 
             // Autostart commands (Phase 3 Enhancement)
             enable_autostart,
             disable_autostart,
             is_autostart_enabled,
+            export_settings,
+            import_settings,
+
+            // Shutdown commands
+            quit_application,
 
             // Activity logging commands (Phase 4 Feature)
             get_activity_logs,
             get_filtered_activity_logs,
             clear_activity_logs,
+            query_activity_logs,
             debug_logging_status,
             reinitialize_logger,
 
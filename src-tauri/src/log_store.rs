@@ -0,0 +1,583 @@
+/*!
+ * Log Store
+ *
+ * Activity log entries in a SQLite database (`activity_log.db` in the app
+ * data directory), one row per entry instead of one JSON container per month:
+ * - Logging an entry is a single insert, and entries older than
+ *   `RETENTION_DAYS` are pruned through the timestamp index
+ * - Indexes on timestamp, action and status back `LogQuery`, which filters
+ *   by date range, action, status and text in the details, newest first
+ *   with offset/limit paging
+ * - The schema version is kept in `PRAGMA user_version`; opening an older
+ *   database backs it up to `activity_log.db.v<version>.bak` and runs the
+ *   missing `MIGRATIONS`
+ * - The monthly `logs_YYYY_MM` containers of earlier versions are imported
+ *   on first run and moved to their `.v0.bak` backup keys
+ *
+ * The `details` and `metadata` columns are encrypted with the storage
+ * cipher (`encryption.rs`), each value bound to its account, entry and
+ * column; rows written in plaintext by earlier versions are encrypted when
+ * the store is opened. Timestamps, actions and statuses stay in the clear
+ * for the indexes, and text search decrypts the candidate rows instead of
+ * matching in SQL.
+ */
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::accounts::storage_key;
+use crate::documents::backup_key;
+use crate::encryption::{is_encrypted, StorageCipher};
+use crate::errors::AppError;
+use crate::logging::{LogAction, LogEntry, LogStatus, MonthlyLogContainer};
+use crate::storage::get_storage;
+
+/// Database file in the app data directory
+pub const LOG_DATABASE_FILE: &str = "activity_log.db";
+
+/// Schema of each version: `MIGRATIONS[n]` upgrades version `n` to `n + 1`
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS log_entries (
+        seq INTEGER PRIMARY KEY,
+        account_id TEXT NOT NULL,
+        id TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        timestamp_ms INTEGER NOT NULL,
+        action TEXT NOT NULL,
+        status TEXT NOT NULL,
+        details TEXT NOT NULL,
+        metadata TEXT NOT NULL,
+        UNIQUE (account_id, id, timestamp)
+    );
+    CREATE INDEX IF NOT EXISTS log_entries_timestamp ON log_entries (timestamp_ms);
+    CREATE INDEX IF NOT EXISTS log_entries_account_timestamp ON log_entries (account_id, timestamp_ms);
+    CREATE INDEX IF NOT EXISTS log_entries_action ON log_entries (account_id, action, timestamp_ms);
+    CREATE INDEX IF NOT EXISTS log_entries_status ON log_entries (account_id, status, timestamp_ms);",
+];
+
+/// Entries older than this are pruned
+const RETENTION_DAYS: i64 = 6 * 30;
+
+/// Default and maximum page sizes for log queries
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Filters and paging of a log query (every filter is optional)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogQuery {
+    pub action: Option<LogAction>,
+    pub status: Option<LogStatus>,
+    /// Only entries at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Case-insensitive text the details must contain
+    pub text: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// One page of log entries (newest first)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Total number of entries matching the filters
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+pub struct LogStore {
+    connection: Mutex<Connection>,
+    /// Seals `details` and `metadata` (`None` only in tests)
+    cipher: Option<Arc<StorageCipher>>,
+}
+
+impl LogStore {
+    /// Open (or create) the database at `path`, encrypting with `cipher`
+    pub fn open(path: &Path, cipher: Arc<StorageCipher>) -> Result<Self, AppError> {
+        let connection = Connection::open(path).map_err(sqlite_error)?;
+        connection.pragma_update(None, "journal_mode", "WAL").map_err(sqlite_error)?;
+        // Overwrite freed pages, so replaced plaintext rows do not linger in the file
+        connection.pragma_update(None, "secure_delete", true).map_err(sqlite_error)?;
+        Self::with_connection(connection, Some(path), Some(cipher))
+    }
+
+    /// Database that lives only as long as the instance (for tests)
+    #[cfg(test)]
+    pub fn open_in_memory(cipher: Option<Arc<StorageCipher>>) -> Result<Self, AppError> {
+        Self::with_connection(Connection::open_in_memory().map_err(sqlite_error)?, None, cipher)
+    }
+
+    fn with_connection(mut connection: Connection, path: Option<&Path>, cipher: Option<Arc<StorageCipher>>) -> Result<Self, AppError> {
+        migrate(&mut connection, path)?;
+        let store = Self { connection: Mutex::new(connection), cipher };
+        match store.encrypt_plaintext_rows()? {
+            0 => {}
+            count => log::info!("Encrypted {} activity log entries written by an earlier version", count),
+        }
+        Ok(store)
+    }
+
+    /// Encrypt a column value of an entry
+    fn seal(&self, account_id: &str, id: &str, column: &str, value: &str) -> Result<String, AppError> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(&column_name(account_id, id, column), value),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Decrypt a column value of an entry (plaintext without a cipher is returned as-is)
+    fn unseal(&self, account_id: &str, id: &str, column: &str, stored: String) -> Result<String, AppError> {
+        match &self.cipher {
+            Some(cipher) if is_encrypted(&stored) => {
+                let mut value = cipher.decrypt(&column_name(account_id, id, column), &stored)?;
+                Ok(std::mem::take(&mut *value))
+            }
+            _ => Ok(stored),
+        }
+    }
+
+    fn row<'a>(&self, account_id: &str, entry: &'a LogEntry) -> Result<EntryRow<'a>, AppError> {
+        let timestamp_ms = DateTime::parse_from_rfc3339(&entry.timestamp)
+            .map_err(|e| AppError::validation("timestamp", format!("Invalid log timestamp '{}': {}", entry.timestamp, e)))?
+            .timestamp_millis();
+        let metadata = serde_json::to_string(&entry.metadata)
+            .map_err(|e| AppError::storage(format!("Failed to serialize log metadata: {}", e)))?;
+        Ok(EntryRow {
+            entry,
+            timestamp_ms,
+            details: self.seal(account_id, &entry.id, "details", &entry.details)?,
+            metadata: self.seal(account_id, &entry.id, "metadata", &metadata)?,
+        })
+    }
+
+    /// Encrypt rows stored in plaintext; returns the number encrypted
+    fn encrypt_plaintext_rows(&self) -> Result<usize, AppError> {
+        if self.cipher.is_none() {
+            return Ok(0);
+        }
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        let rows = {
+            let mut statement = transaction.prepare(
+                "SELECT seq, account_id, id, details, metadata FROM log_entries WHERE details NOT LIKE 'bbenc1:%' OR metadata NOT LIKE 'bbenc1:%'"
+            ).map_err(sqlite_error)?;
+            let rows = statement.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?))
+            }).map_err(sqlite_error)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(sqlite_error)?
+        };
+
+        for (seq, account_id, id, details, metadata) in &rows {
+            let seal = |column: &str, value: &str| if is_encrypted(value) {
+                Ok(value.to_string())
+            } else {
+                self.seal(account_id, id, column, value)
+            };
+            transaction.execute(
+                "UPDATE log_entries SET details = ?1, metadata = ?2 WHERE seq = ?3",
+                params![seal("details", details)?, seal("metadata", metadata)?, seq],
+            ).map_err(sqlite_error)?;
+        }
+        transaction.commit().map_err(sqlite_error)?;
+        Ok(rows.len())
+    }
+
+    /// Add one entry to an account's log and prune entries past retention
+    pub fn insert(&self, account_id: &str, entry: &LogEntry) -> Result<(), AppError> {
+        let row = self.row(account_id, entry)?;
+        let cutoff = (Utc::now() - chrono::Duration::days(RETENTION_DAYS)).timestamp_millis();
+
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        row.insert(&transaction, account_id)?;
+        transaction.execute("DELETE FROM log_entries WHERE timestamp_ms < ?1", params![cutoff])
+            .map_err(sqlite_error)?;
+        transaction.commit().map_err(sqlite_error)
+    }
+
+    /// Add entries in one transaction, skipping ones already present; returns the number added
+    pub fn insert_batch(&self, account_id: &str, entries: &[LogEntry]) -> Result<usize, AppError> {
+        let rows = entries.iter().map(|entry| self.row(account_id, entry)).collect::<Result<Vec<_>, _>>()?;
+
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        let mut inserted = 0;
+        for row in &rows {
+            inserted += row.insert(&transaction, account_id)?;
+        }
+        transaction.commit().map_err(sqlite_error)?;
+        Ok(inserted)
+    }
+
+    /// Entries of an account matching `query`, newest first
+    pub fn query(&self, account_id: &str, query: &LogQuery) -> Result<LogPage, AppError> {
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+        let mut conditions = vec!["account_id = ?".to_string()];
+        let mut values = vec![Value::Text(account_id.to_string())];
+        if let Some(action) = &query.action {
+            conditions.push("action = ?".to_string());
+            values.push(Value::Text(enum_name(action)));
+        }
+        if let Some(status) = &query.status {
+            conditions.push("status = ?".to_string());
+            values.push(Value::Text(enum_name(status)));
+        }
+        if let Some(from) = query.from {
+            conditions.push("timestamp_ms >= ?".to_string());
+            values.push(Value::Integer(from.timestamp_millis()));
+        }
+        if let Some(to) = query.to {
+            conditions.push("timestamp_ms < ?".to_string());
+            values.push(Value::Integer(to.timestamp_millis()));
+        }
+        let text = query.text.as_deref().map(str::trim).filter(|text| !text.is_empty());
+
+        // Encrypted details cannot be matched in SQL: decrypt the candidates and page here
+        if let (Some(text), Some(_)) = (text, &self.cipher) {
+            let needle = text.to_lowercase();
+            let matching: Vec<LogEntry> = self.fetch(account_id, &conditions.join(" AND "), values, None)?
+                .into_iter()
+                .filter(|entry| entry.details.to_lowercase().contains(&needle))
+                .collect();
+            let total = matching.len();
+            let entries = matching.into_iter().skip(offset).take(limit).collect();
+            return Ok(LogPage { entries, total, offset, limit });
+        }
+
+        if let Some(text) = text {
+            conditions.push("details LIKE ? ESCAPE '\\'".to_string());
+            values.push(Value::Text(format!("%{}%", escape_like(text))));
+        }
+        let filter = conditions.join(" AND ");
+
+        let total: i64 = self.connection.lock().unwrap().query_row(
+            &format!("SELECT COUNT(*) FROM log_entries WHERE {}", filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        ).map_err(sqlite_error)?;
+        let entries = self.fetch(account_id, &filter, values, Some((limit, offset)))?;
+
+        Ok(LogPage { entries, total: total as usize, offset, limit })
+    }
+
+    /// Entries matching `filter`, newest first, optionally one `(limit, offset)` page of them
+    fn fetch(&self, account_id: &str, filter: &str, mut values: Vec<Value>, page: Option<(usize, usize)>) -> Result<Vec<LogEntry>, AppError> {
+        let mut sql = format!(
+            "SELECT id, timestamp, action, status, details, metadata FROM log_entries
+             WHERE {} ORDER BY timestamp_ms DESC, seq DESC",
+            filter
+        );
+        if let Some((limit, offset)) = page {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            values.push(Value::Integer(limit as i64));
+            values.push(Value::Integer(offset as i64));
+        }
+
+        let rows = {
+            let connection = self.connection.lock().unwrap();
+            let mut statement = connection.prepare(&sql).map_err(sqlite_error)?;
+            let rows = statement.query_map(params_from_iter(values.iter()), |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get::<_, String>(5)?))
+            }).map_err(sqlite_error)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(sqlite_error)?
+        };
+
+        rows.into_iter()
+            .map(|(id, timestamp, action, status, details, metadata)| {
+                let details = self.unseal(account_id, &id, "details", details)?;
+                let metadata = self.unseal(account_id, &id, "metadata", metadata)?;
+                Ok(LogEntry {
+                    timestamp,
+                    action: parse_enum(action)?,
+                    status: parse_enum(status)?,
+                    details,
+                    metadata: serde_json::from_str(&metadata)
+                        .map_err(|e| AppError::storage(format!("Invalid log metadata: {}", e)))?,
+                    id,
+                })
+            })
+            .collect()
+    }
+
+    /// Every entry of an account, newest first
+    pub fn all_entries(&self, account_id: &str) -> Result<Vec<LogEntry>, AppError> {
+        let mut entries = Vec::new();
+        loop {
+            let query = LogQuery { offset: Some(entries.len()), limit: Some(MAX_PAGE_SIZE), ..LogQuery::default() };
+            let page = self.query(account_id, &query)?;
+            let done = page.entries.len() < MAX_PAGE_SIZE;
+            entries.extend(page.entries);
+            if done {
+                return Ok(entries);
+            }
+        }
+    }
+
+    /// Remove every entry of an account; returns the number removed
+    pub fn clear(&self, account_id: &str) -> Result<u32, AppError> {
+        let deleted = self.connection.lock().unwrap()
+            .execute("DELETE FROM log_entries WHERE account_id = ?1", params![account_id])
+            .map_err(sqlite_error)?;
+        Ok(deleted as u32)
+    }
+}
+
+/// Bring the schema to the current version, backing up a database that has
+/// an older one (`path` is `None` for in-memory databases)
+fn migrate(connection: &mut Connection, path: Option<&Path>) -> Result<(), AppError> {
+    let current = MIGRATIONS.len() as u32;
+    let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(sqlite_error)?;
+    if version > current {
+        return Err(AppError::storage(format!(
+            "The activity log database has schema version {}, but this version only supports up to {}", version, current
+        )));
+    }
+    if version == current {
+        return Ok(());
+    }
+
+    if let Some(path) = path.filter(|_| version > 0) {
+        let backup = format!("{}.v{}.bak", path.display(), version);
+        connection.execute("VACUUM INTO ?1", params![backup])
+            .map_err(|e| AppError::storage(format!("Failed to back up the activity log database to '{}': {}", backup, e)))?;
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        transaction.execute_batch(migration)
+            .and_then(|_| transaction.pragma_update(None, "user_version", from as u32 + 1))
+            .and_then(|_| transaction.commit())
+            .map_err(|e| AppError::storage(format!(
+                "Failed to migrate the activity log database from schema version {} to {}: {}", from, from + 1, e
+            )))?;
+    }
+    Ok(())
+}
+
+/// A log entry in its column representation
+struct EntryRow<'a> {
+    entry: &'a LogEntry,
+    timestamp_ms: i64,
+    /// Sealed details and metadata
+    details: String,
+    metadata: String,
+}
+
+impl EntryRow<'_> {
+    /// Insert unless the entry is already present; returns the number of rows added
+    fn insert(&self, connection: &Connection, account_id: &str) -> Result<usize, AppError> {
+        connection.execute(
+            "INSERT OR IGNORE INTO log_entries (account_id, id, timestamp, timestamp_ms, action, status, details, metadata)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                account_id,
+                self.entry.id,
+                self.entry.timestamp,
+                self.timestamp_ms,
+                enum_name(&self.entry.action),
+                enum_name(&self.entry.status),
+                self.details,
+                self.metadata,
+            ],
+        ).map_err(sqlite_error)
+    }
+}
+
+/// Managed state holding the log store opened at startup
+struct LogStoreState(Arc<LogStore>);
+
+/// Make `store` the app's log store (called once during setup)
+pub fn manage_log_store(app_handle: &AppHandle, store: LogStore) {
+    app_handle.manage(LogStoreState(Arc::new(store)));
+}
+
+/// The app's log store
+pub fn get_log_store(app_handle: &AppHandle) -> Result<Arc<LogStore>, AppError> {
+    app_handle.try_state::<LogStoreState>()
+        .map(|state| state.0.clone())
+        .ok_or_else(|| AppError::storage("Activity log is not initialized yet"))
+}
+
+/// Move the monthly log containers of every account from storage into the
+/// store; returns the number of entries imported
+pub async fn import_legacy_logs(app_handle: &AppHandle) -> Result<usize, AppError> {
+    let store = get_log_store(app_handle)?;
+    let storage = get_storage(app_handle)?;
+    let keys = storage.list_keys().await?;
+
+    let mut imported = 0;
+    for account in crate::accounts::list() {
+        let prefix = storage_key(&account.id, "logs_");
+        for key in keys.iter().filter(|key| key.strip_prefix(&prefix).is_some_and(is_month_key)) {
+            let Some(json) = storage.retrieve(key).await? else {
+                continue;
+            };
+            match serde_json::from_str::<MonthlyLogContainer>(&json) {
+                Ok(container) => {
+                    let (valid, invalid): (Vec<_>, Vec<_>) = container.entries.into_iter()
+                        .partition(|entry| DateTime::parse_from_rfc3339(&entry.timestamp).is_ok());
+                    if !invalid.is_empty() {
+                        log::warn!("Skipping {} log entries with invalid timestamps in '{}'", invalid.len(), key);
+                    }
+                    imported += store.insert_batch(&account.id, &valid)?;
+                }
+                Err(e) => log::warn!("Skipping unreadable log container '{}': {}", key, e),
+            }
+            storage.store(&backup_key(key, 0), &json).await?;
+            storage.delete(key).await?;
+        }
+    }
+    Ok(imported)
+}
+
+/// Whether `suffix` is a container month (`YYYY_MM`)
+fn is_month_key(suffix: &str) -> bool {
+    suffix.split_once('_').is_some_and(|(year, month)| {
+        year.len() == 4 && year.parse::<i32>().is_ok() && month.parse::<u32>().is_ok_and(|month| (1..=12).contains(&month))
+    })
+}
+
+/// Name a column value is bound to when encrypted, so values cannot be moved between rows or columns
+fn column_name(account_id: &str, id: &str, column: &str) -> String {
+    format!("log_entries/{}/{}/{}", account_id, id, column)
+}
+
+/// Serialized name of an action or status (`clock_in`, `failed`, ...)
+fn enum_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value).ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse_enum<T: DeserializeOwned>(name: String) -> Result<T, AppError> {
+    serde_json::from_value(serde_json::Value::String(name.clone()))
+        .map_err(|_| AppError::storage(format!("Unknown log value '{}'", name)))
+}
+
+/// Escape `%`, `_` and `\` for a `LIKE ... ESCAPE '\'` pattern
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn sqlite_error(e: rusqlite::Error) -> AppError {
+    AppError::storage(format!("SQLite error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::LogMetadata;
+
+    fn entry(id: &str, timestamp: &str, action: LogAction, status: LogStatus, details: &str) -> LogEntry {
+        LogEntry {
+            id: id.to_string(),
+            timestamp: timestamp.to_string(),
+            action,
+            status,
+            details: details.to_string(),
+            metadata: LogMetadata { duration: Some(120), trigger_type: None, api_endpoint: None, error_code: None },
+        }
+    }
+
+    #[test]
+    fn test_log_store_queries() {
+        let store = LogStore::open_in_memory(None).unwrap();
+        let entries = vec![
+            entry("a", "2026-09-01T08:00:00+00:00", LogAction::ClockIn, LogStatus::Success, "Clock-in completed"),
+            entry("b", "2026-09-01T17:00:00+00:00", LogAction::ClockOut, LogStatus::Failed, "Clock-out failed: 100% timeout"),
+            entry("c", "2026-09-02T08:00:00+00:00", LogAction::ClockIn, LogStatus::Failed, "Clock-in failed: timeout"),
+        ];
+        assert_eq!(store.insert_batch("default", &entries).unwrap(), 3);
+        // Importing again adds nothing
+        assert_eq!(store.insert_batch("default", &entries).unwrap(), 0);
+        store.insert_batch("other", &entries[..1]).unwrap();
+
+        let page = store.query("default", &LogQuery::default()).unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["c", "b", "a"]);
+        assert_eq!(page.entries[0].metadata.duration, Some(120));
+
+        let failed = LogQuery { status: Some(LogStatus::Failed), limit: Some(1), offset: Some(1), ..LogQuery::default() };
+        let page = store.query("default", &failed).unwrap();
+        assert_eq!((page.total, page.entries[0].id.as_str()), (2, "b"));
+
+        let first_day = LogQuery {
+            from: Some("2026-09-01T00:00:00Z".parse().unwrap()),
+            to: Some("2026-09-02T00:00:00Z".parse().unwrap()),
+            action: Some(LogAction::ClockIn),
+            ..LogQuery::default()
+        };
+        assert_eq!(store.query("default", &first_day).unwrap().entries[0].id, "a");
+
+        let text = |text: &str| LogQuery { text: Some(text.to_string()), ..LogQuery::default() };
+        assert_eq!(store.query("default", &text("TIMEOUT")).unwrap().total, 2);
+        assert_eq!(store.query("default", &text("100%")).unwrap().total, 1);
+        assert_eq!(store.query("default", &text("1_0")).unwrap().total, 0);
+
+        assert_eq!(store.clear("default").unwrap(), 3);
+        assert_eq!(store.query("other", &LogQuery::default()).unwrap().total, 1);
+    }
+
+    #[test]
+    fn test_encrypts_details_and_metadata() {
+        let cipher = Arc::new(StorageCipher::from_passphrase("log test passphrase", &[2u8; 16]).unwrap());
+        let entries = [
+            entry("a", "2026-09-01T08:00:00+00:00", LogAction::ClockIn, LogStatus::Success, "Clock-in for Jane at 08:00"),
+            entry("b", "2026-09-01T17:00:00+00:00", LogAction::ClockOut, LogStatus::Failed, "Clock-out failed: timeout"),
+        ];
+
+        // Plaintext rows of an earlier version are encrypted on open
+        let plaintext = LogStore::open_in_memory(None).unwrap();
+        plaintext.insert_batch("default", &entries[..1]).unwrap();
+        let connection = plaintext.connection.into_inner().unwrap();
+        let store = LogStore::with_connection(connection, None, Some(cipher)).unwrap();
+        store.insert_batch("default", &entries[1..]).unwrap();
+
+        let stored: Vec<(String, String)> = {
+            let connection = store.connection.lock().unwrap();
+            let mut statement = connection.prepare("SELECT details, metadata FROM log_entries").unwrap();
+            let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+            rows.collect::<Result<_, _>>().unwrap()
+        };
+        assert!(stored.iter().all(|(details, metadata)| is_encrypted(details) && is_encrypted(metadata)));
+        assert!(!stored.iter().any(|(details, _)| details.contains("Jane")));
+
+        let page = store.query("default", &LogQuery::default()).unwrap();
+        assert_eq!(page.entries[1].details, "Clock-in for Jane at 08:00");
+        assert_eq!(page.entries[1].metadata.duration, Some(120));
+
+        let text = LogQuery { text: Some("JANE".to_string()), ..LogQuery::default() };
+        let page = store.query("default", &text).unwrap();
+        assert_eq!((page.total, page.entries[0].id.as_str()), (1, "a"));
+    }
+
+    #[test]
+    fn test_refuses_newer_schema() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrate(&mut connection, None).unwrap();
+        assert_eq!(connection.pragma_query_value(None, "user_version", |row| row.get::<_, u32>(0)).unwrap(), 1);
+
+        connection.pragma_update(None, "user_version", 2).unwrap();
+        assert!(LogStore::with_connection(connection, None, None).is_err());
+    }
+
+    #[test]
+    fn test_month_keys() {
+        assert!(is_month_key("2025_10"));
+        assert!(!is_month_key("2025_13"));
+        assert!(!is_month_key("backup"));
+    }
+}
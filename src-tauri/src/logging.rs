@@ -1,8 +1,9 @@
-use chrono::{DateTime, Utc, TimeZone, Datelike};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use crate::errors::AppError;
-use crate::storage::create_storage_backend;
+use crate::log_store::{get_log_store, LogPage, LogQuery};
+use crate::redact::redact;
 
 /// Log entry representing a single app operation or event
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +34,9 @@ pub enum LogAction {
     WakeDetected,
     ScheduleUpdated,
     AppStartup,
+    AppShutdown,
+    ClockSkew,
+    Logout,
     Error,
 }
 
@@ -60,7 +64,7 @@ pub struct LogMetadata {
     pub error_code: Option<String>,
 }
 
-/// Monthly log container with auto-cleanup
+/// Monthly log container of earlier versions (imported into the log store on first run)
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MonthlyLogContainer {
@@ -79,16 +83,29 @@ pub struct MonthlyLogContainer {
 /// Logger service for managing structured activity logs
 pub struct ActivityLogger {
     app_handle: AppHandle,
+    /// Account whose log this writes to
+    account_id: String,
 }
 
 impl ActivityLogger {
-    /// Create a new activity logger instance
+    /// Create a new activity logger instance (the default account's log)
     pub fn new(app_handle: AppHandle) -> Self {
-        Self { app_handle }
+        Self::for_account(app_handle, crate::accounts::DEFAULT_ACCOUNT_ID)
     }
 
-    /// Log a new activity entry
-    pub async fn log(&self, action: LogAction, status: LogStatus, details: String, metadata: LogMetadata) -> Result<(), AppError> {
+    /// Create a logger writing to one account's log
+    pub fn for_account(app_handle: AppHandle, account_id: &str) -> Self {
+        Self {
+            app_handle,
+            account_id: account_id.to_string(),
+        }
+    }
+
+    /// Log a new activity entry (details and error codes are redacted before storage)
+    pub async fn log(&self, action: LogAction, status: LogStatus, details: String, mut metadata: LogMetadata) -> Result<(), AppError> {
+        let details = redact(&details);
+        metadata.error_code = metadata.error_code.map(|code| redact(&code));
+
         let now = Utc::now();
         let log_id = format!("log_{}_{:03}", now.timestamp(), now.timestamp_subsec_millis() % 1000);
 
@@ -101,7 +118,7 @@ impl ActivityLogger {
             metadata,
         };
 
-        self.add_entry_to_monthly_log(entry).await
+        get_log_store(&self.app_handle)?.insert(&self.account_id, &entry)
     }
 
     /// Convenience method to log clock-in operations
@@ -142,6 +159,24 @@ impl ActivityLogger {
         self.log(LogAction::ClockOut, status, details, metadata).await
     }
 
+    /// Convenience method to log clock actions the server did not confirm
+    pub async fn log_clock_unverified(&self, action: LogAction, operation_id: &str, reason: &str) -> Result<(), AppError> {
+        let (label, api_endpoint) = match action {
+            LogAction::ClockOut => ("Clock-out", "/dtr/attendance/logout"),
+            _ => ("Clock-in", "/dtr/attendance/login"),
+        };
+        let details = format!("{} accepted by API but not confirmed by attendance (operation: {}): {}", label, operation_id, reason);
+
+        let metadata = LogMetadata {
+            duration: None,
+            trigger_type: Some("verification".to_string()),
+            api_endpoint: Some(api_endpoint.to_string()),
+            error_code: Some("unverified".to_string()),
+        };
+
+        self.log(action, LogStatus::Warning, details, metadata).await
+    }
+
     /// Convenience method to log attendance checks
     pub async fn log_attendance_check(&self, success: bool, duration_ms: Option<u64>, error: Option<&str>) -> Result<(), AppError> {
         let status = if success { LogStatus::Success } else { LogStatus::Failed };
@@ -180,6 +215,66 @@ impl ActivityLogger {
         self.log(LogAction::TokenRefresh, status, details, metadata).await
     }
 
+    /// Convenience method to log a refresh token that is about to expire
+    pub async fn log_refresh_token_expiring(&self, expires_at: &str, expires_in_secs: i64) -> Result<(), AppError> {
+        let details = if expires_in_secs <= 0 {
+            format!("Refresh token expired at {}; token setup has to be redone", expires_at)
+        } else {
+            format!(
+                "Refresh token expires at {} ({} hours left); token setup will have to be redone after that",
+                expires_at, expires_in_secs / 3600
+            )
+        };
+
+        let metadata = LogMetadata {
+            duration: None,
+            trigger_type: Some("token_expiry_check".to_string()),
+            api_endpoint: None,
+            error_code: Some("refresh_token_expiring".to_string()),
+        };
+
+        self.log(LogAction::TokenRefresh, LogStatus::Warning, details, metadata).await
+    }
+
+    /// Convenience method to log a local clock that drifted from the EMAPTA server
+    pub async fn log_clock_skew(&self, offset_ms: i64, sample_count: usize) -> Result<(), AppError> {
+        let direction = if offset_ms > 0 { "behind" } else { "ahead of" };
+        let details = format!(
+            "Local clock is {} seconds {} the EMAPTA server (based on {} responses)",
+            offset_ms.abs() / 1000, direction, sample_count
+        );
+
+        let metadata = LogMetadata {
+            duration: None,
+            trigger_type: Some("clock_skew_check".to_string()),
+            api_endpoint: None,
+            error_code: Some(format!("offset_ms={}", offset_ms)),
+        };
+
+        self.log(LogAction::ClockSkew, LogStatus::Warning, details, metadata).await
+    }
+
+    /// Convenience method to log a sign-out
+    pub async fn log_logout(&self, revoked: bool, revocation_error: Option<&str>) -> Result<(), AppError> {
+        let (status, details) = match revocation_error {
+            None if revoked => (LogStatus::Success, "Signed out; refresh token revoked and tokens removed".to_string()),
+            None => (LogStatus::Info, "Signed out; no stored tokens to revoke".to_string()),
+            Some(error) => (
+                LogStatus::Warning,
+                format!("Signed out and tokens removed, but revoking the refresh token failed: {}", error),
+            ),
+        };
+
+        let metadata = LogMetadata {
+            duration: None,
+            trigger_type: Some("manual".to_string()),
+            api_endpoint: Some("/auth/v1/auth/protocol/openid-connect/revoke".to_string()),
+            error_code: revocation_error.map(|e| e.to_string()),
+        };
+
+        self.log(LogAction::Logout, status, details, metadata).await
+    }
+
     /// Convenience method to log wake detection events
     pub async fn log_wake_detected(&self, gap_seconds: u64) -> Result<(), AppError> {
         let details = format!("System wake detected after {} seconds of inactivity", gap_seconds);
@@ -222,196 +317,38 @@ impl ActivityLogger {
         self.log(LogAction::AppStartup, status, details, metadata).await
     }
 
-    /// Get recent log entries (up to limit, defaulting to 100)
-    pub async fn get_recent_entries(&self, limit: Option<usize>) -> Result<Vec<LogEntry>, AppError> {
-        let limit = limit.unwrap_or(100);
-        let current_month = get_current_month_key();
-
-        // Get current month's logs
-        let mut all_entries = Vec::new();
-
-        if let Ok(Some(current_container)) = self.get_monthly_container(&current_month).await {
-            // Get most recent entries from current month
-            let mut current_entries = current_container.entries;
-            current_entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)); // Most recent first
-            all_entries.extend(current_entries);
-        }
-
-        // If we need more entries, get from previous month
-        if all_entries.len() < limit {
-            let prev_month = get_previous_month_key(&current_month);
-            if let Ok(Some(prev_container)) = self.get_monthly_container(&prev_month).await {
-                let mut prev_entries = prev_container.entries;
-                prev_entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)); // Most recent first
-                all_entries.extend(prev_entries);
-            }
-        }
-
-        // Sort all entries by timestamp (most recent first) and limit
-        all_entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        all_entries.truncate(limit);
-
-        Ok(all_entries)
-    }
-
-    /// Get log entries filtered by action and/or status
-    pub async fn get_filtered_entries(&self, action_filter: Option<LogAction>, status_filter: Option<LogStatus>, limit: Option<usize>) -> Result<Vec<LogEntry>, AppError> {
-        let all_entries = self.get_recent_entries(limit).await?;
-
-        let filtered = all_entries.into_iter()
-            .filter(|entry| {
-                if let Some(ref action) = action_filter {
-                    if std::mem::discriminant(&entry.action) != std::mem::discriminant(action) {
-                        return false;
-                    }
-                }
-                if let Some(ref status) = status_filter {
-                    if std::mem::discriminant(&entry.status) != std::mem::discriminant(status) {
-                        return false;
-                    }
-                }
-                true
-            })
-            .collect();
-
-        Ok(filtered)
-    }
-
-    /// Clear all log entries (for maintenance)
-    pub async fn clear_all_logs(&self) -> Result<u32, AppError> {
-        let storage = create_storage_backend(self.app_handle.clone())?;
-        let keys = storage.list_keys().await?;
-
-        let mut deleted_count = 0;
-
-        for key in keys {
-            if key.starts_with("logs_") {
-                storage.delete(&key).await?;
-                deleted_count += 1;
-            }
-        }
-
-        Ok(deleted_count)
-    }
-
-    /// Add entry to monthly log container with auto-cleanup
-    async fn add_entry_to_monthly_log(&self, entry: LogEntry) -> Result<(), AppError> {
-        let month_key = get_current_month_key();
-        let storage_key = format!("logs_{}", month_key);
-
-        // Get or create monthly container
-        let mut container = match self.get_monthly_container(&month_key).await? {
-            Some(container) => container,
-            None => MonthlyLogContainer {
-                month_key: month_key.clone(),
-                entries: Vec::new(),
-                total_entries: 0,
-                created_at: Utc::now().to_rfc3339(),
-                updated_at: Utc::now().to_rfc3339(),
-            }
+    /// Convenience method to log the outcome of the quit workflow
+    pub async fn log_app_shutdown(&self, status: LogStatus, details: String) -> Result<(), AppError> {
+        let metadata = LogMetadata {
+            duration: None,
+            trigger_type: Some("app_shutdown".to_string()),
+            api_endpoint: None,
+            error_code: None,
         };
 
-        // Add new entry
-        container.entries.push(entry);
-        container.total_entries += 1;
-        container.updated_at = Utc::now().to_rfc3339();
-
-        // Auto-cleanup: keep only the most recent 1000 entries per month
-        if container.entries.len() > 1000 {
-            // Sort by timestamp (most recent first) and keep only 1000
-            container.entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-            container.entries.truncate(1000);
-        }
-
-        // Save updated container
-        let storage = create_storage_backend(self.app_handle.clone())?;
-        let container_json = serde_json::to_string(&container)
-            .map_err(|e| AppError::storage(&format!("Failed to serialize log container: {}", e)))?;
-
-        storage.store(&storage_key, &container_json).await?;
-
-        // Clean up old months (keep only 6 months)
-        self.cleanup_old_months().await?;
-
-        Ok(())
-    }
-
-    /// Get monthly log container from storage
-    async fn get_monthly_container(&self, month_key: &str) -> Result<Option<MonthlyLogContainer>, AppError> {
-        let storage_key = format!("logs_{}", month_key);
-        let storage = create_storage_backend(self.app_handle.clone())?;
-
-        if let Some(container_json) = storage.retrieve(&storage_key).await? {
-            let container: MonthlyLogContainer = serde_json::from_str(&container_json)
-                .map_err(|e| AppError::storage(&format!("Failed to deserialize log container: {}", e)))?;
-            Ok(Some(container))
-        } else {
-            Ok(None)
-        }
+        self.log(LogAction::AppShutdown, status, details, metadata).await
     }
 
-    /// Clean up log containers older than 6 months
-    async fn cleanup_old_months(&self) -> Result<(), AppError> {
-        let storage = create_storage_backend(self.app_handle.clone())?;
-        let keys = storage.list_keys().await?;
-
-        let now = Utc::now();
-        let six_months_ago = now - chrono::Duration::days(6 * 30); // Approximate 6 months
-
-        for key in keys {
-            if key.starts_with("logs_") {
-                let month_key = &key[5..]; // Remove "logs_" prefix
-
-                if let Some(month_date) = parse_month_key(month_key) {
-                    if month_date < six_months_ago {
-                        println!("[Logging] Cleaning up old log container: {}", key);
-                        storage.delete(&key).await?;
-                    }
-                }
-            }
-        }
-
-        Ok(())
+    /// Get recent log entries (up to limit, defaulting to 100)
+    pub async fn get_recent_entries(&self, limit: Option<usize>) -> Result<Vec<LogEntry>, AppError> {
+        let query = LogQuery { limit, ..LogQuery::default() };
+        Ok(self.query(&query).await?.entries)
     }
-}
-
-/// Get current month key in format "YYYY_MM"
-fn get_current_month_key() -> String {
-    let now = Utc::now();
-    format!("{}_{:02}", now.year(), now.month())
-}
 
-/// Get previous month key from a given month key
-fn get_previous_month_key(current_month: &str) -> String {
-    if let Some((year, month)) = parse_month_key_parts(current_month) {
-        if month == 1 {
-            format!("{}_{:02}", year - 1, 12)
-        } else {
-            format!("{}_{:02}", year, month - 1)
-        }
-    } else {
-        // Fallback to current month if parsing fails
-        get_current_month_key()
+    /// Get log entries filtered by action and/or status
+    pub async fn get_filtered_entries(&self, action_filter: Option<LogAction>, status_filter: Option<LogStatus>, limit: Option<usize>) -> Result<Vec<LogEntry>, AppError> {
+        let query = LogQuery { action: action_filter, status: status_filter, limit, ..LogQuery::default() };
+        Ok(self.query(&query).await?.entries)
     }
-}
 
-/// Parse month key into year and month numbers
-fn parse_month_key_parts(month_key: &str) -> Option<(i32, u32)> {
-    let parts: Vec<&str> = month_key.split('_').collect();
-    if parts.len() == 2 {
-        if let (Ok(year), Ok(month)) = (parts[0].parse::<i32>(), parts[1].parse::<u32>()) {
-            return Some((year, month));
-        }
+    /// Get a page of log entries matching the query (newest first)
+    pub async fn query(&self, query: &LogQuery) -> Result<LogPage, AppError> {
+        get_log_store(&self.app_handle)?.query(&self.account_id, query)
     }
-    None
-}
 
-/// Parse month key into a DateTime for comparison
-fn parse_month_key(month_key: &str) -> Option<DateTime<Utc>> {
-    if let Some((year, month)) = parse_month_key_parts(month_key) {
-        Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
-    } else {
-        None
+    /// Clear all log entries (for maintenance); returns the number of entries removed
+    pub async fn clear_all_logs(&self) -> Result<u32, AppError> {
+        get_log_store(&self.app_handle)?.clear(&self.account_id)
     }
 }
 
@@ -431,9 +368,9 @@ pub fn initialize_logger(app_handle: AppHandle) {
 /// Force re-initialize the global activity logger (for debugging)
 pub fn force_reinitialize_logger(app_handle: AppHandle) {
     unsafe {
-        println!("[LOGGING] Force re-initializing logger...");
+        log::info!("Force re-initializing logger...");
         LOGGER = Some(ActivityLogger::new(app_handle));
-        println!("[LOGGING] Logger force re-initialized");
+        log::info!("Logger force re-initialized");
     }
 }
 
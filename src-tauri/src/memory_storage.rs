@@ -0,0 +1,78 @@
+/*!
+ * Memory Storage
+ *
+ * In-memory `Storage` for tests: nothing touches the disk and every
+ * instance starts empty. Batches apply under one lock, so readers never
+ * see half of one.
+ */
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use async_trait::async_trait;
+
+use crate::commands::{StorageError, StorageResult};
+use crate::storage::Storage;
+
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    values: Mutex<BTreeMap<String, String>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn store(&self, key: &str, data: &str) -> Result<StorageResult, StorageError> {
+        self.values.lock().unwrap().insert(key.to_string(), data.to_string());
+        Ok(StorageResult {
+            success: true,
+            message: format!("Data stored in memory: {}", key),
+        })
+    }
+
+    async fn store_batch(&self, entries: &[(&str, &str)]) -> Result<StorageResult, StorageError> {
+        let mut values = self.values.lock().unwrap();
+        for (key, data) in entries {
+            values.insert(key.to_string(), data.to_string());
+        }
+        Ok(StorageResult {
+            success: true,
+            message: format!("Stored {} keys in memory", entries.len()),
+        })
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<Option<String>, StorageError> {
+        Ok(self.values.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<StorageResult, StorageError> {
+        let existed = self.values.lock().unwrap().remove(key).is_some();
+        Ok(StorageResult {
+            success: true,
+            message: if existed { format!("Data deleted: {}", key) } else { "Key not found (already deleted)".to_string() },
+        })
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>, StorageError> {
+        Ok(self.values.lock().unwrap().keys().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::exercise_storage;
+
+    #[tokio::test]
+    async fn test_memory_storage() {
+        exercise_storage(&MemoryStorage::new()).await;
+    }
+}
@@ -0,0 +1,340 @@
+/*!
+ * Mock EMAPTA Provider
+ *
+ * In-memory `EmaptaProvider` that keeps one day of attendance and issues
+ * JWT-shaped tokens, so the scheduler can run without an EMAPTA account.
+ * Behaviour is scripted through `MockScript`:
+ * - Artificial latency on every call
+ * - The next N clock/attendance calls rejected with 401 (exercises token refresh)
+ * - The next N calls of any kind failing with 503 (exercises retries)
+ * - The next N clock calls recorded but answered with 504 (a lost response)
+ * - Rest days and leave days, refused refresh tokens
+ *
+ * Demo mode picks a script by scenario name (`MockScript::scenario`), so
+ * these behaviours can be tried in the app as well as in tests.
+ *
+ * Any token is accepted, so demo mode works with whatever is entered in setup.
+ */
+
+use std::sync::Mutex;
+use std::time::Duration;
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Local};
+
+use crate::commands::{AttendanceItem, TokenResponse};
+use crate::errors::EmaptaApiError;
+use crate::provider::EmaptaProvider;
+
+const ACCESS_TOKEN_LIFETIME_SECS: i64 = 15 * 60;
+const REFRESH_TOKEN_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+const DEMO_LATENCY: Duration = Duration::from_millis(300);
+const SLOW_DEMO_LATENCY: Duration = Duration::from_secs(3);
+/// Number of scripted failures in the `unauthorized` and `outage` demo scenarios
+const DEMO_FAILED_CALLS: u32 = 2;
+
+/// Demo scenarios accepted by `MockScript::scenario`
+pub const DEMO_SCENARIOS: [&str; 7] = ["default", "slow", "unauthorized", "outage", "restday", "leave", "expired"];
+
+/// Scripted behaviour of the mock
+#[derive(Debug, Clone, Default)]
+pub struct MockScript {
+    /// Delay applied to every call
+    pub latency: Duration,
+    /// Number of upcoming clock/attendance calls answered with 401
+    pub unauthorized_calls: u32,
+    /// Number of upcoming calls (any kind) answered with 503
+    pub server_error_calls: u32,
+    /// Number of upcoming clock calls that are recorded but answered with 504
+    pub lost_response_calls: u32,
+    /// Attendance reports a rest day and clock-in is refused
+    pub restday: bool,
+    /// Attendance reports leave and clock-in is refused
+    pub on_leave: bool,
+    /// Token exchange fails with `invalid_grant`
+    pub reject_refresh: bool,
+}
+
+impl MockScript {
+    /// Script of a named demo scenario (see `DEMO_SCENARIOS`), `None` for unknown names
+    pub fn scenario(name: &str) -> Option<Self> {
+        let demo = Self { latency: DEMO_LATENCY, ..Self::default() };
+        let script = match name.trim().to_lowercase().as_str() {
+            "" | "default" => demo,
+            "slow" => Self { latency: SLOW_DEMO_LATENCY, ..demo },
+            "unauthorized" => Self { unauthorized_calls: DEMO_FAILED_CALLS, ..demo },
+            "outage" => Self { server_error_calls: DEMO_FAILED_CALLS, ..demo },
+            "restday" => Self { restday: true, ..demo },
+            "leave" => Self { on_leave: true, ..demo },
+            "expired" => Self { reject_refresh: true, ..demo },
+            _ => return None,
+        };
+        Some(script)
+    }
+}
+
+/// Number of calls the mock has received, per operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MockCallCounts {
+    pub token_exchange: u32,
+    pub clock_in: u32,
+    pub clock_out: u32,
+    pub attendance: u32,
+    pub revocation: u32,
+}
+
+#[derive(Default)]
+struct MockState {
+    script: MockScript,
+    calls: MockCallCounts,
+    clocked_in_at: Option<DateTime<Local>>,
+    clocked_out_at: Option<DateTime<Local>>,
+    token_generation: u64,
+}
+
+/// In-memory EMAPTA stand-in
+#[derive(Default)]
+pub struct MockEmaptaProvider {
+    state: Mutex<MockState>,
+}
+
+impl MockEmaptaProvider {
+    pub fn new(script: MockScript) -> Self {
+        Self {
+            state: Mutex::new(MockState { script, ..MockState::default() }),
+        }
+    }
+
+    /// Mock running a demo scenario; unknown names fall back to the default scenario
+    pub fn demo(scenario: &str) -> Self {
+        let script = MockScript::scenario(scenario).unwrap_or_else(|| {
+            log::warn!("Unknown demo scenario '{}' (expected one of {}), using the default", scenario, DEMO_SCENARIOS.join(", "));
+            MockScript { latency: DEMO_LATENCY, ..MockScript::default() }
+        });
+        Self::new(script)
+    }
+
+    /// Replace the script; attendance and call counts are kept
+    #[cfg(test)]
+    pub fn set_script(&self, script: MockScript) {
+        self.state.lock().unwrap().script = script;
+    }
+
+    #[cfg(test)]
+    pub fn calls(&self) -> MockCallCounts {
+        self.state.lock().unwrap().calls
+    }
+
+    fn latency(&self) -> Duration {
+        self.state.lock().unwrap().script.latency
+    }
+
+    /// Count the call and apply any scripted failure
+    fn begin_call(&self, count: impl FnOnce(&mut MockCallCounts), requires_auth: bool) -> Result<(), EmaptaApiError> {
+        let mut state = self.state.lock().unwrap();
+        count(&mut state.calls);
+
+        if state.script.server_error_calls > 0 {
+            state.script.server_error_calls -= 1;
+            return Err(EmaptaApiError::Http { status: 503, messages: vec!["Service Unavailable".to_string()] });
+        }
+        if requires_auth && state.script.unauthorized_calls > 0 {
+            state.script.unauthorized_calls -= 1;
+            return Err(EmaptaApiError::Http { status: 401, messages: vec!["Unauthorized".to_string()] });
+        }
+        Ok(())
+    }
+
+    /// Answer a recorded clock call, unless its response is scripted to get lost
+    fn respond(state: &mut MockState) -> Result<bool, EmaptaApiError> {
+        if state.script.lost_response_calls > 0 {
+            state.script.lost_response_calls -= 1;
+            return Err(EmaptaApiError::Http { status: 504, messages: vec!["Gateway Timeout".to_string()] });
+        }
+        Ok(true)
+    }
+
+    fn attendance_item(state: &MockState) -> AttendanceItem {
+        let format = |time: &DateTime<Local>| time.format("%Y-%m-%d %H:%M:%S").to_string();
+        let attendance_status = if state.script.on_leave {
+            "On leave"
+        } else if state.clocked_out_at.is_some() {
+            "Completed"
+        } else if state.clocked_in_at.is_some() {
+            "Started"
+        } else {
+            "Not Started"
+        };
+
+        AttendanceItem {
+            work_date: Local::now().format("%Y-%m-%d").to_string(),
+            attendance_status: attendance_status.to_string(),
+            date_time_in: state.clocked_in_at.as_ref().map(format),
+            date_time_out: state.clocked_out_at.as_ref().map(format),
+            is_restday: Some(state.script.restday),
+        }
+    }
+}
+
+fn rejected(message: &str) -> EmaptaApiError {
+    EmaptaApiError::Http { status: 400, messages: vec![message.to_string()] }
+}
+
+/// Unsigned JWT carrying `iat`/`exp`, so token lifetimes show up in the UI
+fn mock_jwt(kind: &str, generation: u64, lifetime_secs: i64) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let payload = serde_json::json!({
+        "iat": now,
+        "exp": now + lifetime_secs,
+        "sub": "mock-user",
+        "jti": format!("mock-{}-{}", kind, generation),
+    });
+    format!(
+        "{}.{}.mock",
+        URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#),
+        URL_SAFE_NO_PAD.encode(payload.to_string())
+    )
+}
+
+#[async_trait]
+impl EmaptaProvider for MockEmaptaProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn exchange_refresh_token(&self, _refresh_token: &str) -> Result<TokenResponse, EmaptaApiError> {
+        tokio::time::sleep(self.latency()).await;
+        self.begin_call(|calls| calls.token_exchange += 1, false)?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.script.reject_refresh {
+            return Err(EmaptaApiError::Http {
+                status: 400,
+                messages: vec!["invalid_grant".to_string(), "Token is not active".to_string()],
+            });
+        }
+
+        state.token_generation += 1;
+        Ok(TokenResponse {
+            access_token: mock_jwt("access", state.token_generation, ACCESS_TOKEN_LIFETIME_SECS),
+            refresh_token: mock_jwt("refresh", state.token_generation, REFRESH_TOKEN_LIFETIME_SECS),
+            expires_in: Some(ACCESS_TOKEN_LIFETIME_SECS),
+            token_type: "Bearer".to_string(),
+        })
+    }
+
+    async fn clock_in(&self, _access_token: &str) -> Result<bool, EmaptaApiError> {
+        tokio::time::sleep(self.latency()).await;
+        self.begin_call(|calls| calls.clock_in += 1, true)?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.script.restday {
+            return Err(rejected("Cannot clock in on a rest day"));
+        }
+        if state.script.on_leave {
+            return Err(rejected("Cannot clock in while on leave"));
+        }
+        if state.clocked_in_at.is_some() && state.clocked_out_at.is_none() {
+            return Err(rejected("Already clocked in"));
+        }
+
+        state.clocked_in_at = Some(Local::now());
+        state.clocked_out_at = None;
+        Self::respond(&mut state)
+    }
+
+    async fn clock_out(&self, _access_token: &str) -> Result<bool, EmaptaApiError> {
+        tokio::time::sleep(self.latency()).await;
+        self.begin_call(|calls| calls.clock_out += 1, true)?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.clocked_in_at.is_none() || state.clocked_out_at.is_some() {
+            return Err(rejected("Not clocked in"));
+        }
+
+        state.clocked_out_at = Some(Local::now());
+        Self::respond(&mut state)
+    }
+
+    async fn attendance_status(&self, _access_token: &str) -> Result<Option<AttendanceItem>, EmaptaApiError> {
+        tokio::time::sleep(self.latency()).await;
+        self.begin_call(|calls| calls.attendance += 1, true)?;
+
+        let state = self.state.lock().unwrap();
+        Ok(Some(Self::attendance_item(&state)))
+    }
+
+    async fn revoke_refresh_token(&self, _refresh_token: &str) -> Result<(), EmaptaApiError> {
+        tokio::time::sleep(self.latency()).await;
+        self.begin_call(|calls| calls.revocation += 1, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clock_cycle_updates_attendance() {
+        let mock = MockEmaptaProvider::default();
+
+        let before = mock.attendance_status("token").await.unwrap().unwrap();
+        assert_eq!(before.attendance_status, "Not Started");
+
+        assert!(mock.clock_in("token").await.unwrap());
+        assert!(mock.clock_in("token").await.is_err());
+        let started = mock.attendance_status("token").await.unwrap().unwrap();
+        assert_eq!(started.attendance_status, "Started");
+        assert!(started.date_time_in.is_some() && started.date_time_out.is_none());
+
+        assert!(mock.clock_out("token").await.unwrap());
+        let completed = mock.attendance_status("token").await.unwrap().unwrap();
+        assert_eq!(completed.attendance_status, "Completed");
+        assert!(completed.date_time_out.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_scripted_failures() {
+        let mock = MockEmaptaProvider::new(MockScript {
+            unauthorized_calls: 1,
+            server_error_calls: 1,
+            ..MockScript::default()
+        });
+
+        let server_error = mock.clock_in("token").await.unwrap_err();
+        assert!(server_error.into_app_error("clock_in failed").is_retryable());
+        assert!(mock.clock_in("token").await.unwrap_err().requires_token_refresh());
+
+        let tokens = mock.exchange_refresh_token("refresh").await.unwrap();
+        assert!(crate::jwt::decode_claims(&tokens.access_token).unwrap().exp.is_some());
+        assert!(mock.clock_in(&tokens.access_token).await.unwrap());
+        assert_eq!(mock.calls(), MockCallCounts { token_exchange: 1, clock_in: 3, ..MockCallCounts::default() });
+
+        mock.set_script(MockScript { reject_refresh: true, ..MockScript::default() });
+        assert!(mock.exchange_refresh_token("refresh").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restday_and_leave() {
+        let mock = MockEmaptaProvider::new(MockScript { restday: true, ..MockScript::default() });
+        assert_eq!(mock.attendance_status("token").await.unwrap().unwrap().is_restday, Some(true));
+        assert!(mock.clock_in("token").await.is_err());
+
+        mock.set_script(MockScript { on_leave: true, ..MockScript::default() });
+        assert_eq!(mock.attendance_status("token").await.unwrap().unwrap().attendance_status, "On leave");
+        assert!(mock.clock_in("token").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_demo_scenarios() {
+        for name in DEMO_SCENARIOS {
+            assert!(MockScript::scenario(name).is_some(), "{}", name);
+        }
+        assert!(MockScript::scenario("Outage").unwrap().server_error_calls > 0);
+        assert!(MockScript::scenario("nonsense").is_none());
+
+        let mock = MockEmaptaProvider::new(MockScript { latency: Duration::ZERO, ..MockScript::scenario("leave").unwrap() });
+        assert!(mock.clock_in("token").await.is_err());
+    }
+}
@@ -0,0 +1,427 @@
+/*!
+ * Browser Login (OIDC Authorization Code + PKCE)
+ *
+ * Signs the user in against the EMAPTA Keycloak realm instead of having them
+ * copy tokens out of the browser's dev tools:
+ * 1. Bind a one-shot listener on a random 127.0.0.1 port
+ * 2. Open the system browser on the authorization endpoint with a PKCE
+ *    challenge (S256) and a random `state`
+ * 3. Receive the redirect, check `state` and exchange the code plus verifier
+ *    at the token endpoint of the active endpoint profile
+ * 4. Store the tokens through `token_manager::save_initial_tokens`
+ *
+ * Tokens issued to a different user than the one already signed in are not
+ * saved: they wait in a pending slot until the user confirms the switch
+ * (`confirm_pending_login`) or declines it (`discard_pending_login`, which
+ * revokes them). Unanswered pending tokens are revoked after two minutes.
+ *
+ * Demo mode refuses the browser login, since it would reach the live realm.
+ *
+ * The Keycloak client must allow loopback redirect URIs on 127.0.0.1
+ * (loopback redirects may use any port, RFC 8252 section 7.3).
+ */
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri_plugin_opener::OpenerExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::accounts::AccountContext;
+use crate::commands::{EmaptaApiResponse, TokenResponse};
+use crate::endpoints::EndpointProfile;
+use crate::errors::{AppError, EmaptaApiError};
+use crate::provider::EmaptaProvider;
+
+const CALLBACK_PATH: &str = "/callback";
+const LOGIN_SCOPE: &str = "openid";
+/// How long the user has to finish signing in
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Redirect requests are a single GET line plus headers
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+/// Browsers may open idle speculative connections; give up on a request that does not arrive
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const VERIFIER_LENGTH: usize = 64;
+const STATE_LENGTH: usize = 32;
+
+/// Only one login may wait for a redirect at a time
+static LOGIN_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// How long tokens for a different user wait for the user's decision
+const PENDING_LOGIN_TTL: Duration = Duration::from_secs(2 * 60);
+
+/// Tokens of a browser login for a different user, awaiting confirmation
+struct PendingLogin {
+    id: u64,
+    refresh_token: Zeroizing<String>,
+    access_token: Zeroizing<String>,
+}
+
+/// Pending logins by account ID
+static PENDING_LOGINS: Mutex<BTreeMap<String, PendingLogin>> = Mutex::new(BTreeMap::new());
+static NEXT_PENDING_ID: AtomicU64 = AtomicU64::new(0);
+
+/// PKCE verifier and its S256 challenge
+struct Pkce {
+    verifier: Zeroizing<String>,
+    challenge: String,
+}
+
+impl Pkce {
+    fn generate() -> Self {
+        Self::from_verifier(random_string(VERIFIER_LENGTH))
+    }
+
+    fn from_verifier(verifier: String) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self { verifier: Zeroizing::new(verifier), challenge }
+    }
+}
+
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+#[derive(Serialize, Zeroize, ZeroizeOnDrop)]
+struct AuthorizationCodeRequest {
+    grant_type: String,
+    client_id: String,
+    code: String,
+    redirect_uri: String,
+    code_verifier: String,
+}
+
+/// Browser URL that starts the login
+fn authorization_url(profile: &EndpointProfile, redirect_uri: &str, state: &str, challenge: &str) -> Result<String, AppError> {
+    let mut url = reqwest::Url::parse(&profile.authorize_url())
+        .map_err(|e| AppError::configuration(format!("Invalid authorization URL: {}", e)))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &profile.auth_client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("scope", LOGIN_SCOPE)
+        .append_pair("state", state)
+        .append_pair("code_challenge", challenge)
+        .append_pair("code_challenge_method", "S256");
+    Ok(url.into())
+}
+
+/// What the loopback listener received
+#[derive(Debug, PartialEq, Eq)]
+enum Callback {
+    /// Some other request (e.g. `/favicon.ico`); keep waiting
+    Ignored,
+    /// A redirect for another sign-in attempt (wrong `state`); keep waiting
+    Mismatched,
+    Code(String),
+    Failed(String),
+}
+
+/// Interpret the request line of a redirect (`GET /callback?code=...&state=... HTTP/1.1`)
+fn parse_callback(request_line: &str, expected_state: &str) -> Callback {
+    let mut parts = request_line.split_whitespace();
+    let (Some("GET"), Some(target)) = (parts.next(), parts.next()) else {
+        return Callback::Ignored;
+    };
+    let Ok(url) = reqwest::Url::parse(&format!("http://127.0.0.1{}", target)) else {
+        return Callback::Ignored;
+    };
+    if url.path() != CALLBACK_PATH {
+        return Callback::Ignored;
+    }
+
+    let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+
+    if param("state").as_deref() != Some(expected_state) {
+        return Callback::Mismatched;
+    }
+    if let Some(error) = param("error") {
+        let description = param("error_description").unwrap_or_default();
+        return Callback::Failed(format!("Login was not completed: {} {}", error, description).trim().to_string());
+    }
+    match param("code") {
+        Some(code) if !code.is_empty() => Callback::Code(code),
+        _ => Callback::Failed("Login response did not include an authorization code".to_string()),
+    }
+}
+
+/// Read the request line, answer with a small page and report what arrived
+async fn handle_connection(mut stream: TcpStream, expected_state: &str) -> Result<Callback, AppError> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let read_request = async {
+        while !buffer.windows(4).any(|window| window == b"\r\n\r\n") && buffer.len() < MAX_REQUEST_BYTES {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    tokio::time::timeout(REQUEST_READ_TIMEOUT, read_request)
+        .await
+        .map_err(|_| AppError::network("Loopback connection sent no request"))??;
+
+    let request = String::from_utf8_lossy(&buffer);
+    let callback = parse_callback(request.lines().next().unwrap_or_default(), expected_state);
+
+    let (status, body) = match &callback {
+        Callback::Ignored => ("404 Not Found", "Not found".to_string()),
+        Callback::Mismatched => ("400 Bad Request", "This response belongs to a different sign-in attempt".to_string()),
+        Callback::Code(_) => ("200 OK", "Signed in to Black Bird. You can close this tab and return to the app.".to_string()),
+        Callback::Failed(reason) => ("400 Bad Request", format!("Sign-in failed: {}", reason)),
+    };
+    let html = format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>Black Bird</title></head><body><p>{}</p></body></html>",
+        body.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    );
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        html.len(),
+        html
+    );
+    // The browser page is a courtesy; the result does not depend on it
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+
+    Ok(callback)
+}
+
+/// Wait for the redirect carrying the authorization code.
+///
+/// Connections are handled concurrently, so an idle one cannot hold up the redirect.
+async fn wait_for_code(listener: TcpListener, expected_state: &str) -> Result<Zeroizing<String>, AppError> {
+    let expected_state: Arc<str> = Arc::from(expected_state);
+    let mut connections = tokio::task::JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let expected_state = Arc::clone(&expected_state);
+                connections.spawn(async move { handle_connection(stream, &expected_state).await });
+            }
+            Some(handled) = connections.join_next() => match handled {
+                Ok(Ok(Callback::Code(code))) => return Ok(Zeroizing::new(code)),
+                Ok(Ok(Callback::Failed(reason))) => return Err(AppError::authentication(reason)),
+                Ok(Ok(Callback::Mismatched)) => log::warn!("Ignoring a login redirect for a different sign-in attempt"),
+                Ok(Ok(Callback::Ignored)) => {}
+                Ok(Err(e)) => log::warn!("Ignoring unreadable loopback request: {}", e),
+                Err(e) => log::warn!("Loopback request handler failed: {}", e),
+            },
+        }
+    }
+}
+
+/// Exchange the authorization code and PKCE verifier for tokens
+async fn exchange_code(
+    profile: &EndpointProfile,
+    code: &str,
+    redirect_uri: &str,
+    verifier: &str,
+) -> Result<TokenResponse, EmaptaApiError> {
+    let request_body = AuthorizationCodeRequest {
+        grant_type: "authorization_code".to_string(),
+        client_id: profile.auth_client_id.clone(),
+        code: code.to_string(),
+        redirect_uri: redirect_uri.to_string(),
+        code_verifier: verifier.to_string(),
+    };
+
+    // The authorization code grant is form-encoded (RFC 6749 section 4.1.3)
+    let response = crate::http_client::emapta_client()
+        .post(profile.token_url())
+        .form(&request_body)
+        .send()
+        .await
+        .map_err(EmaptaApiError::from_reqwest)?;
+
+    let status = response.status().as_u16();
+    let body = Zeroizing::new(response.text().await.map_err(EmaptaApiError::from_reqwest)?);
+    if !(200..300).contains(&status) {
+        return Err(EmaptaApiError::from_response(status, &body));
+    }
+
+    // The EMAPTA gateway wraps token responses in its envelope; plain Keycloak does not
+    serde_json::from_str::<EmaptaApiResponse>(&body)
+        .map(|envelope| envelope.result)
+        .or_else(|_| serde_json::from_str::<TokenResponse>(&body))
+        .map_err(|e| EmaptaApiError::parse(format!("Unexpected token response: {}", e)))
+}
+
+/// Clears `LOGIN_IN_PROGRESS` however the login ends
+struct LoginGuard;
+
+impl Drop for LoginGuard {
+    fn drop(&mut self) {
+        LOGIN_IN_PROGRESS.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Run the browser login and store the resulting tokens for `account`.
+///
+/// Tokens of a different user are kept pending and the identity change error
+/// is returned; answer it with `confirm_pending_login` or `discard_pending_login`.
+pub async fn login(account: &AccountContext) -> Result<(), AppError> {
+    // The authorize and token endpoints are live Keycloak; demo mode never reaches them
+    if crate::provider::is_demo_mode() {
+        return Err(AppError::authentication("Browser sign-in is not available in demo mode; enter any tokens in setup instead"));
+    }
+    if LOGIN_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return Err(AppError::authentication("A sign-in is already in progress in the browser"));
+    }
+    let _guard = LoginGuard;
+
+    let profile = crate::endpoints::active_profile();
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let redirect_uri = format!("http://127.0.0.1:{}{}", listener.local_addr()?.port(), CALLBACK_PATH);
+
+    let pkce = Pkce::generate();
+    let state = random_string(STATE_LENGTH);
+    let url = authorization_url(&profile, &redirect_uri, &state, &pkce.challenge)?;
+
+    log::info!("Opening the browser for sign-in (redirect {})", redirect_uri);
+    account.app_handle()
+        .opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| AppError::system(format!("Failed to open the browser: {}", e)))?;
+
+    let code = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_code(listener, &state))
+        .await
+        .map_err(|_| AppError::authentication("Sign-in timed out waiting for the browser"))??;
+
+    let mut tokens = exchange_code(&profile, &code, &redirect_uri, &pkce.verifier)
+        .await
+        .map_err(|e| e.into_grant_error("Code exchange failed"))?;
+
+    // `TokenResponse` wipes itself on drop, so take the values rather than copy them
+    let refresh_token = Zeroizing::new(std::mem::take(&mut tokens.refresh_token));
+    let access_token = Zeroizing::new(std::mem::take(&mut tokens.access_token));
+    match crate::token_manager::save_initial_tokens(account, &refresh_token, &access_token, false).await {
+        Err(e) if crate::token_manager::is_identity_change(&e) => {
+            park_pending_login(account, refresh_token, access_token);
+            return Err(e);
+        }
+        result => result?,
+    }
+
+    log::info!("Browser sign-in completed for account '{}'", account.id());
+    Ok(())
+}
+
+/// Hold tokens of a different user until the user decides, revoking them if nobody does
+fn park_pending_login(account: &AccountContext, refresh_token: Zeroizing<String>, access_token: Zeroizing<String>) {
+    let id = NEXT_PENDING_ID.fetch_add(1, Ordering::SeqCst);
+    let replaced = PENDING_LOGINS.lock().unwrap()
+        .insert(account.id().to_string(), PendingLogin { id, refresh_token, access_token });
+    log::info!("Browser sign-in for account '{}' is waiting for confirmation of the user change", account.id());
+
+    let account = account.clone();
+    tauri::async_runtime::spawn(async move {
+        let provider = crate::provider::account_provider(account.id());
+        if let Some(replaced) = replaced {
+            revoke_pending(provider.as_ref(), replaced).await;
+        }
+
+        tokio::time::sleep(PENDING_LOGIN_TTL).await;
+        let expired = {
+            let mut pending = PENDING_LOGINS.lock().unwrap();
+            match pending.get(account.id()) {
+                Some(login) if login.id == id => pending.remove(account.id()),
+                _ => None,
+            }
+        };
+        if let Some(expired) = expired {
+            log::info!("Unconfirmed browser sign-in for account '{}' expired", account.id());
+            revoke_pending(provider.as_ref(), expired).await;
+        }
+    });
+}
+
+fn take_pending_login(account: &AccountContext) -> Result<PendingLogin, AppError> {
+    PENDING_LOGINS.lock().unwrap().remove(account.id())
+        .ok_or_else(|| AppError::authentication("No sign-in is waiting for confirmation (it may have expired)"))
+}
+
+/// Revoke a pending login's refresh token, returning whether the server accepted it
+async fn revoke_pending(provider: &dyn EmaptaProvider, login: PendingLogin) -> bool {
+    match provider.revoke_refresh_token(&login.refresh_token).await {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Failed to revoke the tokens of a discarded sign-in: {}", e);
+            false
+        }
+    }
+}
+
+/// Save the pending tokens of `account`, replacing the previous user's
+pub async fn confirm_pending_login(account: &AccountContext) -> Result<(), AppError> {
+    let login = take_pending_login(account)?;
+    crate::token_manager::save_initial_tokens(account, &login.refresh_token, &login.access_token, true).await?;
+    log::info!("Browser sign-in completed for account '{}' (user changed)", account.id());
+    Ok(())
+}
+
+/// Drop and revoke the pending tokens of `account`, returning whether the server accepted the revocation
+pub async fn discard_pending_login(account: &AccountContext, provider: &dyn EmaptaProvider) -> Result<bool, AppError> {
+    let login = take_pending_login(account)?;
+    log::info!("Browser sign-in for account '{}' discarded", account.id());
+    Ok(revoke_pending(provider, login).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge_matches_rfc_7636_example() {
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string());
+        assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+        assert_eq!(Pkce::generate().verifier.len(), VERIFIER_LENGTH);
+    }
+
+    #[test]
+    fn test_parse_callback() {
+        assert_eq!(
+            parse_callback("GET /callback?state=abc&code=xyz%2B1&session_state=s HTTP/1.1", "abc"),
+            Callback::Code("xyz+1".to_string())
+        );
+        assert_eq!(parse_callback("GET /favicon.ico HTTP/1.1", "abc"), Callback::Ignored);
+        assert_eq!(parse_callback("GET /callback?state=other&code=xyz HTTP/1.1", "abc"), Callback::Mismatched);
+        assert_eq!(
+            parse_callback("GET /callback?error=access_denied&state=abc HTTP/1.1", "abc"),
+            Callback::Failed("Login was not completed: access_denied".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_code_skips_idle_and_mismatched_connections() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let waiting = tokio::spawn(async move { wait_for_code(listener, "abc").await });
+
+        // A speculative connection that never sends anything, then a stale redirect
+        let _idle = TcpStream::connect(address).await.unwrap();
+        let mut stale = TcpStream::connect(address).await.unwrap();
+        stale.write_all(b"GET /callback?state=old&code=stale HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut redirect = TcpStream::connect(address).await.unwrap();
+        redirect.write_all(b"GET /callback?state=abc&code=fresh HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let code = tokio::time::timeout(Duration::from_secs(5), waiting).await.unwrap().unwrap().unwrap();
+        assert_eq!(code.as_str(), "fresh");
+    }
+}
@@ -0,0 +1,183 @@
+/*!
+ * Operation History
+ *
+ * Bounded, persisted record of scheduler operations that have finished
+ * (completed, unverified, failed or cancelled). The scheduler's live queue
+ * only holds operations that are still waiting to run; once an operation
+ * reaches a final state it is archived here:
+ * - Newest entries first, capped at `MAX_HISTORY_ENTRIES`
+ * - Persisted to storage on every change so it survives restarts
+ * - Paged reads for the frontend via `get_operation_history`
+ */
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::AccountContext;
+use crate::documents;
+use crate::errors::AppError;
+use crate::scheduler::ScheduledOperation;
+use crate::storage::get_storage;
+
+/// Storage key for the persisted history (scoped per account)
+const HISTORY_STORAGE_KEY: &str = "operation_history";
+
+/// Maximum number of finished operations kept
+pub const MAX_HISTORY_ENTRIES: usize = 200;
+
+/// Default and maximum page sizes for history reads
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = MAX_HISTORY_ENTRIES;
+
+/// One page of operation history (newest first)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationHistoryPage {
+    pub entries: Vec<ScheduledOperation>,
+    /// Total number of entries in the history
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// In-memory history of finished operations (newest at the front)
+#[derive(Debug, Default)]
+pub struct OperationHistory {
+    entries: VecDeque<ScheduledOperation>,
+}
+
+impl OperationHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a finished operation, evicting the oldest entries beyond the cap
+    pub fn push(&mut self, operation: ScheduledOperation) {
+        self.entries.push_front(operation);
+        self.entries.truncate(MAX_HISTORY_ENTRIES);
+    }
+
+    /// Return a page of entries, newest first
+    pub fn page(&self, offset: Option<usize>, limit: Option<usize>) -> OperationHistoryPage {
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+        OperationHistoryPage {
+            entries: self.entries.iter().skip(offset).take(limit).cloned().collect(),
+            total: self.entries.len(),
+            offset,
+            limit,
+        }
+    }
+
+    /// Replace the contents with previously persisted entries
+    fn restore(&mut self, persisted: Vec<ScheduledOperation>) {
+        // Keep anything archived before the restore ran (newest first)
+        let mut merged: VecDeque<ScheduledOperation> = self.entries.drain(..).collect();
+        merged.extend(persisted);
+        merged.truncate(MAX_HISTORY_ENTRIES);
+        self.entries = merged;
+    }
+}
+
+/// Load persisted history into `history` (missing or unreadable data is ignored)
+pub async fn load_history(account: &AccountContext, history: &Arc<Mutex<OperationHistory>>) -> Result<usize, AppError> {
+    let storage = get_storage(account.app_handle())?;
+    let Some(persisted) = documents::load::<Vec<ScheduledOperation>>(&*storage, &account.key(HISTORY_STORAGE_KEY), &documents::OPERATION_HISTORY).await? else {
+        return Ok(0);
+    };
+    let count = persisted.len();
+
+    history.lock().unwrap().restore(persisted);
+    Ok(count)
+}
+
+/// Archive finished operations and persist the updated history
+pub async fn archive_operations(
+    account: &AccountContext,
+    history: &Arc<Mutex<OperationHistory>>,
+    operations: Vec<ScheduledOperation>,
+) -> Result<(), AppError> {
+    if operations.is_empty() {
+        return Ok(());
+    }
+
+    let entries = {
+        let mut history = history.lock().unwrap();
+        for operation in operations {
+            history.push(operation);
+        }
+        history.entries.clone()
+    };
+
+    let storage = get_storage(account.app_handle())?;
+    documents::save(&*storage, &account.key(HISTORY_STORAGE_KEY), &documents::OPERATION_HISTORY, &entries).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::OperationType;
+
+    fn operation(id: usize) -> ScheduledOperation {
+        ScheduledOperation::new(format!("op-{}", id), OperationType::ClockIn, "2026-09-01T08:00:00+00:00".to_string())
+    }
+
+    fn ids(page: &OperationHistoryPage) -> Vec<&str> {
+        page.entries.iter().map(|operation| operation.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_push_truncates_oldest() {
+        let mut history = OperationHistory::new();
+        for id in 0..MAX_HISTORY_ENTRIES + 5 {
+            history.push(operation(id));
+        }
+
+        let page = history.page(None, Some(MAX_PAGE_SIZE));
+        assert_eq!(page.total, MAX_HISTORY_ENTRIES);
+        assert_eq!(page.entries.first().unwrap().id, format!("op-{}", MAX_HISTORY_ENTRIES + 4));
+        assert_eq!(page.entries.last().unwrap().id, "op-5");
+    }
+
+    #[test]
+    fn test_page_clamps_offset_and_limit() {
+        let mut history = OperationHistory::new();
+        for id in 0..5 {
+            history.push(operation(id));
+        }
+
+        let page = history.page(Some(1), Some(2));
+        assert_eq!((ids(&page), page.total, page.offset, page.limit), (vec!["op-3", "op-2"], 5, 1, 2));
+
+        // Limits are clamped to 1..=MAX_PAGE_SIZE, defaulting to DEFAULT_PAGE_SIZE
+        assert_eq!(history.page(None, Some(0)).limit, 1);
+        assert_eq!(history.page(None, Some(MAX_PAGE_SIZE + 1)).limit, MAX_PAGE_SIZE);
+        assert_eq!(history.page(None, None).limit, DEFAULT_PAGE_SIZE);
+
+        // An offset past the end yields an empty page with the real total
+        let page = history.page(Some(10), None);
+        assert!(page.entries.is_empty());
+        assert_eq!((page.total, page.offset), (5, 10));
+    }
+
+    #[test]
+    fn test_restore_keeps_newer_entries_first() {
+        let mut history = OperationHistory::new();
+        history.push(operation(100));
+        history.push(operation(101));
+
+        // Persisted entries (newest first) go after the ones archived before the restore
+        let persisted: Vec<_> = (0..MAX_HISTORY_ENTRIES).rev().map(operation).collect();
+        history.restore(persisted);
+
+        let page = history.page(None, Some(4));
+        assert_eq!(ids(&page), vec!["op-101", "op-100", &format!("op-{}", MAX_HISTORY_ENTRIES - 1), &format!("op-{}", MAX_HISTORY_ENTRIES - 2)]);
+        assert_eq!(page.total, MAX_HISTORY_ENTRIES);
+        // The oldest persisted entries are the ones dropped by the cap
+        assert_eq!(history.page(Some(MAX_HISTORY_ENTRIES - 1), None).entries[0].id, "op-2");
+    }
+}
@@ -0,0 +1,116 @@
+/*!
+ * EMAPTA Provider
+ *
+ * The five EMAPTA operations the app depends on (token exchange, clock-in,
+ * clock-out, today's attendance and refresh token revocation) behind one
+ * trait, so the scheduler and token manager can run against something other
+ * than the live service:
+ * - `HttpEmaptaProvider` calls the real API (see the client functions in `commands.rs`)
+ * - `MockEmaptaProvider` (in `mock_provider.rs`) is an in-memory stand-in for
+ *   tests and demo mode
+ *
+ * Each account's `BackendScheduler` owns a provider; everything else reaches
+ * it through `account_provider()`.
+ */
+
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use crate::commands::{AttendanceItem, TokenResponse};
+use crate::errors::EmaptaApiError;
+use crate::mock_provider::MockEmaptaProvider;
+
+/// Command-line flag that starts the app against the mock provider
+const DEMO_FLAG: &str = "--demo";
+/// Environment variable that starts the app against the mock provider
+const DEMO_ENV_VAR: &str = "BLACK_BIRD_DEMO";
+/// Command-line flag naming the demo scenario (`--demo-scenario=outage`)
+const DEMO_SCENARIO_FLAG: &str = "--demo-scenario=";
+/// Environment variable naming the demo scenario
+const DEMO_SCENARIO_ENV_VAR: &str = "BLACK_BIRD_DEMO_SCENARIO";
+
+/// Source of EMAPTA clock and token operations
+#[async_trait]
+pub trait EmaptaProvider: Send + Sync {
+    /// Short name for logs (`emapta`, `mock`)
+    fn name(&self) -> &'static str;
+
+    /// Exchange a refresh token for a new access/refresh token pair
+    async fn exchange_refresh_token(&self, refresh_token: &str) -> Result<TokenResponse, EmaptaApiError>;
+
+    async fn clock_in(&self, access_token: &str) -> Result<bool, EmaptaApiError>;
+
+    async fn clock_out(&self, access_token: &str) -> Result<bool, EmaptaApiError>;
+
+    /// Today's attendance record, if the server has one
+    async fn attendance_status(&self, access_token: &str) -> Result<Option<AttendanceItem>, EmaptaApiError>;
+
+    /// Revoke a refresh token so it can no longer be exchanged
+    async fn revoke_refresh_token(&self, refresh_token: &str) -> Result<(), EmaptaApiError>;
+}
+
+/// Shared handle to the active provider
+pub type SharedProvider = Arc<dyn EmaptaProvider>;
+
+/// The live EMAPTA API
+pub struct HttpEmaptaProvider;
+
+#[async_trait]
+impl EmaptaProvider for HttpEmaptaProvider {
+    fn name(&self) -> &'static str {
+        "emapta"
+    }
+
+    async fn exchange_refresh_token(&self, refresh_token: &str) -> Result<TokenResponse, EmaptaApiError> {
+        crate::commands::exchange_refresh_token_api(refresh_token).await
+    }
+
+    async fn clock_in(&self, access_token: &str) -> Result<bool, EmaptaApiError> {
+        crate::commands::clock_in_api(access_token).await
+    }
+
+    async fn clock_out(&self, access_token: &str) -> Result<bool, EmaptaApiError> {
+        crate::commands::clock_out_api(access_token).await
+    }
+
+    async fn attendance_status(&self, access_token: &str) -> Result<Option<AttendanceItem>, EmaptaApiError> {
+        crate::commands::get_attendance_status_api(access_token).await
+    }
+
+    async fn revoke_refresh_token(&self, refresh_token: &str) -> Result<(), EmaptaApiError> {
+        crate::commands::revoke_refresh_token_api(refresh_token).await
+    }
+}
+
+/// Whether the app was started in demo mode (`--demo` or `BLACK_BIRD_DEMO=1`)
+pub fn is_demo_mode() -> bool {
+    let env_enabled = std::env::var(DEMO_ENV_VAR)
+        .is_ok_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"));
+    env_enabled || std::env::args().any(|arg| arg == DEMO_FLAG)
+}
+
+/// Demo scenario named on the command line or in the environment (`default` when unset)
+fn demo_scenario() -> String {
+    std::env::args()
+        .find_map(|arg| arg.strip_prefix(DEMO_SCENARIO_FLAG).map(str::to_string))
+        .or_else(|| std::env::var(DEMO_SCENARIO_ENV_VAR).ok())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Provider selected at startup: the mock in demo mode, otherwise the live API
+pub fn startup_provider() -> SharedProvider {
+    if is_demo_mode() {
+        let scenario = demo_scenario();
+        log::warn!("Demo mode ({} scenario): using the in-memory mock EMAPTA provider, no real clock actions will be made", scenario);
+        Arc::new(MockEmaptaProvider::demo(&scenario))
+    } else {
+        Arc::new(HttpEmaptaProvider)
+    }
+}
+
+/// Provider owned by an account's scheduler (the startup provider before the scheduler exists)
+pub fn account_provider(account_id: &str) -> SharedProvider {
+    crate::scheduler::get_scheduler(account_id)
+        .map(|scheduler| scheduler.provider())
+        .unwrap_or_else(startup_provider)
+}
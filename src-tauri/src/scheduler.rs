@@ -1,13 +1,18 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 use chrono::{DateTime, Local, TimeZone};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tokio::time::sleep;
 
+use crate::accounts::AccountContext;
+use crate::documents;
 use crate::errors::AppError;
-use crate::commands::{clock_in_api, clock_out_api, AttendanceItem};
+use crate::commands::AttendanceItem;
+use crate::clock_skew::ClockSkewStatus;
+use crate::operation_history::{archive_operations, OperationHistory, OperationHistoryPage};
+use crate::provider::{EmaptaProvider, SharedProvider};
 
 /// Work schedule configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,16 +22,86 @@ pub struct WorkSchedule {
     pub clock_in_time: String, // HH:MM format
     pub timezone: String,
     pub min_work_duration_minutes: u32,
+    /// Shift scheduled deadlines by the measured server clock skew
+    #[serde(default)]
+    pub correct_clock_skew: bool,
+}
+
+/// Longest minimum work duration a schedule may ask for
+const MAX_WORK_DURATION_MINUTES: u32 = 24 * 60;
+
+impl WorkSchedule {
+    /// Validate the schedule, normalizing the clock-in time to `HH:MM` and the timezone to its IANA name
+    pub fn validate(mut self) -> Result<Self, AppError> {
+        let clock_in = chrono::NaiveTime::parse_from_str(self.clock_in_time.trim(), "%H:%M")
+            .map_err(|_| AppError::validation("clockInTime", format!(
+                "'{}' is not a time in HH:MM format", self.clock_in_time
+            )))?;
+        self.clock_in_time = clock_in.format("%H:%M").to_string();
+
+        let timezone: chrono_tz::Tz = self.timezone.trim().parse()
+            .map_err(|_| AppError::validation("timezone", format!(
+                "'{}' is not an IANA timezone (e.g. Asia/Manila)", self.timezone
+            )))?;
+        self.timezone = timezone.name().to_string();
+
+        if !(1..=MAX_WORK_DURATION_MINUTES).contains(&self.min_work_duration_minutes) {
+            return Err(AppError::validation("minWorkDurationMinutes", format!(
+                "The minimum work duration must be between 1 and {} minutes", MAX_WORK_DURATION_MINUTES
+            )));
+        }
+        Ok(self)
+    }
 }
 
 /// Scheduler operation types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum OperationType {
     ClockIn,
     ClockOut,
 }
 
+/// Lifecycle state of a scheduled operation
+///
+/// ```text
+/// pending -> running -> completed | unverified | failed | cancelled
+///                    -> retrying -> running | failed | cancelled
+/// pending -> cancelled
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Pending,
+    Running,
+    Retrying,
+    Completed,
+    Unverified,
+    Failed,
+    Cancelled,
+}
+
+impl OperationStatus {
+    /// Whether moving from `self` to `next` is an allowed transition
+    pub fn can_transition_to(self, next: OperationStatus) -> bool {
+        use OperationStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Running | Cancelled)
+                | (Running, Completed | Unverified | Failed | Retrying | Cancelled)
+                | (Retrying, Running | Failed | Cancelled)
+        )
+    }
+
+    /// Final states leave the live queue and move to the operation history
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            OperationStatus::Completed | OperationStatus::Unverified | OperationStatus::Failed | OperationStatus::Cancelled
+        )
+    }
+}
+
 /// Scheduled operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -34,9 +109,71 @@ pub struct ScheduledOperation {
     pub id: String,
     pub operation_type: OperationType,
     pub scheduled_time: String, // ISO 8601
-    pub status: String,         // pending, completed, failed, cancelled
+    pub status: OperationStatus,
     pub actual_time: Option<String>,
     pub error_message: Option<String>,
+    /// Number of times execution was attempted
+    #[serde(default)]
+    pub attempt_count: u32,
+    /// When the latest attempt started (ISO 8601)
+    #[serde(default)]
+    pub started_at: Option<String>,
+    /// Time spent on the latest attempt, including verification (milliseconds)
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+}
+
+impl ScheduledOperation {
+    /// Create a pending operation
+    pub fn new(id: String, operation_type: OperationType, scheduled_time: String) -> Self {
+        Self {
+            id,
+            operation_type,
+            scheduled_time,
+            status: OperationStatus::Pending,
+            actual_time: None,
+            error_message: None,
+            attempt_count: 0,
+            started_at: None,
+            duration_ms: None,
+        }
+    }
+
+    /// Move to `next` and emit a status-change event for `account`.
+    ///
+    /// Invalid transitions are rejected and logged; the status is left unchanged.
+    pub fn transition(&mut self, next: OperationStatus, account: &AccountContext) -> Result<(), AppError> {
+        let from = self.status;
+        if !from.can_transition_to(next) {
+            log::warn!("Rejected invalid transition for {}: {:?} -> {:?}", self.id, from, next);
+            return Err(AppError::schedule(format!(
+                "Invalid status transition for operation {}: {:?} -> {:?}", self.id, from, next
+            )));
+        }
+
+        self.status = next;
+        account.emit(&SchedulerEvent::OperationStatusChanged {
+            operation_id: self.id.clone(),
+            operation_type: self.operation_type.clone(),
+            from,
+            to: next,
+        });
+        Ok(())
+    }
+
+    /// Record the start of an execution attempt
+    fn begin_attempt(&mut self, started_at: DateTime<chrono::Utc>) {
+        self.attempt_count += 1;
+        self.started_at = Some(started_at.to_rfc3339());
+    }
+
+    /// Record the end of an execution attempt
+    fn finish_attempt(&mut self, finished_at: DateTime<chrono::Utc>) {
+        self.actual_time = Some(finished_at.to_rfc3339());
+        self.duration_ms = self.started_at.as_deref()
+            .and_then(|started| DateTime::parse_from_rfc3339(started).ok())
+            .map(|started| (finished_at - started.with_timezone(&chrono::Utc)).num_milliseconds().max(0) as u64);
+    }
 }
 
 /// Current session state
@@ -54,8 +191,21 @@ pub struct SessionState {
 pub struct SchedulerState {
     pub is_running: bool,
     pub current_session: SessionState,
+    /// Live queue of operations waiting to run; finished ones move to the operation history
     pub pending_operations: Vec<ScheduledOperation>,
     pub last_error: Option<String>,
+    /// Measured offset between the EMAPTA server clock and the local clock
+    pub clock_skew: Option<ClockSkewStatus>,
+}
+
+/// What `prepare_shutdown` keeps of the scheduler for the next start.
+///
+/// Queued operations are not kept: the schedule recreates them when the scheduler starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchedulerSnapshot {
+    current_session: SessionState,
+    saved_at: String,
 }
 
 /// Scheduler events sent to the frontend
@@ -78,46 +228,91 @@ pub enum SchedulerEvent {
     ClockInFailed { operation_id: String, error: String },
     #[serde(rename = "clock_out_failed")]
     ClockOutFailed { operation_id: String, error: String },
+    #[serde(rename = "clock_in_unverified")]
+    ClockInUnverified { operation_id: String, reason: String },
+    #[serde(rename = "clock_out_unverified")]
+    ClockOutUnverified { operation_id: String, reason: String },
     #[serde(rename = "schedule_updated")]
     ScheduleUpdated { schedule: WorkSchedule },
     #[serde(rename = "state_changed")]
     StateChanged { state: SchedulerState },
     #[serde(rename = "auto_startup_completed")]
     AutoStartupCompleted { success: bool },
+    #[serde(rename = "clock_skew_warning")]
+    ClockSkewWarning { skew: ClockSkewStatus },
+    #[serde(rename = "operation_status_changed")]
+    OperationStatusChanged {
+        operation_id: String,
+        operation_type: OperationType,
+        from: OperationStatus,
+        to: OperationStatus,
+    },
+    #[serde(rename = "refresh_token_expiring")]
+    RefreshTokenExpiring { expires_at: String, expires_in_secs: i64 },
+    #[serde(rename = "signed_out")]
+    SignedOut,
 }
 
+/// Outcome of reading attendance back after a clock action
+#[derive(Debug, Clone)]
+enum ClockVerification {
+    /// Server recorded the action; carries the server-side timestamp (RFC 3339)
+    Verified { server_time: String },
+    /// Server attendance does not reflect the action
+    Unverified { reason: String },
+}
+
+/// Maximum execution attempts for a scheduled operation that keeps erroring
+const MAX_SCHEDULED_ATTEMPTS: u32 = 3;
+/// Delay before a failed scheduled operation is retried
+const SCHEDULED_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Number of attendance reads attempted when verifying a clock action
+const VERIFICATION_ATTEMPTS: u32 = 3;
+/// Delay between attendance reads while verifying (EMAPTA may lag slightly)
+const VERIFICATION_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Storage key for the saved work schedule (scoped per account)
+const SCHEDULE_KEY: &str = "user_schedule";
+/// Storage key recording whether the scheduler was left running (scoped per account)
+const SCHEDULER_ENABLED_KEY: &str = "scheduler_enabled";
+/// Storage key for the scheduler snapshot saved at shutdown (scoped per account)
+const SCHEDULER_STATE_KEY: &str = "scheduler_state";
+
 /// Internal scheduler task handle
 type TaskHandle = tokio::task::JoinHandle<()>;
 
-/// Backend scheduler for automatic clock operations
+/// Backend scheduler for automatic clock operations of one account
 pub struct BackendScheduler {
-    app_handle: AppHandle,
+    account: AccountContext,
     state: Arc<Mutex<SchedulerState>>,
     schedule: Arc<Mutex<Option<WorkSchedule>>>,
     task_handles: Arc<Mutex<HashMap<String, TaskHandle>>>,
+    history: Arc<Mutex<OperationHistory>>,
+    provider: SharedProvider,
 }
 
 impl BackendScheduler {
 
     /// Call clock-in API using shared token logic
     async fn call_clock_in_with_retry(&self) -> Result<bool, AppError> {
-        crate::token_manager::clock_in_with_shared_tokens(&self.app_handle).await
+        crate::token_manager::clock_in_with_shared_tokens(&self.account, self.provider.as_ref()).await
     }
 
     /// Call clock-out API using shared token logic
     async fn call_clock_out_with_retry(&self) -> Result<bool, AppError> {
-        crate::token_manager::clock_out_with_shared_tokens(&self.app_handle).await
+        crate::token_manager::clock_out_with_shared_tokens(&self.account, self.provider.as_ref()).await
     }
 
     /// Call attendance API using shared token logic
     async fn call_attendance_with_retry(&self) -> Result<Option<AttendanceItem>, AppError> {
-        crate::token_manager::attendance_check_with_shared_tokens(&self.app_handle).await
+        crate::token_manager::attendance_check_with_shared_tokens(&self.account, self.provider.as_ref()).await
     }
 
-    /// Create a new backend scheduler
-    pub fn new(app_handle: AppHandle) -> Self {
+    /// Create a new backend scheduler that performs `account`'s clock operations through `provider`
+    pub fn new(account: AccountContext, provider: SharedProvider) -> Self {
         Self {
-            app_handle,
+            account,
             state: Arc::new(Mutex::new(SchedulerState {
                 is_running: false,
                 current_session: SessionState {
@@ -127,25 +322,48 @@ impl BackendScheduler {
                 },
                 pending_operations: Vec::new(),
                 last_error: None,
+                clock_skew: None,
             })),
             schedule: Arc::new(Mutex::new(None)),
             task_handles: Arc::new(Mutex::new(HashMap::new())),
+            history: Arc::new(Mutex::new(OperationHistory::new())),
+            provider,
         }
     }
 
+    /// EMAPTA provider used for clock and attendance calls
+    pub fn provider(&self) -> SharedProvider {
+        Arc::clone(&self.provider)
+    }
+
+    /// Account this scheduler works for
+    pub fn account(&self) -> &AccountContext {
+        &self.account
+    }
+
+    /// Restore the persisted operation history
+    pub async fn load_history(&self) -> Result<usize, AppError> {
+        crate::operation_history::load_history(&self.account, &self.history).await
+    }
+
+    /// Get a page of finished operations (newest first)
+    pub fn get_operation_history(&self, offset: Option<usize>, limit: Option<usize>) -> OperationHistoryPage {
+        self.history.lock().unwrap().page(offset, limit)
+    }
+
 
     /// Check and perform auto clock-in on app startup
     pub async fn check_auto_startup(&self) -> Result<bool, AppError> {
-        println!("[Scheduler] Checking if auto clock-in should run...");
+        log::info!("Checking if auto clock-in should run...");
 
         // Log app startup event
-        if let Some(logger) = crate::logging::get_logger() {
+        if let Some(logger) = self.account.logger() {
             let _ = logger.log_app_startup(false, None).await; // Will update with actual result later
         }
 
         // Check if we have tokens available (used for attendance API check)
-        if let Err(e) = crate::token_manager::get_saved_access_token(&self.app_handle).await {
-            println!("[Scheduler] No access token found, skipping auto clock-in: {}", e);
+        if let Err(e) = crate::token_manager::get_saved_access_token(&self.account).await {
+            log::info!("No access token found, skipping auto clock-in: {}", e);
             return Ok(false);
         }
 
@@ -156,7 +374,7 @@ impl BackendScheduler {
         };
 
         if current_state.clocked_in {
-            println!("[Scheduler] Already clocked in, skipping auto clock-in");
+            log::info!("Already clocked in, skipping auto clock-in");
             return Ok(false);
         }
 
@@ -166,32 +384,32 @@ impl BackendScheduler {
             if let Ok(last_clock_in) = chrono::DateTime::parse_from_rfc3339(&clock_in_time) {
                 let last_clock_in_date = last_clock_in.with_timezone(&chrono::Local).date_naive();
                 if last_clock_in_date == today {
-                    println!("[Scheduler] Already clocked in today ({}), skipping auto clock-in", today);
+                    log::info!("Already clocked in today ({}), skipping auto clock-in", today);
                     return Ok(false);
                 } else {
-                    println!("[Scheduler] Last clock-in was on {}, today is {} - proceeding with auto clock-in", 
+                    log::info!("Last clock-in was on {}, today is {} - proceeding with auto clock-in", 
                             last_clock_in_date, today);
                 }
             }
         } else {
-            println!("[Scheduler] No previous clock-in time found - proceeding with auto clock-in");
+            log::info!("No previous clock-in time found - proceeding with auto clock-in");
         }
 
         // TODO: Add attendance API check for rest days/leave
         // For now, proceed with auto clock-in
         
         // Check current attendance status from EMAPTA API
-        println!("[Scheduler] Checking current attendance status from EMAPTA API...");
+        log::info!("Checking current attendance status from EMAPTA API...");
         match self.call_attendance_with_retry().await {
             Ok(Some(attendance)) => {
-                println!("[Scheduler] Current attendance status: {}", attendance.attendance_status);
+                log::info!("Current attendance status: {}", attendance.attendance_status);
 
                 // Check if it's a rest day
                 if attendance.is_restday == Some(true) {
-                    println!("[Scheduler] Today is a rest day, skipping auto clock-in");
+                    log::info!("Today is a rest day, skipping auto clock-in");
 
                     // Log the skip reason to activity logs
-                    if let Some(logger) = crate::logging::get_logger() {
+                    if let Some(logger) = self.account.logger() {
                         let _ = logger.log(
                             crate::logging::LogAction::AppStartup,
                             crate::logging::LogStatus::Info,
@@ -214,43 +432,43 @@ impl BackendScheduler {
                    attendance.date_time_out.is_none() {
 
                     let external_clock_in = attendance.date_time_in.as_ref().unwrap();
-                    println!("[Scheduler] External clock-in detected at: {}", external_clock_in);
+                    log::info!("External clock-in detected at: {}", external_clock_in);
 
                     // Calculate expected clock-out time
                     match self.calculate_clock_out_from_external(external_clock_in) {
                         Ok(expected_clock_out) => {
-                            let now = chrono::Utc::now();
+                            let now = self.effective_now();
 
                             // Check if we're OVERDUE for clock-out
                             if now >= expected_clock_out {
-                                println!("[Scheduler] OVERDUE clock-out detected! Expected: {}, Current: {}",
+                                log::warn!("Overdue clock-out detected! Expected: {}, Current: {}",
                                          expected_clock_out.to_rfc3339(), now.to_rfc3339());
-                                println!("[Scheduler] Executing immediate clock-out...");
+                                log::info!("Executing immediate clock-out...");
 
                                 // Execute immediate clock-out (bypass minimum duration)
                                 match self.manual_clock_out(true).await {
                                     Ok(success) => {
                                         if success {
-                                            println!("[Scheduler] Overdue clock-out completed successfully");
+                                            log::info!("Overdue clock-out completed successfully");
                                             return Ok(true); // Report that we took action
                                         } else {
-                                            println!("[Scheduler] Overdue clock-out failed");
+                                            log::warn!("Overdue clock-out failed");
                                             return Ok(false);
                                         }
                                     }
                                     Err(e) => {
-                                        println!("[Scheduler] Error during overdue clock-out: {:?}", e);
+                                        log::warn!("Error during overdue clock-out: {:?}", e);
                                         return Err(e);
                                     }
                                 }
                             } else {
                                 // Not overdue yet - schedule missing clock-out
                                 if !self.has_pending_clock_out() {
-                                    println!("[Scheduler] Scheduling missing clock-out for external clock-in");
-                                    println!("[Scheduler] Expected clock-out time: {}", expected_clock_out.to_rfc3339());
+                                    log::info!("Scheduling missing clock-out for external clock-in");
+                                    log::info!("Expected clock-out time: {}", expected_clock_out.to_rfc3339());
 
                                     // Log to activity logs
-                                    if let Some(logger) = crate::logging::get_logger() {
+                                    if let Some(logger) = self.account.logger() {
                                         let _ = logger.log(
                                             crate::logging::LogAction::AppStartup,
                                             crate::logging::LogStatus::Info,
@@ -266,13 +484,13 @@ impl BackendScheduler {
 
                                     match self.schedule_clock_out_from_external(external_clock_in, expected_clock_out).await {
                                         Ok(_) => {
-                                            println!("[Scheduler] Missing clock-out scheduled successfully");
+                                            log::info!("Missing clock-out scheduled successfully");
                                         }
                                         Err(e) => {
-                                            println!("[Scheduler] Failed to schedule missing clock-out: {:?}", e);
+                                            log::warn!("Failed to schedule missing clock-out: {:?}", e);
 
                                             // Log scheduling failure
-                                            if let Some(logger) = crate::logging::get_logger() {
+                                            if let Some(logger) = self.account.logger() {
                                                 let _ = logger.log(
                                                     crate::logging::LogAction::AppStartup,
                                                     crate::logging::LogStatus::Failed,
@@ -288,10 +506,10 @@ impl BackendScheduler {
                                         }
                                     }
                                 } else {
-                                    println!("[Scheduler] Clock-out already scheduled");
+                                    log::info!("Clock-out already scheduled");
 
                                     // Log that clock-out is already scheduled
-                                    if let Some(logger) = crate::logging::get_logger() {
+                                    if let Some(logger) = self.account.logger() {
                                         let _ = logger.log(
                                             crate::logging::LogAction::AppStartup,
                                             crate::logging::LogStatus::Info,
@@ -307,7 +525,7 @@ impl BackendScheduler {
                                 }
 
                                 // Log that auto clock-in was skipped due to external clock-in
-                                if let Some(logger) = crate::logging::get_logger() {
+                                if let Some(logger) = self.account.logger() {
                                     let _ = logger.log(
                                         crate::logging::LogAction::AppStartup,
                                         crate::logging::LogStatus::Info,
@@ -325,8 +543,8 @@ impl BackendScheduler {
                             }
                         }
                         Err(e) => {
-                            println!("[Scheduler] Failed to parse external clock-in time: {:?}", e);
-                            println!("[Scheduler] Skipping external clock-in handling");
+                            log::warn!("Failed to parse external clock-in time: {:?}", e);
+                            log::info!("Skipping external clock-in handling");
                             return Ok(false);
                         }
                     }
@@ -334,10 +552,10 @@ impl BackendScheduler {
 
                 // Check if it's a completed day
                 if attendance.attendance_status == "Completed" {
-                    println!("[Scheduler] Work day already completed, updating session state");
+                    log::info!("Work day already completed, updating session state");
 
                     // Log the skip reason to activity logs
-                    if let Some(logger) = crate::logging::get_logger() {
+                    if let Some(logger) = self.account.logger() {
                         let _ = logger.log(
                             crate::logging::LogAction::AppStartup,
                             crate::logging::LogStatus::Info,
@@ -364,10 +582,10 @@ impl BackendScheduler {
 
                 // Check if on leave
                 if attendance.attendance_status == "On leave" {
-                    println!("[Scheduler] On leave today, skipping auto clock-in");
+                    log::info!("On leave today, skipping auto clock-in");
 
                     // Log the skip reason to activity logs
-                    if let Some(logger) = crate::logging::get_logger() {
+                    if let Some(logger) = self.account.logger() {
                         let _ = logger.log(
                             crate::logging::LogAction::AppStartup,
                             crate::logging::LogStatus::Info,
@@ -384,22 +602,22 @@ impl BackendScheduler {
                     return Ok(false);
                 }
 
-                println!("[Scheduler] Attendance check passed, proceeding with auto clock-in");
+                log::info!("Attendance check passed, proceeding with auto clock-in");
             }
             Ok(None) => {
-                println!("[Scheduler] No attendance record found for today, proceeding with auto clock-in");
+                log::info!("No attendance record found for today, proceeding with auto clock-in");
             }
             Err(error) => {
-                println!("[Scheduler] Failed to check attendance status: {}", error);
-                println!("[Scheduler] Proceeding with auto clock-in anyway");
+                log::warn!("Failed to check attendance status: {}", error);
+                log::info!("Proceeding with auto clock-in anyway");
                 // Don't block auto clock-in if API check fails
             }
         }
         
-        println!("[Scheduler] Conditions met, attempting auto clock-in...");
+        log::info!("Conditions met, attempting auto clock-in...");
 
         // Log that auto clock-in is proceeding
-        if let Some(logger) = crate::logging::get_logger() {
+        if let Some(logger) = self.account.logger() {
             let _ = logger.log(
                 crate::logging::LogAction::AppStartup,
                 crate::logging::LogStatus::Info,
@@ -417,18 +635,18 @@ impl BackendScheduler {
         match self.manual_clock_in().await {
             Ok(success) => {
                 if success {
-                    println!("[Scheduler] Auto clock-in successful!");
+                    log::info!("Auto clock-in successful!");
                     // Emit a specific auto-startup event
-                    let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::AutoStartupCompleted { success: true });
+                    self.account.emit(&SchedulerEvent::AutoStartupCompleted { success: true });
                 } else {
-                    println!("[Scheduler] Auto clock-in failed");
-                    let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::AutoStartupCompleted { success: false });
+                    log::warn!("Auto clock-in failed");
+                    self.account.emit(&SchedulerEvent::AutoStartupCompleted { success: false });
                 }
                 Ok(success)
             }
             Err(error) => {
-                println!("[Scheduler] Error during auto clock-in: {:?}", error);
-                let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::AutoStartupCompleted { success: false });
+                log::warn!("Error during auto clock-in: {:?}", error);
+                self.account.emit(&SchedulerEvent::AutoStartupCompleted { success: false });
                 Err(error)
             }
         }
@@ -436,7 +654,7 @@ impl BackendScheduler {
 
     /// Start the scheduler with the given schedule
     pub async fn start_scheduler(&self, schedule: WorkSchedule) -> Result<(), AppError> {
-        println!("[Scheduler] Starting with schedule: {:?}", schedule);
+        log::info!("Starting with schedule: {:?}", schedule);
         
         // Update schedule
         {
@@ -452,11 +670,12 @@ impl BackendScheduler {
         }
 
         // Clear existing tasks
-        self.cancel_all_tasks().await;
+        self.cancel_pending_operations(None, "Superseded by scheduler restart").await;
 
         // Emit event
-        let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::SchedulerStarted);
-        let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::ScheduleUpdated { schedule: schedule.clone() });
+        self.account.emit(&SchedulerEvent::SchedulerStarted);
+        self.account.emit(&SchedulerEvent::ScheduleUpdated { schedule: schedule.clone() });
+        self.save_enabled(true).await;
 
         // Schedule operations if auto scheduling is enabled
         if schedule.auto_schedule_enabled {
@@ -466,64 +685,212 @@ impl BackendScheduler {
         Ok(())
     }
 
+    /// Start with the saved schedule, unless the user stopped the scheduler or the account is signed out.
+    ///
+    /// A session left open at the last shutdown is restored first, and its
+    /// clock-out is scheduled again when the scheduler starts.
+    pub async fn restore_schedule(&self) -> Result<bool, AppError> {
+        let session_restored = match self.restore_session().await {
+            Ok(restored) => restored,
+            Err(e) => {
+                log::warn!("Failed to restore the session of '{}': {}", self.account.id(), e);
+                false
+            }
+        };
+
+        let storage = crate::storage::get_storage(self.account.app_handle())?;
+        let enabled = documents::load::<bool>(&*storage, &self.account.key(SCHEDULER_ENABLED_KEY), &documents::SCHEDULER_ENABLED).await?;
+        if enabled == Some(false) {
+            log::info!("Scheduler of '{}' was stopped before the last exit", self.account.id());
+            return Ok(false);
+        }
+        let Some(schedule) = load_schedule(&self.account).await? else {
+            return Ok(false);
+        };
+        if crate::token_manager::get_stored_tokens(&self.account).await?.0.is_none() {
+            log::info!("Account '{}' is signed out, not arming its scheduler", self.account.id());
+            return Ok(false);
+        }
+
+        let auto_schedule = schedule.auto_schedule_enabled;
+        self.start_scheduler(schedule).await?;
+        if session_restored && auto_schedule && !self.has_pending_clock_out() {
+            self.schedule_clock_out().await?;
+        }
+        Ok(true)
+    }
+
+    /// Load the snapshot saved at the last shutdown and take over its open session.
+    ///
+    /// The snapshot is consumed; sessions clocked in more than a day ago are dropped.
+    async fn restore_session(&self) -> Result<bool, AppError> {
+        let storage = crate::storage::get_storage(self.account.app_handle())?;
+        let key = self.account.key(SCHEDULER_STATE_KEY);
+        let Some(snapshot) = documents::load::<SchedulerSnapshot>(&*storage, &key, &documents::SCHEDULER_STATE).await? else {
+            return Ok(false);
+        };
+        storage.delete(&key).await?;
+
+        // A work day is at most 24 hours (`min_work_duration_minutes` <= 1440)
+        let session = snapshot.current_session;
+        let recent = session.clock_in_time.as_deref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .is_some_and(|time| chrono::Utc::now() - time.with_timezone(&chrono::Utc) < chrono::Duration::days(1));
+        if !session.clocked_in || !recent {
+            log::info!("Session saved by '{}' at {} is no longer open, not restored", self.account.id(), snapshot.saved_at);
+            return Ok(false);
+        }
+
+        log::info!("Restored the open session of '{}' (expected clock-out {:?})",
+                 self.account.id(), session.expected_clock_out_time);
+        self.state.lock().unwrap().current_session = session;
+        Ok(true)
+    }
+
+    /// Re-apply an edited schedule if the scheduler is running for a signed-in account.
+    ///
+    /// A stopped scheduler stays stopped; the saved schedule is used when it is next started.
+    pub async fn apply_schedule(&self, schedule: WorkSchedule) -> Result<bool, AppError> {
+        if !self.state.lock().unwrap().is_running {
+            return Ok(false);
+        }
+        if crate::token_manager::get_stored_tokens(&self.account).await?.0.is_none() {
+            log::info!("Account '{}' is signed out, not re-arming its scheduler", self.account.id());
+            return Ok(false);
+        }
+
+        self.start_scheduler(schedule).await?;
+        Ok(true)
+    }
+
+    /// Record whether the scheduler should be restored at the next launch
+    async fn save_enabled(&self, enabled: bool) {
+        let saved = async {
+            let storage = crate::storage::get_storage(self.account.app_handle())?;
+            documents::save(&*storage, &self.account.key(SCHEDULER_ENABLED_KEY), &documents::SCHEDULER_ENABLED, &enabled).await
+        }.await;
+        if let Err(e) = saved {
+            log::warn!("Failed to save whether the scheduler of '{}' is running: {}", self.account.id(), e);
+        }
+    }
+
     /// Stop the scheduler
     pub async fn stop_scheduler(&self) -> Result<(), AppError> {
-        println!("[Scheduler] Stopping scheduler");
+        log::info!("Stopping scheduler");
         
         // Update state
         {
             let mut state = self.state.lock().unwrap();
             state.is_running = false;
-            state.pending_operations.clear();
         }
 
-        // Cancel all tasks
-        self.cancel_all_tasks().await;
+        // Cancel all tasks and archive their operations
+        self.cancel_pending_operations(None, "Scheduler stopped").await;
 
         // Emit event
-        let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::SchedulerStopped);
+        self.account.emit(&SchedulerEvent::SchedulerStopped);
+        // Stays stopped across restarts (also after sign-out, which stops it first)
+        self.save_enabled(false).await;
+
+        Ok(())
+    }
+
+    /// Stop scheduling for application exit: cancel tasks and persist the open
+    /// session. The enabled flag is left as it is, so `restore_schedule`
+    /// resumes a running scheduler, and the session, on the next start.
+    pub async fn prepare_shutdown(&self) -> Result<(), AppError> {
+        log::info!("Preparing for shutdown");
+
+        self.cancel_pending_operations(None, "Application shut down").await;
+        let snapshot = {
+            let mut state = self.state.lock().unwrap();
+            state.is_running = false;
+            SchedulerSnapshot {
+                current_session: state.current_session.clone(),
+                saved_at: chrono::Utc::now().to_rfc3339(),
+            }
+        };
 
+        self.account.emit(&SchedulerEvent::SchedulerStopped);
+
+        let storage = crate::storage::get_storage(self.account.app_handle())?;
+        documents::save(&*storage, &self.account.key(SCHEDULER_STATE_KEY), &documents::SCHEDULER_STATE, &snapshot).await?;
         Ok(())
     }
 
     /// Get current scheduler state
     pub fn get_state(&self) -> SchedulerState {
-        let state = self.state.lock().unwrap();
-        state.clone()
+        let mut state = self.state.lock().unwrap().clone();
+        state.clock_skew = crate::clock_skew::current_status();
+        state
+    }
+
+    /// Notify the frontend that the local clock drifted past the warning threshold
+    pub fn notify_clock_skew(&self, skew: ClockSkewStatus) {
+        self.account.emit(&SchedulerEvent::ClockSkewWarning { skew });
+    }
+
+    /// Current time used for deadlines (server-corrected when enabled in the schedule)
+    fn effective_now(&self) -> DateTime<chrono::Utc> {
+        effective_now_standalone(&self.schedule)
     }
 
     /// Manual clock in
     pub async fn manual_clock_in(&self) -> Result<bool, AppError> {
-        println!("[Scheduler] Manual clock in requested");
+        log::info!("Manual clock in requested");
+
+        let operation = self.begin_manual_operation(OperationType::ClockIn);
+
+        // Snapshot attendance so the result can be verified afterwards
+        let before = fetch_attendance_snapshot(&self.account, self.provider.as_ref()).await;
 
         // Call clock-in API with retry logic
-        let success = self.call_clock_in_with_retry().await?;
+        let success = match self.call_clock_in_with_retry().await {
+            Ok(success) => success,
+            Err(e) => {
+                self.finish_manual_operation(operation, OperationStatus::Failed, Some(e.to_string())).await;
+                return Err(e);
+            }
+        };
         
         if success {
             let now = chrono::Utc::now().to_rfc3339();
-            let expected_clock_out = self.calculate_expected_clock_out_time(&now);
+
+            // Prefer the server's recorded time-in so clock-out is computed from it
+            let verification = verify_clock_action(&self.account, self.provider.as_ref(), &OperationType::ClockIn, before.as_ref()).await;
+            let (clock_in_time, status, error_message) = match verification {
+                ClockVerification::Verified { server_time } => (server_time, OperationStatus::Completed, None),
+                ClockVerification::Unverified { reason } => {
+                    report_unverified(&self.account, &OperationType::ClockIn, &operation.id, &reason).await;
+                    (now, OperationStatus::Unverified, Some(reason))
+                }
+            };
+            let expected_clock_out = self.calculate_expected_clock_out_time(&clock_in_time);
             
             // Update state
             {
                 let mut state = self.state.lock().unwrap();
                 state.current_session.clocked_in = true;
-                state.current_session.clock_in_time = Some(now.clone());
+                state.current_session.clock_in_time = Some(clock_in_time.clone());
                 state.current_session.expected_clock_out_time = Some(expected_clock_out);
-                
-                // Cancel pending clock-in operations
-                state.pending_operations.retain(|op| {
-                    !matches!(op.operation_type, OperationType::ClockIn) || op.status != "pending"
-                });
             }
 
+            // Cancel pending clock-in operations
+            self.cancel_pending_operations(Some(OperationType::ClockIn), "Superseded by manual clock-in").await;
+
+            let operation_id = operation.id.clone();
+            self.finish_manual_operation(operation, status, error_message).await;
+
             // Schedule clock out
             self.schedule_clock_out().await?;
             
             // Emit event
-            let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::ClockInSucceeded {
-                operation_id: "manual".to_string(),
-                actual_time: now,
+            self.account.emit(&SchedulerEvent::ClockInSucceeded {
+                operation_id,
+                actual_time: clock_in_time,
             });
+        } else {
+            self.finish_manual_operation(operation, OperationStatus::Failed, Some("Clock-in API returned false".to_string())).await;
         }
 
         Ok(success)
@@ -531,17 +898,34 @@ impl BackendScheduler {
 
     /// Manual clock out
     pub async fn manual_clock_out(&self, bypass_minimum: bool) -> Result<bool, AppError> {
-        println!("[Scheduler] Manual clock out requested (bypass_minimum: {})", bypass_minimum);
+        log::info!("Manual clock out requested (bypass_minimum: {})", bypass_minimum);
 
         if !bypass_minimum && !self.can_clock_out() {
             return Err(AppError::validation("operation", "Cannot clock out before minimum work duration"));
         }
 
+        let operation = self.begin_manual_operation(OperationType::ClockOut);
+
+        // Snapshot attendance so the result can be verified afterwards
+        let before = fetch_attendance_snapshot(&self.account, self.provider.as_ref()).await;
+
         // Call clock-out API with retry logic
-        let success = self.call_clock_out_with_retry().await?;
+        let success = match self.call_clock_out_with_retry().await {
+            Ok(success) => success,
+            Err(e) => {
+                self.finish_manual_operation(operation, OperationStatus::Failed, Some(e.to_string())).await;
+                return Err(e);
+            }
+        };
         
         if success {
-            let now = chrono::Utc::now().to_rfc3339();
+            let (now, status, error_message) = match verify_clock_action(&self.account, self.provider.as_ref(), &OperationType::ClockOut, before.as_ref()).await {
+                ClockVerification::Verified { server_time } => (server_time, OperationStatus::Completed, None),
+                ClockVerification::Unverified { reason } => {
+                    report_unverified(&self.account, &OperationType::ClockOut, &operation.id, &reason).await;
+                    (chrono::Utc::now().to_rfc3339(), OperationStatus::Unverified, Some(reason))
+                }
+            };
             
             // Update state
             {
@@ -549,21 +933,24 @@ impl BackendScheduler {
                 state.current_session.clocked_in = false;
                 state.current_session.clock_in_time = None;
                 state.current_session.expected_clock_out_time = None;
-                
-                // Cancel pending clock-out operations
-                state.pending_operations.retain(|op| {
-                    !matches!(op.operation_type, OperationType::ClockOut) || op.status != "pending"
-                });
             }
 
+            // Cancel pending clock-out operations
+            self.cancel_pending_operations(Some(OperationType::ClockOut), "Superseded by manual clock-out").await;
+
+            let operation_id = operation.id.clone();
+            self.finish_manual_operation(operation, status, error_message).await;
+
             // Schedule next clock in
             self.schedule_next_clock_in().await?;
             
             // Emit event
-            let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::ClockOutSucceeded {
-                operation_id: "manual".to_string(),
+            self.account.emit(&SchedulerEvent::ClockOutSucceeded {
+                operation_id,
                 actual_time: now,
             });
+        } else {
+            self.finish_manual_operation(operation, OperationStatus::Failed, Some("Clock-out API returned false".to_string())).await;
         }
 
         Ok(success)
@@ -576,7 +963,11 @@ impl BackendScheduler {
         
         if let (Some(clock_in_time), Some(schedule)) = (&state.current_session.clock_in_time, &*schedule) {
             if let Ok(clock_in_dt) = DateTime::parse_from_rfc3339(clock_in_time) {
-                let now = chrono::Utc::now();
+                let now = if schedule.correct_clock_skew {
+                    crate::clock_skew::server_now()
+                } else {
+                    chrono::Utc::now()
+                };
                 let elapsed_minutes = (now - clock_in_dt.with_timezone(&chrono::Utc)).num_minutes() as u32;
                 return elapsed_minutes >= schedule.min_work_duration_minutes;
             }
@@ -606,33 +997,34 @@ impl BackendScheduler {
         // Add to pending operations
         {
             let mut state = self.state.lock().unwrap();
-            state.pending_operations.push(ScheduledOperation {
-                id: operation_id.clone(),
-                operation_type: OperationType::ClockIn,
-                scheduled_time: next_clock_in_time.to_rfc3339(),
-                status: "pending".to_string(),
-                actual_time: None,
-                error_message: None,
-            });
+            state.pending_operations.push(ScheduledOperation::new(
+                operation_id.clone(),
+                OperationType::ClockIn,
+                next_clock_in_time.to_rfc3339(),
+            ));
         }
 
         // Create shared state for the async task
-        let app_handle = self.app_handle.clone();
+        let account = self.account.clone();
         let state = Arc::clone(&self.state);
         let schedule_ref = Arc::clone(&self.schedule);
+        let history = Arc::clone(&self.history);
+        let provider = Arc::clone(&self.provider);
         let operation_id_clone = operation_id.clone();
         
-        let delay = (next_clock_in_time.timestamp() - chrono::Utc::now().timestamp()) as u64;
-        let delay_duration = Duration::from_secs(delay.max(1)); // Minimum 1 second delay
+        let delay = next_clock_in_time.timestamp() - self.effective_now().timestamp();
+        let delay_duration = Duration::from_secs(delay.max(1) as u64); // Minimum 1 second delay
         
         let task = tokio::spawn(async move {
             sleep(delay_duration).await;
             
             // Execute clock in
             let _ = execute_scheduled_clock_in(
-                app_handle,
+                account,
+                provider,
                 state,
                 schedule_ref,
+                history,
                 &operation_id_clone
             ).await;
         });
@@ -644,7 +1036,7 @@ impl BackendScheduler {
         }
 
         // Emit event
-        let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::ClockInScheduled {
+        self.account.emit(&SchedulerEvent::ClockInScheduled {
             operation_id,
             scheduled_time: next_clock_in_time.to_rfc3339(),
         });
@@ -673,34 +1065,33 @@ impl BackendScheduler {
         // Add to pending operations
         {
             let mut state = self.state.lock().unwrap();
-            state.pending_operations.push(ScheduledOperation {
-                id: operation_id.clone(),
-                operation_type: OperationType::ClockOut,
-                scheduled_time: clock_out_time.clone(),
-                status: "pending".to_string(),
-                actual_time: None,
-                error_message: None,
-            });
+            state.pending_operations.push(ScheduledOperation::new(
+                operation_id.clone(),
+                OperationType::ClockOut,
+                clock_out_time.clone(),
+            ));
         }
 
         // Create shared state for the async task
-        let app_handle = self.app_handle.clone();
+        let account = self.account.clone();
         let state = Arc::clone(&self.state);
         let schedule_ref = Arc::clone(&self.schedule);
+        let history = Arc::clone(&self.history);
+        let provider = Arc::clone(&self.provider);
         let operation_id_clone = operation_id.clone();
         
-        let now = chrono::Utc::now();
+        let now = self.effective_now();
         let delay_seconds = clock_out_dt.timestamp() - now.timestamp();
 
         // Handle negative delays (past due times) and very long delays
         let delay_duration = if delay_seconds <= 0 {
-            println!("[Scheduler] Clock-out time is in the past or now ({}), executing immediately", clock_out_dt.to_rfc3339());
+            log::info!("Clock-out time is in the past or now ({}), executing immediately", clock_out_dt.to_rfc3339());
             Duration::from_secs(1) // Execute almost immediately
         } else if delay_seconds > 86400 { // More than 24 hours
-            println!("[Scheduler] WARNING: Clock-out delay is very long ({} seconds = {} hours), capping to 12 hours", delay_seconds, delay_seconds / 3600);
+            log::warn!("Clock-out delay is very long ({} seconds = {} hours), capping to 12 hours", delay_seconds, delay_seconds / 3600);
             Duration::from_secs(43200) // Cap at 12 hours
         } else {
-            println!("[Scheduler] Clock-out scheduled in {} seconds ({:.1} hours)", delay_seconds, delay_seconds as f32 / 3600.0);
+            log::info!("Clock-out scheduled in {} seconds ({:.1} hours)", delay_seconds, delay_seconds as f32 / 3600.0);
             Duration::from_secs(delay_seconds as u64)
         };
         
@@ -709,9 +1100,11 @@ impl BackendScheduler {
             
             // Execute clock out
             let _ = execute_scheduled_clock_out(
-                app_handle,
+                account,
+                provider,
                 state,
                 schedule_ref,
+                history,
                 &operation_id_clone
             ).await;
         });
@@ -723,7 +1116,7 @@ impl BackendScheduler {
         }
 
         // Emit event
-        let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::ClockOutScheduled {
+        self.account.emit(&SchedulerEvent::ClockOutScheduled {
             operation_id,
             scheduled_time: clock_out_time,
         });
@@ -732,39 +1125,79 @@ impl BackendScheduler {
     }
 
 
-    /// Cancel all scheduled tasks
-    async fn cancel_all_tasks(&self) {
-        let mut handles = self.task_handles.lock().unwrap();
-        for (_, handle) in handles.drain() {
-            handle.abort();
+    /// Cancel pending operations (all, or only one type), abort their tasks
+    /// and move them to the operation history
+    async fn cancel_pending_operations(&self, operation_type: Option<OperationType>, reason: &str) {
+        let cancelled: Vec<ScheduledOperation> = {
+            let mut state = self.state.lock().unwrap();
+            let (cancelled, remaining) = state.pending_operations.drain(..)
+                .partition(|op| operation_type.as_ref().is_none_or(|t| *t == op.operation_type));
+            state.pending_operations = remaining;
+            cancelled
+        };
+
+        {
+            let mut handles = self.task_handles.lock().unwrap();
+            match operation_type {
+                None => {
+                    for (_, handle) in handles.drain() {
+                        handle.abort();
+                    }
+                }
+                Some(_) => {
+                    for operation in &cancelled {
+                        if let Some(handle) = handles.remove(&operation.id) {
+                            handle.abort();
+                        }
+                    }
+                }
+            }
         }
-    }
 
-    /// Get next clock-in time based on schedule
-    fn get_next_clock_in_time(&self, schedule: &WorkSchedule) -> Result<DateTime<chrono::Utc>, AppError> {
-        let now = Local::now();
-        let time_parts: Vec<&str> = schedule.clock_in_time.split(':').collect();
-        
-        if time_parts.len() != 2 {
-            return Err(AppError::validation("schedule", "Invalid clock-in time format"));
+        let now = chrono::Utc::now().to_rfc3339();
+        let cancelled = cancelled.into_iter().map(|mut operation| {
+            let _ = operation.transition(OperationStatus::Cancelled, &self.account);
+            operation.actual_time = Some(now.clone());
+            operation.error_message = Some(reason.to_string());
+            operation
+        }).collect();
+
+        if let Err(e) = archive_operations(&self.account, &self.history, cancelled).await {
+            log::warn!("Failed to persist operation history: {}", e);
         }
-        
-        let hour: u32 = time_parts[0].parse()
-            .map_err(|_| AppError::validation("schedule", "Invalid hour in clock-in time"))?;
-        let minute: u32 = time_parts[1].parse()
-            .map_err(|_| AppError::validation("schedule", "Invalid minute in clock-in time"))?;
-        
-        let mut next_clock_in = now.date_naive().and_hms_opt(hour, minute, 0)
-            .ok_or_else(|| AppError::validation("schedule", "Invalid time"))?;
-        
-        // If time has passed today, schedule for tomorrow
-        if next_clock_in <= now.naive_local() {
-            next_clock_in = next_clock_in + chrono::Duration::days(1);
+    }
+
+    /// Create the history record for a manual (unscheduled) clock action
+    fn begin_manual_operation(&self, operation_type: OperationType) -> ScheduledOperation {
+        let now = chrono::Utc::now();
+        let prefix = match operation_type {
+            OperationType::ClockIn => "manual_clock_in",
+            OperationType::ClockOut => "manual_clock_out",
+        };
+        let mut operation = ScheduledOperation::new(
+            format!("{}_{}", prefix, now.timestamp()),
+            operation_type,
+            now.to_rfc3339(),
+        );
+        let _ = operation.transition(OperationStatus::Running, &self.account);
+        operation.begin_attempt(now);
+        operation
+    }
+
+    /// Finish a manual clock action and archive it in the operation history
+    async fn finish_manual_operation(&self, mut operation: ScheduledOperation, status: OperationStatus, error_message: Option<String>) {
+        let _ = operation.transition(status, &self.account);
+        operation.error_message = error_message;
+        operation.finish_attempt(chrono::Utc::now());
+
+        if let Err(e) = archive_operations(&self.account, &self.history, vec![operation]).await {
+            log::warn!("Failed to persist operation history: {}", e);
         }
-        
-        Ok(Local.from_local_datetime(&next_clock_in).single()
-            .ok_or_else(|| AppError::validation("schedule", "Invalid time"))?
-            .with_timezone(&chrono::Utc))
+    }
+
+    /// Get next clock-in time based on schedule, by the skew-corrected clock
+    fn get_next_clock_in_time(&self, schedule: &WorkSchedule) -> Result<DateTime<chrono::Utc>, AppError> {
+        next_clock_in_after(&schedule.clock_in_time, self.effective_now().with_timezone(&Local))
     }
 
     /// Calculate expected clock-out time
@@ -783,44 +1216,9 @@ impl BackendScheduler {
 
     /// Calculate expected clock-out time from external clock-in (EMAPTA date format)
     fn calculate_clock_out_from_external(&self, external_clock_in: &str) -> Result<DateTime<chrono::Utc>, AppError> {
-        println!("[Scheduler] Parsing external clock-in time: '{}'", external_clock_in);
-
-        // Parse EMAPTA datetime format with improved timezone handling
-        let clock_in_dt = DateTime::parse_from_rfc3339(external_clock_in)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .or_else(|_| {
-                // Try parsing without timezone info - IMPORTANT: Assume LOCAL timezone, not UTC
-                if !external_clock_in.contains('T') {
-                    // Format: "2024-10-09 09:00:00" -> parse as local time
-                    let with_t = external_clock_in.replace(' ', "T");
-
-                    // Try parsing as local time first (more accurate for EMAPTA times)
-                    if let Ok(naive_dt) = chrono::NaiveDateTime::parse_from_str(&with_t, "%Y-%m-%dT%H:%M:%S") {
-                        if let Some(local_dt) = chrono::Local.from_local_datetime(&naive_dt).single() {
-                            let utc_dt = local_dt.with_timezone(&chrono::Utc);
-                            println!("[Scheduler] Parsed as local time: {} -> UTC: {}", local_dt, utc_dt);
-                            return Ok(utc_dt);
-                        } else {
-                            return Err(AppError::validation("time", "Ambiguous local time"));
-                        }
-                    }
+        log::info!("Parsing external clock-in time: '{}'", external_clock_in);
 
-                    // Fallback: treat as UTC if local parsing fails
-                    let with_tz = if with_t.ends_with('Z') || with_t.contains('+') || with_t.contains('-') {
-                        with_t
-                    } else {
-                        println!("[Scheduler] WARNING: Falling back to UTC interpretation for time: {}", external_clock_in);
-                        format!("{}Z", with_t)
-                    };
-                    DateTime::parse_from_rfc3339(&with_tz)
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                        .map_err(|e| AppError::validation("time", &format!("Invalid external clock-in time format '{}': {}", external_clock_in, e)))
-                } else {
-                    DateTime::parse_from_rfc3339(external_clock_in)
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                        .map_err(|e| AppError::validation("time", &format!("Invalid external clock-in time format '{}': {}", external_clock_in, e)))
-                }
-            })?;
+        let clock_in_dt = parse_emapta_datetime(external_clock_in)?;
 
         let schedule = self.schedule.lock().unwrap();
         let work_duration = if let Some(schedule) = &*schedule {
@@ -834,7 +1232,7 @@ impl BackendScheduler {
 
     /// Schedule clock-out for external clock-in
     async fn schedule_clock_out_from_external(&self, external_clock_in: &str, expected_clock_out: DateTime<chrono::Utc>) -> Result<(), AppError> {
-        println!("[Scheduler] Scheduling clock-out for external clock-in at: {}", expected_clock_out.to_rfc3339());
+        log::info!("Scheduling clock-out for external clock-in at: {}", expected_clock_out.to_rfc3339());
 
         let operation_id = format!("clock_out_external_{}", expected_clock_out.timestamp());
 
@@ -846,34 +1244,33 @@ impl BackendScheduler {
             state.current_session.expected_clock_out_time = Some(expected_clock_out.to_rfc3339());
 
             // Add to pending operations
-            state.pending_operations.push(ScheduledOperation {
-                id: operation_id.clone(),
-                operation_type: OperationType::ClockOut,
-                scheduled_time: expected_clock_out.to_rfc3339(),
-                status: "pending".to_string(),
-                actual_time: None,
-                error_message: None,
-            });
+            state.pending_operations.push(ScheduledOperation::new(
+                operation_id.clone(),
+                OperationType::ClockOut,
+                expected_clock_out.to_rfc3339(),
+            ));
         }
 
         // Create shared state for the async task
-        let app_handle = self.app_handle.clone();
+        let account = self.account.clone();
         let state = Arc::clone(&self.state);
         let schedule_ref = Arc::clone(&self.schedule);
+        let history = Arc::clone(&self.history);
+        let provider = Arc::clone(&self.provider);
         let operation_id_clone = operation_id.clone();
 
-        let now = chrono::Utc::now();
+        let now = self.effective_now();
         let delay_seconds = expected_clock_out.timestamp() - now.timestamp();
 
         // Handle negative delays (past due times) and very long delays
         let delay_duration = if delay_seconds <= 0 {
-            println!("[Scheduler] External clock-out time is in the past or now ({}), executing immediately", expected_clock_out.to_rfc3339());
+            log::info!("External clock-out time is in the past or now ({}), executing immediately", expected_clock_out.to_rfc3339());
             Duration::from_secs(1) // Execute almost immediately
         } else if delay_seconds > 86400 { // More than 24 hours
-            println!("[Scheduler] WARNING: External clock-out delay is very long ({} seconds = {} hours), capping to 12 hours", delay_seconds, delay_seconds / 3600);
+            log::warn!("External clock-out delay is very long ({} seconds = {} hours), capping to 12 hours", delay_seconds, delay_seconds / 3600);
             Duration::from_secs(43200) // Cap at 12 hours
         } else {
-            println!("[Scheduler] External clock-out scheduled in {} seconds ({:.1} hours)", delay_seconds, delay_seconds as f32 / 3600.0);
+            log::info!("External clock-out scheduled in {} seconds ({:.1} hours)", delay_seconds, delay_seconds as f32 / 3600.0);
             Duration::from_secs(delay_seconds as u64)
         };
 
@@ -882,9 +1279,11 @@ impl BackendScheduler {
 
             // Execute clock out
             let _ = execute_scheduled_clock_out(
-                app_handle,
+                account,
+                provider,
                 state,
                 schedule_ref,
+                history,
                 &operation_id_clone
             ).await;
         });
@@ -896,7 +1295,7 @@ impl BackendScheduler {
         }
 
         // Emit event
-        let _ = self.app_handle.emit("scheduler_event", &SchedulerEvent::ClockOutScheduled {
+        self.account.emit(&SchedulerEvent::ClockOutScheduled {
             operation_id,
             scheduled_time: expected_clock_out.to_rfc3339(),
         });
@@ -908,7 +1307,7 @@ impl BackendScheduler {
     fn has_pending_clock_out(&self) -> bool {
         let state = self.state.lock().unwrap();
         state.pending_operations.iter().any(|op| {
-            matches!(op.operation_type, OperationType::ClockOut) && op.status == "pending"
+            matches!(op.operation_type, OperationType::ClockOut) && !op.status.is_final()
         })
     }
 
@@ -920,65 +1319,284 @@ impl BackendScheduler {
 
 
 /// Call clock-in API using shared token logic (standalone)
-async fn call_clock_in_with_retry_standalone(app_handle: &AppHandle) -> Result<bool, AppError> {
-    crate::token_manager::clock_in_with_shared_tokens(app_handle).await
+async fn call_clock_in_with_retry_standalone(account: &AccountContext, provider: &dyn EmaptaProvider) -> Result<bool, AppError> {
+    crate::token_manager::clock_in_with_shared_tokens(account, provider).await
 }
 
 /// Call clock-out API using shared token logic (standalone)
-async fn call_clock_out_with_retry_standalone(app_handle: &AppHandle) -> Result<bool, AppError> {
-    crate::token_manager::clock_out_with_shared_tokens(app_handle).await
+async fn call_clock_out_with_retry_standalone(account: &AccountContext, provider: &dyn EmaptaProvider) -> Result<bool, AppError> {
+    crate::token_manager::clock_out_with_shared_tokens(account, provider).await
+}
+
+/// Parse an EMAPTA attendance timestamp into UTC.
+///
+/// EMAPTA returns either RFC 3339 strings or naive "YYYY-MM-DD HH:MM:SS"
+/// values; naive values are interpreted in the LOCAL timezone.
+fn parse_emapta_datetime(value: &str) -> Result<DateTime<chrono::Utc>, AppError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .or_else(|_| {
+            // Try parsing without timezone info - IMPORTANT: Assume LOCAL timezone, not UTC
+            if !value.contains('T') {
+                // Format: "2024-10-09 09:00:00" -> parse as local time
+                let with_t = value.replace(' ', "T");
+
+                // Try parsing as local time first (more accurate for EMAPTA times)
+                if let Ok(naive_dt) = chrono::NaiveDateTime::parse_from_str(&with_t, "%Y-%m-%dT%H:%M:%S") {
+                    if let Some(local_dt) = chrono::Local.from_local_datetime(&naive_dt).single() {
+                        let utc_dt = local_dt.with_timezone(&chrono::Utc);
+                        log::info!("Parsed as local time: {} -> UTC: {}", local_dt, utc_dt);
+                        return Ok(utc_dt);
+                    } else {
+                        return Err(AppError::validation("time", "Ambiguous local time"));
+                    }
+                }
+
+                // Fallback: treat as UTC if local parsing fails
+                let with_tz = if with_t.ends_with('Z') || with_t.contains('+') || with_t.contains('-') {
+                    with_t
+                } else {
+                    log::warn!("Falling back to UTC interpretation for time: {}", value);
+                    format!("{}Z", with_t)
+                };
+                DateTime::parse_from_rfc3339(&with_tz)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .map_err(|e| AppError::validation("time", &format!("Invalid external clock-in time format '{}': {}", value, e)))
+            } else {
+                DateTime::parse_from_rfc3339(value)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .map_err(|e| AppError::validation("time", &format!("Invalid external clock-in time format '{}': {}", value, e)))
+            }
+        })
+}
+
+/// First occurrence of the `HH:MM` clock-in time after `now` (today or tomorrow)
+fn next_clock_in_after(clock_in_time: &str, now: DateTime<Local>) -> Result<DateTime<chrono::Utc>, AppError> {
+    let time_parts: Vec<&str> = clock_in_time.split(':').collect();
+    
+    if time_parts.len() != 2 {
+        return Err(AppError::validation("schedule", "Invalid clock-in time format"));
+    }
+    
+    let hour: u32 = time_parts[0].parse()
+        .map_err(|_| AppError::validation("schedule", "Invalid hour in clock-in time"))?;
+    let minute: u32 = time_parts[1].parse()
+        .map_err(|_| AppError::validation("schedule", "Invalid minute in clock-in time"))?;
+    
+    let mut next_clock_in = now.date_naive().and_hms_opt(hour, minute, 0)
+        .ok_or_else(|| AppError::validation("schedule", "Invalid time"))?;
+    
+    // If time has passed today, schedule for tomorrow
+    if next_clock_in <= now.naive_local() {
+        next_clock_in = next_clock_in + chrono::Duration::days(1);
+    }
+    
+    Ok(Local.from_local_datetime(&next_clock_in).single()
+        .ok_or_else(|| AppError::validation("schedule", "Invalid time"))?
+        .with_timezone(&chrono::Utc))
+}
+
+/// Read today's attendance record, treating failures as "unknown"
+async fn fetch_attendance_snapshot(account: &AccountContext, provider: &dyn EmaptaProvider) -> Option<AttendanceItem> {
+    match crate::token_manager::attendance_check_with_shared_tokens(account, provider).await {
+        Ok(item) => item,
+        Err(e) => {
+            log::info!("Attendance snapshot unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// Server timestamp recorded for `operation_type` in an attendance item
+fn action_timestamp(item: &AttendanceItem, operation_type: &OperationType) -> Option<String> {
+    match operation_type {
+        OperationType::ClockIn => item.date_time_in.clone(),
+        OperationType::ClockOut => item.date_time_out.clone(),
+    }
+}
+
+/// Whether attendance already shows the action since the `before` snapshot.
+///
+/// Read errors are returned so callers never repeat a clock call blind.
+async fn clock_action_visible<R, RF>(
+    read_attendance: R,
+    operation_type: &OperationType,
+    before: Option<&AttendanceItem>,
+) -> Result<bool, AppError>
+where
+    R: Fn() -> RF,
+    RF: std::future::Future<Output = Result<Option<AttendanceItem>, AppError>>,
+{
+    let previous = before.and_then(|item| action_timestamp(item, operation_type));
+    let current = read_attendance().await?
+        .and_then(|item| action_timestamp(&item, operation_type));
+    Ok(current.is_some() && current != previous)
+}
+
+/// Confirm a clock action by reading attendance back and comparing the
+/// relevant timestamp (`date_time_in` / `date_time_out`) with the snapshot
+/// taken before the call.
+async fn verify_clock_action(
+    account: &AccountContext,
+    provider: &dyn EmaptaProvider,
+    operation_type: &OperationType,
+    before: Option<&AttendanceItem>,
+) -> ClockVerification {
+    let read_attendance = || crate::token_manager::attendance_check_with_shared_tokens(account, provider);
+    verify_against_attendance(read_attendance, operation_type, before).await
+}
+
+/// `verify_clock_action` against any attendance source
+async fn verify_against_attendance<R, RF>(
+    read_attendance: R,
+    operation_type: &OperationType,
+    before: Option<&AttendanceItem>,
+) -> ClockVerification
+where
+    R: Fn() -> RF,
+    RF: std::future::Future<Output = Result<Option<AttendanceItem>, AppError>>,
+{
+    let field_of = |item: &AttendanceItem| action_timestamp(item, operation_type);
+    let previous = before.and_then(field_of);
+    let mut reason = "Attendance record not available".to_string();
+
+    for attempt in 1..=VERIFICATION_ATTEMPTS {
+        if attempt > 1 {
+            sleep(VERIFICATION_RETRY_DELAY).await;
+        }
+
+        let after = match read_attendance().await {
+            Ok(Some(item)) => item,
+            Ok(None) => {
+                reason = "No attendance record for today after the call".to_string();
+                continue;
+            }
+            Err(e) => {
+                reason = format!("Attendance read-back failed: {}", e);
+                continue;
+            }
+        };
+
+        match field_of(&after) {
+            Some(current) if previous.as_deref() != Some(current.as_str()) => {
+                return match parse_emapta_datetime(&current) {
+                    Ok(server_dt) => {
+                        log::info!("{:?} verified against attendance at {}", operation_type, current);
+                        ClockVerification::Verified { server_time: server_dt.to_rfc3339() }
+                    }
+                    Err(e) => ClockVerification::Unverified {
+                        reason: format!("Server timestamp '{}' could not be parsed: {}", current, e),
+                    },
+                };
+            }
+            Some(current) => {
+                reason = format!("Server timestamp unchanged ({})", current);
+            }
+            None => {
+                reason = "Server attendance has no timestamp for this action".to_string();
+            }
+        }
+    }
+
+    log::info!("{:?} could not be verified: {}", operation_type, reason);
+    ClockVerification::Unverified { reason }
+}
+
+/// Emit the unverified event and record it in the activity log
+async fn report_unverified(account: &AccountContext, operation_type: &OperationType, operation_id: &str, reason: &str) {
+    let event = match operation_type {
+        OperationType::ClockIn => SchedulerEvent::ClockInUnverified {
+            operation_id: operation_id.to_string(),
+            reason: reason.to_string(),
+        },
+        OperationType::ClockOut => SchedulerEvent::ClockOutUnverified {
+            operation_id: operation_id.to_string(),
+            reason: reason.to_string(),
+        },
+    };
+    account.emit(&event);
+
+    if let Some(logger) = account.logger() {
+        let action = match operation_type {
+            OperationType::ClockIn => crate::logging::LogAction::ClockIn,
+            OperationType::ClockOut => crate::logging::LogAction::ClockOut,
+        };
+        let _ = logger.log_clock_unverified(action, operation_id, reason).await;
+    }
 }
 
 /// Execute automatic clock-in (standalone function to avoid Send issues)
 async fn execute_scheduled_clock_in(
-    app_handle: AppHandle,
+    account: AccountContext,
+    provider: SharedProvider,
     state: Arc<Mutex<SchedulerState>>,
     schedule: Arc<Mutex<Option<WorkSchedule>>>,
+    history: Arc<Mutex<OperationHistory>>,
     operation_id: &str,
 ) -> Result<(), AppError> {
-    println!("[Scheduler] Executing automatic clock-in: {}", operation_id);
+    log::info!("Executing automatic clock-in: {}", operation_id);
+
+    start_operation_attempt(&account, &state, operation_id);
+
+    let before = fetch_attendance_snapshot(&account, provider.as_ref()).await;
 
     // Use storage-first pattern with retry logic
-    let result = call_clock_in_with_retry_standalone(&app_handle).await;
+    let result = run_scheduled_attempts(&account, provider.as_ref(), &state, operation_id, &OperationType::ClockIn, before.as_ref(), || {
+        call_clock_in_with_retry_standalone(&account, provider.as_ref())
+    }).await;
 
     let now = chrono::Utc::now().to_rfc3339();
 
+    let verification = match result {
+        Ok(true) => Some(verify_clock_action(&account, provider.as_ref(), &OperationType::ClockIn, before.as_ref()).await),
+        _ => None,
+    };
+    if let Some(ClockVerification::Unverified { reason }) = &verification {
+        report_unverified(&account, &OperationType::ClockIn, operation_id, reason).await;
+    }
+
     // Update operation status
     {
         let mut state = state.lock().unwrap();
         if let Some(operation) = state.pending_operations.iter_mut().find(|op| op.id == operation_id) {
-            operation.actual_time = Some(now.clone());
+            operation.finish_attempt(chrono::Utc::now());
             
             match result {
                 Ok(true) => {
-                    operation.status = "completed".to_string();
+                    let (clock_in_time, status, error_message) = match verification {
+                        Some(ClockVerification::Verified { server_time }) => (server_time, OperationStatus::Completed, None),
+                        Some(ClockVerification::Unverified { reason }) => (now.clone(), OperationStatus::Unverified, Some(reason)),
+                        None => (now.clone(), OperationStatus::Completed, None),
+                    };
+                    operation.error_message = error_message;
+                    let _ = operation.transition(status, &account);
                     
                     // Update session state
-                    let expected_clock_out = calculate_expected_clock_out_time_standalone(&now, &schedule);
+                    let expected_clock_out = calculate_expected_clock_out_time_standalone(&clock_in_time, &schedule);
                     state.current_session.clocked_in = true;
-                    state.current_session.clock_in_time = Some(now.clone());
+                    state.current_session.clock_in_time = Some(clock_in_time.clone());
                     state.current_session.expected_clock_out_time = Some(expected_clock_out);
                     
                     // Emit success event
-                    let _ = app_handle.emit("scheduler_event", &SchedulerEvent::ClockInSucceeded {
+                    account.emit(&SchedulerEvent::ClockInSucceeded {
                         operation_id: operation_id.to_string(),
-                        actual_time: now,
+                        actual_time: clock_in_time,
                     });
                 }
                 Ok(false) => {
-                    operation.status = "failed".to_string();
                     operation.error_message = Some("Clock-in API returned false".to_string());
+                    let _ = operation.transition(OperationStatus::Failed, &account);
                     
-                    let _ = app_handle.emit("scheduler_event", &SchedulerEvent::ClockInFailed {
+                    account.emit(&SchedulerEvent::ClockInFailed {
                         operation_id: operation_id.to_string(),
                         error: "API returned false".to_string(),
                     });
                 }
                 Err(err) => {
-                    operation.status = "failed".to_string();
                     operation.error_message = Some(err.to_string());
+                    let _ = operation.transition(OperationStatus::Failed, &account);
                     
-                    let _ = app_handle.emit("scheduler_event", &SchedulerEvent::ClockInFailed {
+                    account.emit(&SchedulerEvent::ClockInFailed {
                         operation_id: operation_id.to_string(),
                         error: err.to_string(),
                     });
@@ -987,32 +1605,59 @@ async fn execute_scheduled_clock_in(
         }
     }
 
+    archive_finished_operation(&account, &state, &history, operation_id).await;
+
     Ok(())
 }
 
 /// Execute automatic clock-out (standalone function to avoid Send issues)
 async fn execute_scheduled_clock_out(
-    app_handle: AppHandle,
+    account: AccountContext,
+    provider: SharedProvider,
     state: Arc<Mutex<SchedulerState>>,
     _schedule: Arc<Mutex<Option<WorkSchedule>>>,
+    history: Arc<Mutex<OperationHistory>>,
     operation_id: &str,
 ) -> Result<(), AppError> {
-    println!("[Scheduler] Executing automatic clock-out: {}", operation_id);
+    log::info!("Executing automatic clock-out: {}", operation_id);
 
-    // Use storage-first pattern with retry logic
-    let result = call_clock_out_with_retry_standalone(&app_handle).await;
+    start_operation_attempt(&account, &state, operation_id);
 
-    let now = chrono::Utc::now().to_rfc3339();
+    let before = fetch_attendance_snapshot(&account, provider.as_ref()).await;
+
+    // Use storage-first pattern with retry logic
+    let result = run_scheduled_attempts(&account, provider.as_ref(), &state, operation_id, &OperationType::ClockOut, before.as_ref(), || {
+        call_clock_out_with_retry_standalone(&account, provider.as_ref())
+    }).await;
+
+    let mut now = chrono::Utc::now().to_rfc3339();
+
+    let verification = match result {
+        Ok(true) => Some(verify_clock_action(&account, provider.as_ref(), &OperationType::ClockOut, before.as_ref()).await),
+        _ => None,
+    };
+    if let Some(ClockVerification::Unverified { reason }) = &verification {
+        report_unverified(&account, &OperationType::ClockOut, operation_id, reason).await;
+    }
 
     // Update operation status
     {
         let mut state = state.lock().unwrap();
         if let Some(operation) = state.pending_operations.iter_mut().find(|op| op.id == operation_id) {
-            operation.actual_time = Some(now.clone());
+            operation.finish_attempt(chrono::Utc::now());
             
             match result {
                 Ok(true) => {
-                    operation.status = "completed".to_string();
+                    let (status, error_message) = match verification {
+                        Some(ClockVerification::Verified { server_time }) => {
+                            now = server_time;
+                            (OperationStatus::Completed, None)
+                        }
+                        Some(ClockVerification::Unverified { reason }) => (OperationStatus::Unverified, Some(reason)),
+                        None => (OperationStatus::Completed, None),
+                    };
+                    operation.error_message = error_message;
+                    let _ = operation.transition(status, &account);
                     
                     // Update session state
                     state.current_session.clocked_in = false;
@@ -1020,25 +1665,25 @@ async fn execute_scheduled_clock_out(
                     state.current_session.expected_clock_out_time = None;
                     
                     // Emit success event
-                    let _ = app_handle.emit("scheduler_event", &SchedulerEvent::ClockOutSucceeded {
+                    account.emit(&SchedulerEvent::ClockOutSucceeded {
                         operation_id: operation_id.to_string(),
                         actual_time: now,
                     });
                 }
                 Ok(false) => {
-                    operation.status = "failed".to_string();
                     operation.error_message = Some("Clock-out API returned false".to_string());
+                    let _ = operation.transition(OperationStatus::Failed, &account);
                     
-                    let _ = app_handle.emit("scheduler_event", &SchedulerEvent::ClockOutFailed {
+                    account.emit(&SchedulerEvent::ClockOutFailed {
                         operation_id: operation_id.to_string(),
                         error: "API returned false".to_string(),
                     });
                 }
                 Err(err) => {
-                    operation.status = "failed".to_string();
                     operation.error_message = Some(err.to_string());
+                    let _ = operation.transition(OperationStatus::Failed, &account);
                     
-                    let _ = app_handle.emit("scheduler_event", &SchedulerEvent::ClockOutFailed {
+                    account.emit(&SchedulerEvent::ClockOutFailed {
                         operation_id: operation_id.to_string(),
                         error: err.to_string(),
                     });
@@ -1047,10 +1692,141 @@ async fn execute_scheduled_clock_out(
         }
     }
 
+    archive_finished_operation(&account, &state, &history, operation_id).await;
+
     Ok(())
 }
 
 
+/// Move a queued operation to `running` and record the attempt start
+fn start_operation_attempt(account: &AccountContext, state: &Arc<Mutex<SchedulerState>>, operation_id: &str) {
+    let mut state = state.lock().unwrap();
+    if let Some(operation) = state.pending_operations.iter_mut().find(|op| op.id == operation_id) {
+        if operation.transition(OperationStatus::Running, account).is_ok() {
+            operation.begin_attempt(chrono::Utc::now());
+        }
+    }
+}
+
+/// Run a scheduled clock call, retrying transient errors up to `MAX_SCHEDULED_ATTEMPTS`
+///
+/// Between attempts the operation sits in `retrying` with the last error.
+/// See `retry_clock_call` for when a call is repeated.
+async fn run_scheduled_attempts<F, Fut>(
+    account: &AccountContext,
+    provider: &dyn EmaptaProvider,
+    state: &Arc<Mutex<SchedulerState>>,
+    operation_id: &str,
+    operation_type: &OperationType,
+    before: Option<&AttendanceItem>,
+    call: F,
+) -> Result<bool, AppError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<bool, AppError>>,
+{
+    let read_attendance = || crate::token_manager::attendance_check_with_shared_tokens(account, provider);
+    retry_clock_call(call, read_attendance, operation_id, operation_type, before, |event| match event {
+        AttemptEvent::Failed(err) => {
+            let mut state = state.lock().unwrap();
+            if let Some(operation) = state.pending_operations.iter_mut().find(|op| op.id == operation_id) {
+                operation.error_message = Some(err.to_string());
+                let _ = operation.transition(OperationStatus::Retrying, account);
+            }
+        }
+        AttemptEvent::Restarted => start_operation_attempt(account, state, operation_id),
+    }).await
+}
+
+/// Progress of `retry_clock_call`, reported to the operation's status
+enum AttemptEvent<'a> {
+    /// An attempt failed with a transient error and will be retried
+    Failed(&'a AppError),
+    /// The retry delay is over and the next attempt starts
+    Restarted,
+}
+
+/// Call `call` until it succeeds, fails for good or `MAX_SCHEDULED_ATTEMPTS` is reached.
+///
+/// Authentication and client errors fail immediately. The clock call is not
+/// idempotent and a transient error may arrive after the server recorded the
+/// action, so attendance is read back before every re-POST: the retry stops
+/// as a success once the action is visible, and fails with the last error if
+/// attendance cannot be read.
+async fn retry_clock_call<F, Fut, R, RF>(
+    call: F,
+    read_attendance: R,
+    operation_id: &str,
+    operation_type: &OperationType,
+    before: Option<&AttendanceItem>,
+    mut on_event: impl FnMut(AttemptEvent),
+) -> Result<bool, AppError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<bool, AppError>>,
+    R: Fn() -> RF,
+    RF: std::future::Future<Output = Result<Option<AttendanceItem>, AppError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(err) if err.is_retryable() && attempt < MAX_SCHEDULED_ATTEMPTS => {
+                log::warn!("{} attempt {} failed, retrying in {}s: {}",
+                         operation_id, attempt, SCHEDULED_RETRY_DELAY.as_secs(), err);
+                on_event(AttemptEvent::Failed(&err));
+
+                sleep(SCHEDULED_RETRY_DELAY).await;
+                on_event(AttemptEvent::Restarted);
+
+                match clock_action_visible(&read_attendance, operation_type, before).await {
+                    Ok(true) => {
+                        log::info!("{} already recorded by the server, not retrying", operation_id);
+                        return Ok(true);
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        log::warn!("{} not retried, attendance unreadable: {}", operation_id, e);
+                        return Err(err);
+                    }
+                }
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Move a finished operation from the live queue to the operation history
+async fn archive_finished_operation(
+    account: &AccountContext,
+    state: &Arc<Mutex<SchedulerState>>,
+    history: &Arc<Mutex<OperationHistory>>,
+    operation_id: &str,
+) {
+    let finished = {
+        let mut state = state.lock().unwrap();
+        state.pending_operations.iter()
+            .position(|op| op.id == operation_id && op.status.is_final())
+            .map(|index| state.pending_operations.remove(index))
+    };
+
+    if let Some(operation) = finished {
+        if let Err(e) = archive_operations(account, history, vec![operation]).await {
+            log::warn!("Failed to persist operation history: {}", e);
+        }
+    }
+}
+
+/// Current time used for deadlines (standalone)
+fn effective_now_standalone(schedule: &Arc<Mutex<Option<WorkSchedule>>>) -> DateTime<chrono::Utc> {
+    let correct = schedule.lock().unwrap().as_ref().is_some_and(|s| s.correct_clock_skew);
+    if correct {
+        crate::clock_skew::server_now()
+    } else {
+        chrono::Utc::now()
+    }
+}
+
 /// Calculate expected clock-out time (standalone)
 fn calculate_expected_clock_out_time_standalone(
     clock_in_time: &str,
@@ -1068,20 +1844,184 @@ fn calculate_expected_clock_out_time_standalone(
     (chrono::Utc::now() + chrono::Duration::hours(9)).to_rfc3339()
 }
 
-// Global scheduler instance
-static mut SCHEDULER: Option<BackendScheduler> = None;
-static SCHEDULER_INIT: std::sync::Once = std::sync::Once::new();
+// Scheduler instances, one per account
+static SCHEDULERS: RwLock<BTreeMap<String, Arc<BackendScheduler>>> = RwLock::new(BTreeMap::new());
 
-/// Initialize the global scheduler
+/// Load the saved schedule of an account
+pub async fn load_schedule(account: &AccountContext) -> Result<Option<WorkSchedule>, AppError> {
+    let storage = crate::storage::get_storage(account.app_handle())?;
+    documents::load::<WorkSchedule>(&*storage, &account.key(SCHEDULE_KEY), &documents::SCHEDULE).await?
+        .map(WorkSchedule::validate)
+        .transpose()
+}
+
+/// Validate and save the schedule of an account, returning it as stored
+pub async fn save_schedule(account: &AccountContext, schedule: WorkSchedule) -> Result<WorkSchedule, AppError> {
+    let schedule = schedule.validate()?;
+    let storage = crate::storage::get_storage(account.app_handle())?;
+    documents::save(&*storage, &account.key(SCHEDULE_KEY), &documents::SCHEDULE, &schedule).await?;
+    Ok(schedule)
+}
+
+/// Initialize a scheduler for every registered account
 pub fn initialize_scheduler(app_handle: AppHandle) {
-    unsafe {
-        SCHEDULER_INIT.call_once(|| {
-            SCHEDULER = Some(BackendScheduler::new(app_handle));
-        });
+    for account in crate::accounts::list() {
+        add_scheduler(AccountContext::new(app_handle.clone(), account.id), crate::provider::startup_provider());
     }
 }
 
-/// Get the global scheduler instance
-pub fn get_scheduler() -> Option<&'static BackendScheduler> {
-    unsafe { SCHEDULER.as_ref() }
+/// Create the scheduler of a newly added account (an existing one is kept)
+pub fn add_scheduler(account: AccountContext, provider: SharedProvider) -> Arc<BackendScheduler> {
+    let mut schedulers = SCHEDULERS.write().unwrap();
+    let scheduler = schedulers
+        .entry(account.id().to_string())
+        .or_insert_with(|| Arc::new(BackendScheduler::new(account, provider)));
+    Arc::clone(scheduler)
+}
+
+/// Drop the scheduler of a removed account (stop it first)
+pub fn remove_scheduler(account_id: &str) -> Option<Arc<BackendScheduler>> {
+    SCHEDULERS.write().unwrap().remove(account_id)
+}
+
+/// Get the scheduler of an account
+pub fn get_scheduler(account_id: &str) -> Option<Arc<BackendScheduler>> {
+    SCHEDULERS.read().unwrap().get(account_id).cloned()
+}
+
+/// Schedulers of all accounts, ordered by account ID
+pub fn all_schedulers() -> Vec<Arc<BackendScheduler>> {
+    SCHEDULERS.read().unwrap().values().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_provider::{MockCallCounts, MockEmaptaProvider, MockScript};
+
+    type Call<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, AppError>> + 'a>>;
+
+    /// Attendance reader over `mock`, as the token manager would call it
+    fn reader(mock: &MockEmaptaProvider) -> impl Fn() -> Call<'_, Option<AttendanceItem>> + '_ {
+        move || Box::pin(async move { mock.attendance_status("token").await.map_err(|e| e.into_app_error("attendance failed")) })
+    }
+
+    /// Clock-in call over `mock`
+    fn clock_in(mock: &MockEmaptaProvider) -> impl Fn() -> Call<'_, bool> + '_ {
+        move || Box::pin(async move { mock.clock_in("token").await.map_err(|e| e.into_app_error("clock_in failed")) })
+    }
+
+    #[test]
+    fn test_operation_status_transitions() {
+        use OperationStatus::*;
+
+        assert!(Pending.can_transition_to(Running));
+        assert!(Pending.can_transition_to(Cancelled));
+        assert!(Running.can_transition_to(Unverified));
+        assert!(Running.can_transition_to(Retrying));
+        assert!(Retrying.can_transition_to(Running));
+
+        assert!(!Pending.can_transition_to(Completed));
+        assert!(!Completed.can_transition_to(Running));
+        assert!(!Cancelled.can_transition_to(Pending));
+        assert!(!Failed.can_transition_to(Retrying));
+    }
+
+    #[test]
+    fn test_operation_status_serializes_as_legacy_strings() {
+        assert_eq!(serde_json::to_string(&OperationStatus::Pending).unwrap(), "\"pending\"");
+        let status: OperationStatus = serde_json::from_str("\"cancelled\"").unwrap();
+        assert_eq!(status, OperationStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        let schedule = WorkSchedule {
+            auto_schedule_enabled: true,
+            clock_in_time: " 9:05".to_string(),
+            timezone: "Asia/Manila".to_string(),
+            min_work_duration_minutes: 550,
+            correct_clock_skew: false,
+        };
+        assert_eq!(schedule.clone().validate().unwrap().clock_in_time, "09:05");
+
+        let field = |schedule: WorkSchedule| match schedule.validate() {
+            Err(AppError::Validation { field, .. }) => field,
+            other => panic!("expected a validation error, got {:?}", other),
+        };
+        assert_eq!(field(WorkSchedule { clock_in_time: "25:00".to_string(), ..schedule.clone() }), "clockInTime");
+        assert_eq!(field(WorkSchedule { timezone: " ".to_string(), ..schedule.clone() }), "timezone");
+        assert_eq!(field(WorkSchedule { timezone: "Mars/Olympus_Mons".to_string(), ..schedule.clone() }), "timezone");
+        assert_eq!(WorkSchedule { timezone: " America/New_York ".to_string(), ..schedule.clone() }.validate().unwrap().timezone, "America/New_York");
+        assert_eq!(field(WorkSchedule { min_work_duration_minutes: 0, ..schedule }), "minWorkDurationMinutes");
+    }
+
+    #[test]
+    fn test_next_clock_in_follows_the_given_clock() {
+        let at = |hour, minute| Local.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).single().unwrap();
+
+        // The local clock says 08:50, the server already 09:10 (20 minutes of skew)
+        assert_eq!(next_clock_in_after("09:00", at(8, 50)).unwrap(), at(9, 0).with_timezone(&chrono::Utc));
+        assert_eq!(
+            next_clock_in_after("09:00", at(9, 10)).unwrap(),
+            (at(9, 0) + chrono::Duration::days(1)).with_timezone(&chrono::Utc)
+        );
+    }
+
+    #[test]
+    fn test_parse_emapta_datetime() {
+        let parsed = parse_emapta_datetime("2026-03-10T09:00:00+08:00").unwrap();
+        assert_eq!(parsed, chrono::Utc.with_ymd_and_hms(2026, 3, 10, 1, 0, 0).unwrap());
+
+        // Naive timestamps are local time
+        let local = Local.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).single().unwrap();
+        assert_eq!(parse_emapta_datetime("2026-03-10 09:00:00").unwrap(), local.with_timezone(&chrono::Utc));
+
+        assert!(parse_emapta_datetime("yesterday").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_verification_reads_attendance_back() {
+        let mock = MockEmaptaProvider::default();
+        let before = mock.attendance_status("token").await.unwrap();
+        mock.clock_in("token").await.unwrap();
+
+        let verified = verify_against_attendance(reader(&mock), &OperationType::ClockIn, before.as_ref()).await;
+        assert!(matches!(verified, ClockVerification::Verified { .. }));
+
+        // The same snapshot before and after: every read is tried, 2s apart
+        let after = mock.attendance_status("token").await.unwrap();
+        let calls = mock.calls().attendance;
+        let started = tokio::time::Instant::now();
+        let unverified = verify_against_attendance(reader(&mock), &OperationType::ClockIn, after.as_ref()).await;
+        assert!(matches!(unverified, ClockVerification::Unverified { reason } if reason.contains("unchanged")));
+        assert_eq!(mock.calls().attendance - calls, VERIFICATION_ATTEMPTS);
+        assert_eq!(started.elapsed(), VERIFICATION_RETRY_DELAY * (VERIFICATION_ATTEMPTS - 1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_reposts_only_when_the_action_is_not_visible() {
+        // A 503 that the server did not record: the call is repeated
+        let mock = MockEmaptaProvider::new(MockScript { server_error_calls: 1, ..MockScript::default() });
+        let before = MockEmaptaProvider::default().attendance_status("token").await.unwrap();
+        let mut events = Vec::new();
+        let result = retry_clock_call(clock_in(&mock), reader(&mock), "clock_in_1", &OperationType::ClockIn, before.as_ref(), |event| {
+            events.push(matches!(event, AttemptEvent::Failed(_)));
+        }).await;
+        assert!(result.unwrap());
+        assert_eq!(events, vec![true, false]);
+        assert_eq!(mock.calls(), MockCallCounts { clock_in: 2, attendance: 1, ..MockCallCounts::default() });
+
+        // A lost response to a recorded clock-in: no second POST
+        let mock = MockEmaptaProvider::new(MockScript { lost_response_calls: 1, ..MockScript::default() });
+        let result = retry_clock_call(clock_in(&mock), reader(&mock), "clock_in_2", &OperationType::ClockIn, before.as_ref(), |_| {}).await;
+        assert!(result.unwrap());
+        assert_eq!(mock.calls(), MockCallCounts { clock_in: 1, attendance: 1, ..MockCallCounts::default() });
+
+        // Attendance unreadable as well: give up with the clock error instead of posting blind
+        let mock = MockEmaptaProvider::new(MockScript { server_error_calls: 2, ..MockScript::default() });
+        let result = retry_clock_call(clock_in(&mock), reader(&mock), "clock_in_3", &OperationType::ClockIn, before.as_ref(), |_| {}).await;
+        assert!(result.unwrap_err().to_string().contains("clock_in failed"));
+        assert_eq!(mock.calls(), MockCallCounts { clock_in: 1, attendance: 1, ..MockCallCounts::default() });
+    }
 }
@@ -0,0 +1,395 @@
+/*!
+ * Settings Bundle
+ *
+ * Export and import of everything needed to set Black Bird up on another
+ * machine, as one JSON file in the versioned `SETTINGS_BUNDLE` document
+ * envelope:
+ * - Preferences: autostart, HTTP client, endpoint profiles and diagnostic
+ *   log levels (including per-module overrides)
+ * - Every account with its schedule, and optionally its activity log
+ * - Token pairs, only when a passphrase is given: they are encrypted with a
+ *   key derived from it (Argon2id, AES-256-GCM) and never written in the clear
+ *
+ * Import parses and validates the whole bundle against the current schema
+ * (and decrypts the tokens) before anything is applied, so a bad file or
+ * wrong passphrase changes nothing.
+ */
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_dialog::DialogExt;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::accounts::{Account, AccountContext};
+use crate::diagnostics::DiagnosticLogSettings;
+use crate::documents;
+use crate::encryption::{new_salt, StorageCipher};
+use crate::endpoints::EndpointConfig;
+use crate::errors::AppError;
+use crate::http_client::HttpClientConfig;
+use crate::log_store::get_log_store;
+use crate::logging::LogEntry;
+use crate::scheduler::WorkSchedule;
+
+/// Associated data binding the encrypted tokens to their purpose
+const TOKENS_AAD: &str = "settings_bundle_tokens";
+const MIN_PASSPHRASE_LENGTH: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+    /// App version that wrote the bundle
+    pub app_version: String,
+    pub exported_at: String,
+    pub preferences: Preferences,
+    pub accounts: Vec<AccountSettings>,
+    /// Token pairs by account ID, encrypted with the export passphrase
+    #[serde(default)]
+    pub tokens: Option<EncryptedTokens>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preferences {
+    pub autostart: bool,
+    pub http_client: HttpClientConfig,
+    pub endpoints: EndpointConfig,
+    pub diagnostic_logs: DiagnosticLogSettings,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSettings {
+    pub account: Account,
+    #[serde(default)]
+    pub schedule: Option<WorkSchedule>,
+    /// Only present when logs were included in the export
+    #[serde(default)]
+    pub activity_logs: Option<Vec<LogEntry>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedTokens {
+    /// Argon2id salt (base64)
+    pub salt: String,
+    /// Encrypted JSON of the token pairs
+    pub data: String,
+}
+
+/// One account's tokens inside `EncryptedTokens`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundledTokens {
+    refresh_token: String,
+    access_token: String,
+}
+
+impl Drop for BundledTokens {
+    fn drop(&mut self) {
+        self.refresh_token.zeroize();
+        self.access_token.zeroize();
+    }
+}
+
+/// What an export wrote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsExportReport {
+    pub path: String,
+    pub accounts: usize,
+    pub log_entries: usize,
+    pub tokens_included: bool,
+}
+
+/// What an import applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsImportReport {
+    /// Accounts that did not exist yet and were added
+    pub accounts_added: Vec<String>,
+    pub schedules: usize,
+    pub log_entries: usize,
+    /// Accounts whose tokens were restored
+    pub tokens_imported: usize,
+    /// The bundle has tokens but no passphrase was given
+    pub tokens_skipped: bool,
+}
+
+/// Let the user choose where to save (`for_export`) or which bundle to open; `None` when cancelled
+pub async fn pick_path(app_handle: &AppHandle, for_export: bool) -> Result<Option<PathBuf>, AppError> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let dialog = app_handle.dialog().file().add_filter("Black Bird settings", &["json"]);
+    if for_export {
+        let file_name = format!("black-bird-settings-{}.json", chrono::Local::now().format("%Y-%m-%d"));
+        dialog.set_file_name(file_name).save_file(move |path| {
+            let _ = sender.send(path);
+        });
+    } else {
+        dialog.pick_file(move |path| {
+            let _ = sender.send(path);
+        });
+    }
+
+    receiver.await.ok().flatten()
+        .map(|path| path.into_path().map_err(|e| AppError::system(format!("Unusable file path: {}", e))))
+        .transpose()
+}
+
+/// Write the settings bundle to `path`; tokens are included only with a passphrase
+pub async fn export(
+    app_handle: &AppHandle,
+    path: &Path,
+    include_logs: bool,
+    passphrase: Option<&str>,
+) -> Result<SettingsExportReport, AppError> {
+    if let Some(passphrase) = passphrase {
+        check_passphrase(passphrase)?;
+    }
+    let log_store = if include_logs { Some(get_log_store(app_handle)?) } else { None };
+
+    let mut diagnostic_logs = crate::diagnostics::current_settings(app_handle).await?;
+    diagnostic_logs.file_path = None;
+    let preferences = Preferences {
+        autostart: app_handle.autolaunch().is_enabled()
+            .map_err(|e| AppError::system(format!("Failed to check auto-launch status: {}", e)))?,
+        http_client: crate::http_client::current_config(),
+        endpoints: crate::endpoints::current_config(),
+        diagnostic_logs,
+    };
+
+    let mut accounts = Vec::new();
+    let mut tokens = BTreeMap::new();
+    let mut log_entries = 0;
+    for account in crate::accounts::list() {
+        let context = AccountContext::new(app_handle.clone(), account.id.clone());
+        let schedule = match crate::scheduler::load_schedule(&context).await {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                log::warn!("Leaving the schedule of account '{}' out of the export: {}", account.id, e);
+                None
+            }
+        };
+        let activity_logs = log_store.as_ref().map(|store| store.all_entries(&account.id)).transpose()?;
+        log_entries += activity_logs.as_ref().map_or(0, Vec::len);
+
+        if passphrase.is_some() {
+            if let (Some(refresh_token), Some(access_token)) = crate::token_manager::get_stored_tokens(&context).await? {
+                tokens.insert(account.id.clone(), BundledTokens {
+                    refresh_token: refresh_token.to_string(),
+                    access_token: access_token.to_string(),
+                });
+            }
+        }
+        accounts.push(AccountSettings { account, schedule, activity_logs });
+    }
+
+    let tokens = match passphrase {
+        Some(passphrase) => Some(seal_tokens(passphrase, &tokens)?),
+        None => None,
+    };
+    let bundle = SettingsBundle {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        preferences,
+        accounts,
+        tokens,
+    };
+
+    let report = SettingsExportReport {
+        path: path.display().to_string(),
+        accounts: bundle.accounts.len(),
+        log_entries,
+        tokens_included: bundle.tokens.is_some(),
+    };
+    std::fs::write(path, documents::encode(&documents::SETTINGS_BUNDLE, &bundle)?)
+        .map_err(|e| AppError::storage(format!("Failed to write settings bundle '{}': {}", path.display(), e)))?;
+
+    log::info!("Exported settings of {} account(s) to {}", report.accounts, report.path);
+    Ok(report)
+}
+
+/// Validate the bundle at `path` and apply it; tokens are restored only with the export passphrase
+pub async fn import(app_handle: &AppHandle, path: &Path, passphrase: Option<&str>) -> Result<SettingsImportReport, AppError> {
+    let text = Zeroizing::new(std::fs::read_to_string(path)
+        .map_err(|e| AppError::storage(format!("Failed to read settings bundle '{}': {}", path.display(), e)))?);
+    let bundle: SettingsBundle = documents::parse(&documents::SETTINGS_BUNDLE, &path.display().to_string(), &text)?;
+
+    // Validate everything before the first write
+    let tokens = validate(&bundle, passphrase)?;
+    let log_store = get_log_store(app_handle)?;
+
+    let preferences = bundle.preferences;
+    crate::http_client::update_config(app_handle, preferences.http_client).await?;
+    crate::endpoints::update_config(app_handle, preferences.endpoints).await?;
+    crate::diagnostics::replace_settings(app_handle, preferences.diagnostic_logs).await?;
+    let autolaunch = app_handle.autolaunch();
+    let autostart = if preferences.autostart { autolaunch.enable() } else { autolaunch.disable() };
+    autostart.map_err(|e| AppError::system(format!("Failed to update auto-launch: {}", e)))?;
+
+    let mut report = SettingsImportReport {
+        accounts_added: Vec::new(),
+        schedules: 0,
+        log_entries: 0,
+        tokens_imported: 0,
+        tokens_skipped: bundle.tokens.is_some() && passphrase.is_none(),
+    };
+    for settings in bundle.accounts {
+        let account = settings.account;
+        if !crate::accounts::exists(&account.id) {
+            crate::accounts::add_account(app_handle, &account.name, Some(account.id.clone())).await?;
+            crate::scheduler::add_scheduler(
+                AccountContext::new(app_handle.clone(), account.id.clone()),
+                crate::provider::startup_provider(),
+            );
+            report.accounts_added.push(account.id.clone());
+        }
+        let context = AccountContext::new(app_handle.clone(), account.id.clone());
+
+        if let Some(schedule) = settings.schedule {
+            let schedule = crate::scheduler::save_schedule(&context, schedule).await?;
+            if let Some(scheduler) = crate::scheduler::get_scheduler(&account.id) {
+                // Running/stopped is local state; only a running scheduler picks the schedule up
+                scheduler.apply_schedule(schedule).await?;
+            }
+            report.schedules += 1;
+        }
+        if let Some(entries) = &settings.activity_logs {
+            report.log_entries += log_store.insert_batch(&account.id, entries)?;
+        }
+        // Restoring tokens is an explicit choice, so a different signed-in user is replaced
+        if let Some(pair) = tokens.get(&account.id) {
+            crate::token_manager::save_initial_tokens(&context, &pair.refresh_token, &pair.access_token, true).await?;
+            report.tokens_imported += 1;
+        }
+    }
+
+    log::info!("Imported settings from {} ({} account(s) added)", path.display(), report.accounts_added.len());
+    Ok(report)
+}
+
+/// Check the bundle against the current schema and open its tokens
+fn validate(bundle: &SettingsBundle, passphrase: Option<&str>) -> Result<BTreeMap<String, BundledTokens>, AppError> {
+    crate::http_client::build_client(&bundle.preferences.http_client)?;
+    bundle.preferences.endpoints.clone().validate()?;
+    crate::diagnostics::validate_settings(&bundle.preferences.diagnostic_logs)?;
+
+    let mut ids = std::collections::HashSet::new();
+    for settings in &bundle.accounts {
+        crate::accounts::validate_account(&settings.account)?;
+        if !ids.insert(settings.account.id.as_str()) {
+            return Err(AppError::validation("accounts", format!("Account '{}' appears twice", settings.account.id)));
+        }
+        if let Some(schedule) = &settings.schedule {
+            schedule.clone().validate()?;
+        }
+        for entry in settings.activity_logs.iter().flatten() {
+            chrono::DateTime::parse_from_rfc3339(&entry.timestamp).map_err(|e| AppError::validation(
+                "activityLogs", format!("Invalid timestamp '{}' in the log of '{}': {}", entry.timestamp, settings.account.id, e)
+            ))?;
+        }
+    }
+
+    let tokens = match (&bundle.tokens, passphrase) {
+        (Some(sealed), Some(passphrase)) => open_tokens(passphrase, sealed)?,
+        _ => BTreeMap::new(),
+    };
+    if let Some(id) = tokens.keys().find(|id| !ids.contains(id.as_str())) {
+        return Err(AppError::validation("tokens", format!("Tokens for unknown account '{}'", id)));
+    }
+    Ok(tokens)
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(AppError::validation("passphrase", format!(
+            "The passphrase must be at least {} characters long", MIN_PASSPHRASE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+fn seal_tokens(passphrase: &str, tokens: &BTreeMap<String, BundledTokens>) -> Result<EncryptedTokens, AppError> {
+    check_passphrase(passphrase)?;
+    let salt = new_salt();
+    let cipher = StorageCipher::from_passphrase(passphrase, &salt)?;
+    let json = Zeroizing::new(serde_json::to_string(tokens)?);
+    Ok(EncryptedTokens {
+        salt: STANDARD.encode(&salt),
+        data: cipher.encrypt(TOKENS_AAD, &json)?,
+    })
+}
+
+fn open_tokens(passphrase: &str, sealed: &EncryptedTokens) -> Result<BTreeMap<String, BundledTokens>, AppError> {
+    let salt = STANDARD.decode(&sealed.salt)
+        .map_err(|e| AppError::validation("tokens", format!("Invalid token salt: {}", e)))?;
+    let json = StorageCipher::from_passphrase(passphrase, &salt)?
+        .decrypt(TOKENS_AAD, &sealed.data)
+        .map_err(|_| AppError::validation("passphrase", "Wrong passphrase, or the bundle was modified"))?;
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::validation("tokens", format!("Invalid token data: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(tokens: Option<EncryptedTokens>) -> SettingsBundle {
+        SettingsBundle {
+            app_version: "0.1.0".to_string(),
+            exported_at: "2026-10-01T08:00:00+00:00".to_string(),
+            preferences: Preferences {
+                autostart: true,
+                http_client: HttpClientConfig::default(),
+                endpoints: EndpointConfig::default(),
+                diagnostic_logs: DiagnosticLogSettings::default(),
+            },
+            accounts: vec![AccountSettings {
+                account: Account { id: "default".to_string(), name: "Default".to_string(), created_at: String::new() },
+                schedule: None,
+                activity_logs: None,
+            }],
+            tokens,
+        }
+    }
+
+    #[test]
+    fn test_tokens_need_the_passphrase() {
+        let pairs = BTreeMap::from([("default".to_string(), BundledTokens {
+            refresh_token: "refresh-value".to_string(),
+            access_token: "access-value".to_string(),
+        })]);
+        assert!(seal_tokens("short", &pairs).is_err());
+
+        let sealed = seal_tokens("correct horse", &pairs).unwrap();
+        assert!(!sealed.data.contains("refresh-value"));
+        let text = documents::encode(&documents::SETTINGS_BUNDLE, &bundle(Some(sealed))).unwrap();
+
+        let parsed: SettingsBundle = documents::parse(&documents::SETTINGS_BUNDLE, "bundle.json", &text).unwrap();
+        assert!(validate(&parsed, Some("wrong horse")).is_err());
+        assert!(validate(&parsed, None).unwrap().is_empty());
+        assert_eq!(validate(&parsed, Some("correct horse")).unwrap()["default"].refresh_token, "refresh-value");
+    }
+
+    #[test]
+    fn test_rejects_invalid_bundles() {
+        let mut invalid = bundle(None);
+        invalid.preferences.diagnostic_logs.level = "loud".to_string();
+        assert!(validate(&invalid, None).is_err());
+
+        let mut duplicate = bundle(None);
+        duplicate.accounts.push(bundle(None).accounts.remove(0));
+        assert!(validate(&duplicate, None).is_err());
+
+        // Not enveloped, or from a newer version
+        assert!(documents::parse::<SettingsBundle>(&documents::SETTINGS_BUNDLE, "bundle.json", "{}").is_err());
+        let newer = documents::encode(&documents::SETTINGS_BUNDLE, &bundle(None)).unwrap()
+            .replace("\"schemaVersion\":1", "\"schemaVersion\":2");
+        assert!(documents::parse::<SettingsBundle>(&documents::SETTINGS_BUNDLE, "bundle.json", &newer).is_err());
+    }
+}
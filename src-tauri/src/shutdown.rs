@@ -0,0 +1,217 @@
+/*!
+ * Graceful Shutdown
+ *
+ * Quitting while clocked in would silently drop the pending clock-out task.
+ * This module runs the quit workflow instead of exiting immediately:
+ * - Detect active sessions (of any account) and ask whether to clock out now
+ *   or keep them
+ * - Stop every scheduler, cancel its tasks and persist its open session
+ * - Record the chosen outcome in each account's activity log before exiting
+ */
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult};
+
+use crate::logging::LogStatus;
+use crate::scheduler::{all_schedulers, BackendScheduler};
+
+const CLOCK_OUT_LABEL: &str = "Clock out now";
+const KEEP_SESSION_LABEL: &str = "Keep session";
+const CANCEL_LABEL: &str = "Cancel";
+
+/// Guards against running the workflow twice (e.g. double-clicking "Quit")
+static SHUTDOWN_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// What to do with an active EMAPTA session when quitting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownChoice {
+    /// Clock out on the server before exiting
+    ClockOut,
+    /// Leave the server session open (the scheduled clock-out will not run)
+    KeepSession,
+}
+
+/// Start the quit workflow, prompting the user when a session is active
+pub fn request_shutdown<R: Runtime>(app: &AppHandle<R>) {
+    if SHUTDOWN_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        log::info!("Shutdown already in progress");
+        return;
+    }
+
+    let sessions = active_sessions();
+    if sessions.is_empty() {
+        log::info!("No active session, shutting down");
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { run_shutdown(app, None).await });
+        return;
+    }
+
+    log::info!("{} active session(s) detected, asking user", sessions.len());
+    let clocked_in = sessions.iter()
+        .map(|(scheduler, clock_in_time)| format!(
+            "{} (since {})", account_name(scheduler), format_local_time(clock_in_time)
+        ))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // Bring the window forward so the dialog is not hidden behind other apps
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+
+    let app_for_result = app.clone();
+    app.dialog()
+        .message(format!(
+            "You are still clocked in: {}.\n\nClock out now, or keep the EMAPTA session open? \
+             If you keep it, the scheduled clock-out will not run while Black Bird is closed.",
+            clocked_in
+        ))
+        .title("Quit Black Bird")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            CLOCK_OUT_LABEL.to_string(),
+            KEEP_SESSION_LABEL.to_string(),
+            CANCEL_LABEL.to_string(),
+        ))
+        .show_with_result(move |result| {
+            let choice = match result {
+                MessageDialogResult::Yes => Some(ShutdownChoice::ClockOut),
+                MessageDialogResult::No => Some(ShutdownChoice::KeepSession),
+                MessageDialogResult::Custom(label) if label == CLOCK_OUT_LABEL => Some(ShutdownChoice::ClockOut),
+                MessageDialogResult::Custom(label) if label == KEEP_SESSION_LABEL => Some(ShutdownChoice::KeepSession),
+                _ => None,
+            };
+
+            match choice {
+                Some(choice) => {
+                    tauri::async_runtime::spawn(async move {
+                        run_shutdown(app_for_result, Some(choice)).await
+                    });
+                }
+                None => {
+                    log::info!("Quit cancelled by user");
+                    SHUTDOWN_IN_PROGRESS.store(false, Ordering::SeqCst);
+                }
+            }
+        });
+}
+
+/// Run the shutdown sequence and exit the application, unless a shutdown is already under way
+///
+/// `choice` is only consulted for accounts with an active session.
+pub async fn perform_shutdown<R: Runtime>(app: AppHandle<R>, choice: Option<ShutdownChoice>) {
+    if SHUTDOWN_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        log::info!("Shutdown already in progress");
+        return;
+    }
+    run_shutdown(app, choice).await;
+}
+
+/// The shutdown sequence, run by whoever set `SHUTDOWN_IN_PROGRESS`
+async fn run_shutdown<R: Runtime>(app: AppHandle<R>, choice: Option<ShutdownChoice>) {
+    let sessions = active_sessions();
+
+    // Each account with an open session records its own outcome
+    let mut outcomes = Vec::new();
+    for (scheduler, clock_in_time) in sessions {
+        let outcome = close_session(&scheduler, &clock_in_time, choice).await;
+        outcomes.push((scheduler, outcome));
+    }
+
+    // Stop scheduling, cancel tasks and persist open sessions
+    for scheduler in all_schedulers() {
+        if let Err(e) = scheduler.prepare_shutdown().await {
+            log::warn!("Failed to persist scheduler state of '{}': {}", scheduler.account().id(), e);
+        }
+    }
+
+    // Record the outcome; awaiting the write ensures it reaches disk before exit
+    if outcomes.is_empty() {
+        let details = "Application quit with no active session".to_string();
+        log::info!("{}", details);
+        if let Some(logger) = crate::logging::get_logger() {
+            if let Err(e) = logger.log_app_shutdown(LogStatus::Info, details).await {
+                log::warn!("Failed to write shutdown log entry: {}", e);
+            }
+        }
+    }
+    for (scheduler, (status, details)) in outcomes {
+        log::info!("{}: {}", scheduler.account().id(), details);
+        if let Some(logger) = scheduler.account().logger() {
+            if let Err(e) = logger.log_app_shutdown(status, details).await {
+                log::warn!("Failed to write shutdown log entry: {}", e);
+            }
+        }
+    }
+
+    app.exit(0);
+}
+
+/// Clock out or keep one account's session, describing the outcome for its activity log
+async fn close_session(
+    scheduler: &BackendScheduler,
+    clock_in_time: &str,
+    choice: Option<ShutdownChoice>,
+) -> (LogStatus, String) {
+    if choice == Some(ShutdownChoice::ClockOut) {
+        log::info!("Clocking out of '{}' before quitting...", scheduler.account().id());
+        return match scheduler.manual_clock_out(true).await {
+            Ok(true) => (LogStatus::Success, "Application quit after clocking out".to_string()),
+            Ok(false) => (
+                LogStatus::Failed,
+                "Application quit; clock-out before quitting was rejected and the session is still open".to_string(),
+            ),
+            Err(e) => (
+                LogStatus::Failed,
+                format!("Application quit; clock-out before quitting failed and the session is still open: {}", e),
+            ),
+        };
+    }
+
+    let expected = scheduler.get_state().current_session.expected_clock_out_time
+        .map(|t| format_local_time(&t))
+        .unwrap_or_else(|| "unknown".to_string());
+    (
+        LogStatus::Warning,
+        format!(
+            "Application quit while clocked in since {}; server session kept open (clock-out due {} will not run)",
+            format_local_time(clock_in_time), expected
+        ),
+    )
+}
+
+/// Accounts whose scheduler believes a session is open, with its clock-in time
+fn active_sessions() -> Vec<(Arc<BackendScheduler>, String)> {
+    all_schedulers()
+        .into_iter()
+        .filter_map(|scheduler| {
+            let session = scheduler.get_state().current_session;
+            if !session.clocked_in {
+                return None;
+            }
+            let clock_in_time = session.clock_in_time.unwrap_or_else(|| "an unknown time".to_string());
+            Some((scheduler, clock_in_time))
+        })
+        .collect()
+}
+
+/// Display name of a scheduler's account
+fn account_name(scheduler: &BackendScheduler) -> String {
+    crate::accounts::list()
+        .into_iter()
+        .find(|account| account.id == scheduler.account().id())
+        .map(|account| account.name)
+        .unwrap_or_else(|| scheduler.account().id().to_string())
+}
+
+/// Format an RFC 3339 timestamp as local "HH:MM AM", passing other values through
+fn format_local_time(value: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%I:%M %p").to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
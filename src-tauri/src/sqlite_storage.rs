@@ -0,0 +1,168 @@
+/*!
+ * SQLite Storage
+ *
+ * `Storage` in a single SQLite database (`storage.db` in the app data
+ * directory) instead of one file per key:
+ * - Writes are transactional in WAL mode, so a crash never leaves a
+ *   half-written value and batches commit as one transaction
+ * - Values are encrypted with the same cipher as the file backend
+ * - When the database is first created, existing file storage in the same
+ *   directory is imported so switching backends keeps tokens and settings
+ */
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::commands::{StorageError, StorageResult};
+use crate::encryption::{is_encrypted, StorageCipher};
+use crate::errors::AppError;
+use crate::storage::Storage;
+
+/// Database file in the app data directory
+pub const DATABASE_FILE: &str = "storage.db";
+
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+    /// Encrypts values when set
+    cipher: Option<Arc<StorageCipher>>,
+}
+
+impl SqliteStorage {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &Path, cipher: Option<Arc<StorageCipher>>) -> Result<Self, StorageError> {
+        let connection = Connection::open(path).map_err(sqlite_error)?;
+        connection.pragma_update(None, "journal_mode", "WAL").map_err(sqlite_error)?;
+        connection.pragma_update(None, "synchronous", "FULL").map_err(sqlite_error)?;
+        Self::with_connection(connection, cipher)
+    }
+
+    /// Unencrypted database that lives only as long as the instance (for tests)
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::with_connection(Connection::open_in_memory().map_err(sqlite_error)?, None)
+    }
+
+    fn with_connection(connection: Connection, cipher: Option<Arc<StorageCipher>>) -> Result<Self, StorageError> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entries (
+                key TEXT PRIMARY KEY NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
+        ).map_err(sqlite_error)?;
+        Ok(Self { connection: Mutex::new(connection), cipher })
+    }
+
+    /// Whether the database holds no entries yet
+    pub fn is_empty(&self) -> Result<bool, StorageError> {
+        let connection = self.connection.lock().unwrap();
+        let count: i64 = connection.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .map_err(sqlite_error)?;
+        Ok(count == 0)
+    }
+
+    fn seal(&self, key: &str, data: &str) -> Result<String, StorageError> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(key, data),
+            None => Ok(data.to_string()),
+        }
+    }
+
+    fn open_value(&self, key: &str, stored: String) -> Result<String, StorageError> {
+        match &self.cipher {
+            Some(cipher) if is_encrypted(&stored) => {
+                let mut plaintext = cipher.decrypt(key, &stored)?;
+                Ok(std::mem::take(&mut *plaintext))
+            }
+            Some(_) => Err(AppError::encryption(format!("Stored value of '{}' is not encrypted", key))),
+            None => Ok(stored),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn store(&self, key: &str, data: &str) -> Result<StorageResult, StorageError> {
+        self.store_batch(&[(key, data)]).await?;
+        Ok(StorageResult {
+            success: true,
+            message: format!("Data stored in database: {}", key),
+        })
+    }
+
+    async fn store_batch(&self, entries: &[(&str, &str)]) -> Result<StorageResult, StorageError> {
+        let sealed = entries.iter()
+            .map(|(key, data)| Ok((*key, self.seal(key, data)?)))
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        for (key, value) in &sealed {
+            transaction.execute(
+                "INSERT INTO entries (key, value, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                params![key, value, now],
+            ).map_err(sqlite_error)?;
+        }
+        transaction.commit().map_err(sqlite_error)?;
+
+        Ok(StorageResult {
+            success: true,
+            message: format!("Stored {} keys atomically", entries.len()),
+        })
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<Option<String>, StorageError> {
+        let stored: Option<String> = {
+            let connection = self.connection.lock().unwrap();
+            connection.query_row("SELECT value FROM entries WHERE key = ?1", params![key], |row| row.get(0))
+                .optional()
+                .map_err(sqlite_error)?
+        };
+        stored.map(|stored| self.open_value(key, stored)).transpose()
+    }
+
+    async fn delete(&self, key: &str) -> Result<StorageResult, StorageError> {
+        let deleted = self.connection.lock().unwrap()
+            .execute("DELETE FROM entries WHERE key = ?1", params![key])
+            .map_err(sqlite_error)?;
+        Ok(StorageResult {
+            success: true,
+            message: if deleted > 0 { format!("Data deleted: {}", key) } else { "Key not found (already deleted)".to_string() },
+        })
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>, StorageError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT key FROM entries ORDER BY key").map_err(sqlite_error)?;
+        let keys = statement.query_map([], |row| row.get(0))
+            .map_err(sqlite_error)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(sqlite_error)?;
+        Ok(keys)
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> AppError {
+    AppError::storage(format!("SQLite error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::exercise_storage;
+
+    #[tokio::test]
+    async fn test_sqlite_storage() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        assert!(storage.is_empty().unwrap());
+        exercise_storage(&storage).await;
+    }
+}
@@ -1,96 +1,206 @@
-use crate::commands::{StorageError, StorageResult};
-use serde::{Deserialize, Serialize};
-use std::fs;
+/*!
+ * Storage
+ *
+ * Key-value persistence behind one trait, so callers do not depend on where
+ * values end up:
+ * - `FileStorage` (in `file_storage.rs`): one encrypted file per key in the
+ *   app data directory, the default
+ * - `SqliteStorage` (in `sqlite_storage.rs`): a single encrypted database,
+ *   selected with `BLACK_BIRD_STORAGE=sqlite`
+ * - `MemoryStorage` (in `memory_storage.rs`): in-memory, for tests
+ *
+ * Either persistent backend is wrapped in `KeyringStorage`, which keeps the
+ * token keys in the platform keyring when one is available. One instance is
+ * opened during setup and held in Tauri managed state; everything else
+ * reaches it through `get_storage()`. Passphrase-protected storage launched
+ * without its passphrase stays locked until `unlock_storage`.
+ */
+
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use serde::Serialize;
 use tauri::{AppHandle, Manager};
 
-#[derive(Debug, Clone)]
-pub struct StorageConfig {
-    pub app_data_dir: PathBuf,
-    pub encryption_enabled: bool,
+use crate::commands::{StorageError, StorageResult};
+use crate::encryption::{recorded_key_mode, KeyMode, StorageCipher};
+use crate::errors::AppError;
+use crate::file_storage::FileStorage;
+use crate::keyring_store::{KeyringStorage, PlatformKeyring};
+use crate::sqlite_storage::{SqliteStorage, DATABASE_FILE};
+
+/// Environment variable selecting the storage backend (`file` or `sqlite`)
+const STORAGE_ENV_VAR: &str = "BLACK_BIRD_STORAGE";
+
+/// String values stored under string keys
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Short name for logs (`file`, `sqlite`, `memory`)
+    fn name(&self) -> &'static str;
+
+    /// Store a value, replacing any previous one
+    async fn store(&self, key: &str, data: &str) -> Result<StorageResult, StorageError>;
+
+    /// Store several keys so that after a crash either all or none of the new values are visible
+    async fn store_batch(&self, entries: &[(&str, &str)]) -> Result<StorageResult, StorageError>;
+
+    /// Value stored under `key`, if any
+    async fn retrieve(&self, key: &str) -> Result<Option<String>, StorageError>;
+
+    /// Remove a key (succeeds when it does not exist)
+    async fn delete(&self, key: &str) -> Result<StorageResult, StorageError>;
+
+    /// Stored keys, sorted
+    async fn list_keys(&self) -> Result<Vec<String>, StorageError>;
 }
 
-pub struct StorageBackend {
-    config: StorageConfig,
-    app_handle: AppHandle,
+/// Shared handle to the app's storage
+pub type SharedStorage = Arc<dyn Storage>;
+
+/// Managed state holding the storage opened at startup
+struct StorageState(SharedStorage);
+
+/// Open the configured backend in `app_data_dir`
+pub async fn open_storage(app_data_dir: PathBuf) -> Result<SharedStorage, StorageError> {
+    std::fs::create_dir_all(&app_data_dir)?;
+    let cipher = StorageCipher::for_dir(&app_data_dir)?;
+
+    let backend = std::env::var(STORAGE_ENV_VAR).unwrap_or_default();
+    let inner: Box<dyn Storage> = match backend.trim().to_lowercase().as_str() {
+        "sqlite" => {
+            let database = SqliteStorage::open(&app_data_dir.join(DATABASE_FILE), Some(cipher.clone()))?;
+            if database.is_empty()? {
+                let files = FileStorage::open(app_data_dir, Some(cipher))?;
+                let imported = copy_all(&files, &database).await?;
+                if imported > 0 {
+                    log::info!("Imported {} keys from file storage into the database", imported);
+                }
+            }
+            Box::new(database)
+        }
+        "" | "file" => Box::new(FileStorage::open(app_data_dir, Some(cipher))?),
+        other => {
+            return Err(AppError::configuration(format!(
+                "Unknown storage backend '{}' in {} (expected 'file' or 'sqlite')", other, STORAGE_ENV_VAR
+            )));
+        }
+    };
+
+    log::info!("Using {} storage", inner.name());
+    Ok(Arc::new(KeyringStorage::new(inner, Arc::new(PlatformKeyring))))
 }
 
-impl StorageBackend {
-    pub fn new(app_handle: AppHandle) -> Result<Self, StorageError> {
-        let app_data_dir = app_handle.path().app_data_dir().map_err(StorageError::from)?;
-        fs::create_dir_all(&app_data_dir)?;
-        
-        let config = StorageConfig {
-            app_data_dir,
-            encryption_enabled: true,
-        };
-        
-        Ok(Self { config, app_handle })
-    }
-    
-    fn get_file_path(&self, key: &str) -> PathBuf {
-        let extension = if self.config.encryption_enabled { "enc" } else { "json" };
-        self.config.app_data_dir.join(format!("{}.{}", key, extension))
-    }
-    
-    pub async fn store(&self, key: &str, data: &str) -> Result<StorageResult, StorageError> {
-        let file_path = self.get_file_path(key);
-        fs::write(&file_path, data)?;
-        Ok(StorageResult {
-            success: true,
-            message: format!("Data stored successfully: {:?}", file_path),
-        })
-    }
-    
-    pub async fn retrieve(&self, key: &str) -> Result<Option<String>, StorageError> {
-        let file_path = self.get_file_path(key);
-        if !file_path.exists() {
-            return Ok(None);
+/// Copy every key of `from` into `to` in one batch; returns the number of keys copied
+pub async fn copy_all(from: &dyn Storage, to: &dyn Storage) -> Result<usize, StorageError> {
+    let mut entries = Vec::new();
+    for key in from.list_keys().await? {
+        if let Some(value) = from.retrieve(&key).await? {
+            entries.push((key, value));
         }
-        let data = fs::read_to_string(&file_path)?;
-        Ok(Some(data))
     }
-    
-    pub async fn delete(&self, key: &str) -> Result<StorageResult, StorageError> {
-        let file_path = self.get_file_path(key);
-        if file_path.exists() {
-            fs::remove_file(&file_path)?;
-            Ok(StorageResult {
-                success: true,
-                message: format!("Data deleted: {:?}", file_path),
-            })
-        } else {
-            Ok(StorageResult {
-                success: true,
-                message: "File not found (already deleted)".to_string(),
-            })
-        }
+    if entries.is_empty() {
+        return Ok(0);
     }
-    
-    pub async fn list_keys(&self) -> Result<Vec<String>, StorageError> {
-        let mut keys = Vec::new();
-        if !self.config.app_data_dir.exists() {
-            return Ok(keys);
-        }
-        
-        for entry in fs::read_dir(&self.config.app_data_dir)? {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            
-            if let Some(name_str) = file_name.to_str() {
-                let extension = if self.config.encryption_enabled { ".enc" } else { ".json" };
-                if name_str.ends_with(extension) {
-                    let key = name_str.trim_end_matches(extension);
-                    keys.push(key.to_string());
-                }
-            }
-        }
-        
-        keys.sort();
-        Ok(keys)
+
+    let borrowed: Vec<(&str, &str)> = entries.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+    to.store_batch(&borrowed).await?;
+    Ok(entries.len())
+}
+
+/// Make `storage` the app's storage (called once during setup)
+pub fn manage_storage(app_handle: &AppHandle, storage: SharedStorage) {
+    app_handle.manage(StorageState(storage));
+}
+
+/// Why the storage could not be opened at startup (cleared once it is unlocked)
+static LOCKED_REASON: Mutex<Option<String>> = Mutex::new(None);
+/// Serializes unlock attempts, so storage is opened and managed once
+static UNLOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Whether the storage is open, for the unlock screen
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageStatus {
+    pub unlocked: bool,
+    pub key_mode: Option<KeyMode>,
+    /// Why the storage is locked
+    pub error: Option<String>,
+}
+
+/// Leave the storage locked at startup, waiting for `unlock_storage`
+pub fn set_locked(reason: String) {
+    *LOCKED_REASON.lock().unwrap() = Some(reason);
+}
+
+/// Current state of the app's storage
+pub fn storage_status(app_handle: &AppHandle) -> Result<StorageStatus, StorageError> {
+    Ok(StorageStatus {
+        unlocked: get_storage(app_handle).is_ok(),
+        key_mode: recorded_key_mode(&app_handle.path().app_data_dir()?)?,
+        error: LOCKED_REASON.lock().unwrap().clone(),
+    })
+}
+
+/// Open storage that was locked at startup with its passphrase.
+///
+/// Returns `false` when the storage was already open.
+pub async fn unlock_storage(app_handle: &AppHandle, passphrase: &str) -> Result<bool, StorageError> {
+    let _guard = UNLOCK.lock().await;
+    if get_storage(app_handle).is_ok() {
+        return Ok(false);
     }
+
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    StorageCipher::unlock(&app_data_dir, passphrase)?;
+    manage_storage(app_handle, open_storage(app_data_dir).await?);
+    *LOCKED_REASON.lock().unwrap() = None;
+    Ok(true)
 }
 
-pub fn create_storage_backend(app_handle: AppHandle) -> Result<StorageBackend, StorageError> {
-    StorageBackend::new(app_handle)
+/// The app's storage
+pub fn get_storage(app_handle: &AppHandle) -> Result<SharedStorage, StorageError> {
+    app_handle.try_state::<StorageState>()
+        .map(|state| state.0.clone())
+        .ok_or_else(|| AppError::storage("Storage is not initialized yet"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::memory_storage::MemoryStorage;
+
+    /// Fresh directory under the system temp dir
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("black-bird-{}-{}-{}", name, std::process::id(), rand::random::<u32>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Behaviour every backend must share
+    pub async fn exercise_storage(storage: &dyn Storage) {
+        assert_eq!(storage.retrieve("missing").await.unwrap(), None);
+
+        storage.store("user_schedule", "{\"a\":1}").await.unwrap();
+        storage.store("user_schedule", "{\"a\":2}").await.unwrap();
+        assert_eq!(storage.retrieve("user_schedule").await.unwrap().as_deref(), Some("{\"a\":2}"));
+
+        storage.store_batch(&[("refresh_token", "refresh"), ("access_token", "access")]).await.unwrap();
+        assert_eq!(storage.retrieve("access_token").await.unwrap().as_deref(), Some("access"));
+        assert_eq!(storage.list_keys().await.unwrap(), vec!["access_token", "refresh_token", "user_schedule"]);
+
+        storage.delete("access_token").await.unwrap();
+        storage.delete("access_token").await.unwrap();
+        assert_eq!(storage.retrieve("access_token").await.unwrap(), None);
+        assert_eq!(storage.list_keys().await.unwrap(), vec!["refresh_token", "user_schedule"]);
+    }
+
+    #[tokio::test]
+    async fn test_copy_all() {
+        let from = MemoryStorage::new();
+        from.store_batch(&[("a", "1"), ("b", "2")]).await.unwrap();
+
+        let to = MemoryStorage::new();
+        assert_eq!(copy_all(&from, &to).await.unwrap(), 2);
+        assert_eq!(to.retrieve("b").await.unwrap().as_deref(), Some("2"));
+    }
 }
@@ -3,95 +3,536 @@
  *
  * Implements the shared token logic pattern for all API operations:
  * 1. Try with saved access token first
- * 2. Only refresh on token-related errors (401, invalid_token, etc.)
- * 3. Single retry after token refresh
- * 4. Fixed storage keys that overwrite previous tokens
+ * 2. Only refresh on token-related errors (see `EmaptaApiError::requires_token_refresh`)
+ * 3. Single retry after token refresh (one refresh shared by concurrent callers)
+ * 4. Fixed storage keys that overwrite previous tokens (one pair per account)
+ *
+ * A background task also refreshes ahead of the access token's `exp`, so
+ * scheduled clock actions rarely pay for a refresh on the critical path,
+ * and warns before the refresh token itself runs out.
  */
 
-use crate::errors::AppError;
-use crate::storage::create_storage_backend;
-use crate::commands::{exchange_refresh_token_api, TokenResponse};
+use crate::accounts::{storage_key, AccountContext};
+use crate::errors::{AppError, EmaptaApiError};
+use crate::documents;
+use crate::storage::{get_storage, Storage};
+use crate::provider::EmaptaProvider;
+use crate::jwt::{decode_claims, JwtClaims};
+use crate::logging::ActivityLogger;
+use crate::scheduler::SchedulerEvent;
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zeroize::Zeroizing;
 
-// Fixed storage keys - never change these
+// Fixed storage keys - never change these (scoped per account by `AccountContext::key`)
 const REFRESH_TOKEN_KEY: &str = "refresh_token";
 const ACCESS_TOKEN_KEY: &str = "access_token";
 
-/// Check if an error is token-related (requires refresh)
-pub fn is_token_error(error: &str) -> bool {
-    error.contains("401") ||
-    error.contains("unauthorized") ||
-    error.contains("invalid_token") ||
-    error.contains("token_expired") ||
-    error.contains("Unauthorized") ||
-    error.contains("Token") && error.contains("expired")
+/// Per-account locks serializing token refreshes and token writes (see `refresh_and_save_tokens`)
+static REFRESH_LOCKS: Mutex<Option<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = Mutex::new(None);
+
+/// The refresh lock of one account
+fn refresh_lock(account_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = REFRESH_LOCKS.lock().unwrap();
+    Arc::clone(locks.get_or_insert_with(HashMap::new).entry(account_id.to_string()).or_default())
+}
+
+/// Accounts signed out in this session; background work leaves them alone until the next sign-in
+static SIGNED_OUT: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Whether an account was signed out and has not been set up again since
+pub fn is_signed_out(account_id: &str) -> bool {
+    SIGNED_OUT.lock().unwrap().as_ref().is_some_and(|accounts| accounts.contains(account_id))
+}
+
+fn set_signed_out(account_id: &str, signed_out: bool) {
+    let mut accounts = SIGNED_OUT.lock().unwrap();
+    let accounts = accounts.get_or_insert_with(HashSet::new);
+    if signed_out {
+        accounts.insert(account_id.to_string());
+    } else {
+        accounts.remove(account_id);
+    }
+}
+
+/// Validation field reported when new tokens belong to a different subject; setting it on
+/// the setup command confirms the switch
+pub const IDENTITY_CHANGE_FIELD: &str = "confirmIdentityChange";
+
+/// Whether `error` is the refusal of tokens issued to a different subject (see `save_initial_tokens`)
+pub fn is_identity_change(error: &AppError) -> bool {
+    matches!(error, AppError::Validation { field, .. } if field == IDENTITY_CHANGE_FIELD)
+}
+
+/// Refresh the access token once it has less than this left (seconds)
+const ACCESS_TOKEN_REFRESH_AHEAD_SECS: i64 = 5 * 60;
+/// Warn when the refresh token has less than this left (seconds), capped at half its lifetime
+const REFRESH_TOKEN_WARNING_SECS: i64 = 24 * 60 * 60;
+/// How often the background task re-checks token lifetimes
+const PROACTIVE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Back-off after a failed proactive refresh (reactive refresh still covers API calls)
+const PROACTIVE_FAILURE_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Access and refresh token as stored after a refresh (wiped from memory on drop)
+#[derive(Debug, Clone)]
+pub struct TokenPair {
+    pub access_token: Zeroizing<String>,
+    pub refresh_token: Zeroizing<String>,
+}
+
+/// Lifetime of one stored token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenLifetime {
+    /// Issued-at time (ISO 8601 UTC), if the token is a JWT carrying `iat`
+    pub issued_at: Option<String>,
+    /// Expiry time (ISO 8601 UTC), if the token is a JWT carrying `exp`
+    pub expires_at: Option<String>,
+    /// Seconds until expiry (negative once expired)
+    pub expires_in_secs: Option<i64>,
+    pub expired: bool,
+}
+
+/// Lifetimes of the stored tokens (`None` when a token is not stored)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStatus {
+    pub access_token: Option<TokenLifetime>,
+    pub refresh_token: Option<TokenLifetime>,
+    /// When the background task will refresh the access token
+    pub next_refresh_at: Option<String>,
+    /// The refresh token is close to expiry and setup will soon have to be redone
+    pub refresh_token_expiring: bool,
+    /// Who the stored access token was issued to
+    pub identity: Option<TokenIdentity>,
+}
+
+/// Owner of a stored access token, read from its claims without calling the network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenIdentity {
+    pub subject: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub preferred_username: Option<String>,
+    pub issuer: Option<String>,
+    /// Access token expiry (ISO 8601 UTC)
+    pub expires_at: Option<String>,
+    pub expired: bool,
+}
+
+impl TokenIdentity {
+    fn from_claims(claims: JwtClaims, now: i64) -> Self {
+        Self {
+            expires_at: claims.exp.and_then(format_unix),
+            expired: claims.expires_in(now).is_some_and(|secs| secs <= 0),
+            subject: claims.sub,
+            name: claims.name,
+            email: claims.email,
+            preferred_username: claims.preferred_username,
+            issuer: claims.iss,
+        }
+    }
+}
+
+/// Outcome of a sign-out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutReport {
+    /// The server accepted the revocation of the refresh token
+    pub revoked: bool,
+    /// Why revocation failed (the local tokens are removed regardless)
+    pub revocation_error: Option<String>,
+}
+
+fn format_unix(seconds: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(seconds, 0).map(|dt| dt.to_rfc3339())
+}
+
+fn token_lifetime(claims: Option<&JwtClaims>, now: i64) -> TokenLifetime {
+    let expires_in_secs = claims.and_then(|c| c.expires_in(now));
+    TokenLifetime {
+        issued_at: claims.and_then(|c| c.iat).and_then(format_unix),
+        expires_at: claims.and_then(|c| c.exp).and_then(format_unix),
+        expires_in_secs,
+        expired: expires_in_secs.is_some_and(|secs| secs <= 0),
+    }
+}
+
+/// Warning window for a refresh token: a day, or half its lifetime for short-lived tokens
+fn refresh_warning_window(claims: &JwtClaims) -> i64 {
+    claims.lifetime()
+        .map(|lifetime| (lifetime / 2).min(REFRESH_TOKEN_WARNING_SECS))
+        .unwrap_or(REFRESH_TOKEN_WARNING_SECS)
+}
+
+fn is_refresh_token_expiring(claims: &JwtClaims, now: i64) -> bool {
+    claims.expires_in(now).is_some_and(|secs| secs <= refresh_warning_window(claims))
+}
+
+/// Whether the access token should be refreshed now (opaque tokens are left to reactive refresh)
+fn is_access_token_due(claims: &JwtClaims, now: i64) -> bool {
+    claims.expires_in(now).is_some_and(|secs| secs <= ACCESS_TOKEN_REFRESH_AHEAD_SECS)
+}
+
+/// Read a stored token (tokens are `TOKEN` documents)
+async fn load_token(storage: &dyn Storage, key: &str) -> Result<Option<Zeroizing<String>>, AppError> {
+    Ok(documents::load::<String>(storage, key, &documents::TOKEN).await?.map(Zeroizing::new))
+}
+
+/// A token as stored
+fn token_document(token: &str) -> Result<Zeroizing<String>, AppError> {
+    documents::encode(&documents::TOKEN, &token).map(Zeroizing::new)
+}
+
+/// An account's stored refresh and access tokens
+pub async fn get_stored_tokens(account: &AccountContext) -> Result<(Option<Zeroizing<String>>, Option<Zeroizing<String>>), AppError> {
+    let storage = get_storage(account.app_handle())?;
+    Ok((
+        load_token(&*storage, &account.key(REFRESH_TOKEN_KEY)).await?,
+        load_token(&*storage, &account.key(ACCESS_TOKEN_KEY)).await?,
+    ))
 }
 
 /// Get saved access token from storage
-pub async fn get_saved_access_token(app_handle: &AppHandle) -> Result<String, AppError> {
-    let storage = create_storage_backend(app_handle.clone())?;
-    storage.retrieve(ACCESS_TOKEN_KEY).await?
+pub async fn get_saved_access_token(account: &AccountContext) -> Result<Zeroizing<String>, AppError> {
+    let storage = get_storage(account.app_handle())?;
+    load_token(&*storage, &account.key(ACCESS_TOKEN_KEY)).await?
         .ok_or_else(|| AppError::authentication("No access token found".to_string()))
 }
 
 /// Refresh tokens using saved refresh token and overwrite storage keys
-pub async fn refresh_and_save_tokens(app_handle: &AppHandle) -> Result<TokenResponse, AppError> {
+///
+/// Refreshes are single-flight per account: callers queue on the account's
+/// refresh lock, and a caller whose `stale_access_token` was already replaced
+/// while it waited reuses the stored pair instead of spending the (rotating)
+/// refresh token again.
+pub async fn refresh_and_save_tokens(
+    account: &AccountContext,
+    provider: &dyn EmaptaProvider,
+    stale_access_token: Option<&str>,
+) -> Result<TokenPair, AppError> {
+    let storage = get_storage(account.app_handle())?;
+    refresh_stored_tokens(&*storage, account.id(), provider, stale_access_token, account.logger()).await
+}
+
+/// `refresh_and_save_tokens` against explicit storage and activity logger
+async fn refresh_stored_tokens(
+    storage: &dyn Storage,
+    account_id: &str,
+    provider: &dyn EmaptaProvider,
+    stale_access_token: Option<&str>,
+    logger: Option<ActivityLogger>,
+) -> Result<TokenPair, AppError> {
+    let lock = refresh_lock(account_id);
+    let _refresh_guard = lock.lock().await;
     let start_time = std::time::Instant::now();
-    let storage = create_storage_backend(app_handle.clone())?;
+    let (access_token_key, refresh_token_key) = (storage_key(account_id, ACCESS_TOKEN_KEY), storage_key(account_id, REFRESH_TOKEN_KEY));
+
+    if let Some(stale_access_token) = stale_access_token {
+        let current_access_token = load_token(storage, &access_token_key).await?;
+        if let Some(access_token) = current_access_token.filter(|token| token.as_str() != stale_access_token) {
+            let refresh_token = load_token(storage, &refresh_token_key).await?
+                .ok_or_else(|| AppError::authentication("No refresh token found".to_string()))?;
+            log::info!("Tokens were refreshed concurrently, reusing them");
+            return Ok(TokenPair { access_token, refresh_token });
+        }
+    }
 
     // Get current refresh token
-    let refresh_token = storage.retrieve(REFRESH_TOKEN_KEY).await?
+    let refresh_token = load_token(storage, &refresh_token_key).await?
         .ok_or_else(|| AppError::authentication("No refresh token found".to_string()))?;
 
     // Exchange for new tokens
-    let result = exchange_refresh_token_api(&refresh_token).await;
+    let result = provider.exchange_refresh_token(&refresh_token).await;
     let duration_ms = start_time.elapsed().as_millis() as u64;
 
     match result {
-        Ok(new_tokens) => {
-            // OVERWRITE existing keys with new tokens (fixed key strategy)
-            storage.store(REFRESH_TOKEN_KEY, &new_tokens.refresh_token).await?;
-            storage.store(ACCESS_TOKEN_KEY, &new_tokens.access_token).await?;
+        Ok(mut new_tokens) => {
+            // OVERWRITE existing keys with new tokens (fixed key strategy), as one atomic write
+            storage.store_batch(&[
+                (&refresh_token_key, &token_document(&new_tokens.refresh_token)?),
+                (&access_token_key, &token_document(&new_tokens.access_token)?),
+            ]).await?;
 
-            println!("[TokenManager] Tokens refreshed and saved successfully");
+            log::info!("Tokens refreshed and saved successfully");
 
             // Log successful token refresh
-            if let Some(logger) = crate::logging::get_logger() {
+            if let Some(logger) = &logger {
                 let _ = logger.log_token_refresh(true, Some(duration_ms), None).await;
             }
 
-            Ok(new_tokens)
+            // `TokenResponse` wipes itself on drop, so take the values rather than copy them
+            Ok(TokenPair {
+                access_token: Zeroizing::new(std::mem::take(&mut new_tokens.access_token)),
+                refresh_token: Zeroizing::new(std::mem::take(&mut new_tokens.refresh_token)),
+            })
         }
         Err(e) => {
-            let error_msg = format!("Token refresh failed: {}", e);
-
             // Log failed token refresh
-            if let Some(logger) = crate::logging::get_logger() {
-                let _ = logger.log_token_refresh(false, Some(duration_ms), Some(&e)).await;
+            if let Some(logger) = &logger {
+                let _ = logger.log_token_refresh(false, Some(duration_ms), Some(&e.to_string())).await;
+            }
+
+            // 400/401/403 mean the refresh token itself is no longer usable;
+            // outages and transport failures stay retryable
+            Err(e.into_grant_error("Token refresh failed"))
+        }
+    }
+}
+
+/// Report the lifetimes of an account's stored tokens
+pub async fn get_token_status(account: &AccountContext) -> Result<TokenStatus, AppError> {
+    let storage = get_storage(account.app_handle())?;
+    let access_token = load_token(&*storage, &account.key(ACCESS_TOKEN_KEY)).await?;
+    let refresh_token = load_token(&*storage, &account.key(REFRESH_TOKEN_KEY)).await?;
+
+    let now = chrono::Utc::now().timestamp();
+    let access_claims = access_token.as_deref().and_then(|t| decode_claims(t).ok());
+    let refresh_claims = refresh_token.as_deref().and_then(|t| decode_claims(t).ok());
+    let identity = access_claims.clone().map(|claims| TokenIdentity::from_claims(claims, now));
+
+    Ok(TokenStatus {
+        access_token: access_token.as_ref().map(|_| token_lifetime(access_claims.as_ref(), now)),
+        refresh_token: refresh_token.as_ref().map(|_| token_lifetime(refresh_claims.as_ref(), now)),
+        next_refresh_at: access_claims.as_ref()
+            .and_then(|c| c.exp)
+            .and_then(|exp| format_unix((exp - ACCESS_TOKEN_REFRESH_AHEAD_SECS).max(now))),
+        refresh_token_expiring: refresh_claims.as_ref().is_some_and(|c| is_refresh_token_expiring(c, now)),
+        identity,
+    })
+}
+
+/// Identity of an account's stored access token (`None` without a JWT access token)
+pub async fn get_token_identity(account: &AccountContext) -> Result<Option<TokenIdentity>, AppError> {
+    let storage = get_storage(account.app_handle())?;
+    let access_token = load_token(&*storage, &account.key(ACCESS_TOKEN_KEY)).await?;
+
+    let now = chrono::Utc::now().timestamp();
+    Ok(access_token.as_deref()
+        .and_then(|t| decode_claims(t).ok())
+        .map(|claims| TokenIdentity::from_claims(claims, now)))
+}
+
+/// Subject of a token pair: the access token's `sub`, else the refresh token's
+fn token_subject(access_token: Option<&str>, refresh_token: Option<&str>) -> Option<String> {
+    [access_token, refresh_token]
+        .into_iter()
+        .flatten()
+        .find_map(|token| decode_claims(token).ok()?.sub)
+}
+
+/// Bookkeeping for the proactive refresh task, keyed by refresh token `exp`
+#[derive(Debug, Default)]
+struct ProactiveRefreshState {
+    /// Refresh token we already tried to extend by refreshing
+    extension_attempted_for: Option<i64>,
+    /// Refresh token we already warned about
+    warned_for: Option<i64>,
+}
+
+/// Start the background task that refreshes every account's tokens ahead of expiry
+pub fn start_proactive_refresh(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        log::info!("Proactive token refresh started");
+        let mut states: HashMap<String, ProactiveRefreshState> = HashMap::new();
+        let mut backoff_until: HashMap<String, tokio::time::Instant> = HashMap::new();
+
+        loop {
+            let now = tokio::time::Instant::now();
+            for account in crate::accounts::list() {
+                if is_signed_out(&account.id) || backoff_until.get(&account.id).is_some_and(|until| *until > now) {
+                    continue;
+                }
+
+                let context = AccountContext::new(app_handle.clone(), account.id.clone());
+                let provider = crate::provider::account_provider(&account.id);
+                let state = states.entry(account.id.clone()).or_default();
+                if let Err(e) = proactive_refresh_check(&context, provider.as_ref(), state).await {
+                    log::warn!("Proactive refresh failed for account '{}': {}", account.id, e);
+                    backoff_until.insert(account.id, now + PROACTIVE_FAILURE_BACKOFF);
+                }
             }
+            tokio::time::sleep(PROACTIVE_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// One pass of the proactive refresh task for one account
+async fn proactive_refresh_check(
+    account: &AccountContext,
+    provider: &dyn EmaptaProvider,
+    state: &mut ProactiveRefreshState,
+) -> Result<(), AppError> {
+    let storage = get_storage(account.app_handle())?;
+    let Some(refresh_token) = load_token(&*storage, &account.key(REFRESH_TOKEN_KEY)).await? else {
+        return Ok(()); // Not set up yet
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let refresh_claims = decode_claims(&refresh_token).ok();
+    if let Some(claims) = refresh_claims.as_ref().filter(|c| c.expires_in(now).is_some_and(|secs| secs <= 0)) {
+        // Refreshing cannot succeed; say so once per token, also when it expired while the app was closed
+        if state.warned_for != claims.exp {
+            state.warned_for = claims.exp;
+            warn_refresh_token_expiring(account, claims, now).await;
+        }
+        return Ok(());
+    }
 
-            Err(AppError::authentication(error_msg))
+    let access_token = load_token(&*storage, &account.key(ACCESS_TOKEN_KEY)).await?;
+    let access_due = match access_token.as_deref() {
+        Some(access_token) => decode_claims(access_token).is_ok_and(|c| is_access_token_due(&c, now)),
+        None => true,
+    };
+
+    // A refresh rotates the refresh token too, which may push its expiry out;
+    // try that once per refresh token before warning about it
+    let expiring_exp = refresh_claims.as_ref()
+        .filter(|c| is_refresh_token_expiring(c, now))
+        .and_then(|c| c.exp);
+    let try_extension = expiring_exp.is_some() && state.extension_attempted_for != expiring_exp;
+
+    let refresh_claims = if access_due || try_extension {
+        if try_extension {
+            state.extension_attempted_for = expiring_exp;
+        }
+        log::info!("Refreshing tokens ahead of expiry");
+        let tokens = refresh_and_save_tokens(account, provider, access_token.as_ref().map(|token| token.as_str())).await?;
+        decode_claims(&tokens.refresh_token).ok()
+    } else {
+        refresh_claims
+    };
+
+    if let Some(claims) = refresh_claims.filter(|c| is_refresh_token_expiring(c, now)) {
+        if state.warned_for != claims.exp {
+            state.warned_for = claims.exp;
+            warn_refresh_token_expiring(account, &claims, now).await;
         }
     }
+
+    Ok(())
+}
+
+/// Tell the user the refresh token will soon expire or has expired (event plus activity log;
+/// `expires_in_secs` is not positive once it has)
+async fn warn_refresh_token_expiring(account: &AccountContext, claims: &JwtClaims, now: i64) {
+    let (Some(expires_at), Some(expires_in_secs)) = (claims.exp.and_then(format_unix), claims.expires_in(now)) else {
+        return;
+    };
+
+    if expires_in_secs <= 0 {
+        log::warn!("Refresh token of account '{}' expired at {}", account.id(), expires_at);
+    } else {
+        log::warn!("Refresh token of account '{}' expires at {} ({} minutes left)", account.id(), expires_at, expires_in_secs / 60);
+    }
+
+    if let Some(logger) = account.logger() {
+        let _ = logger.log_refresh_token_expiring(&expires_at, expires_in_secs).await;
+    }
+
+    account.emit(&SchedulerEvent::RefreshTokenExpiring {
+        expires_at,
+        expires_in_secs,
+    });
 }
 
-/// Save initial tokens during setup (both refresh and access token)
+/// Save initial tokens during setup (both refresh and access token).
+///
+/// Refuses tokens issued to a different subject than the ones already stored,
+/// unless `allow_identity_change` is set (the user confirmed the switch). When
+/// either side carries no subject the tokens are saved as before.
 pub async fn save_initial_tokens(
-    app_handle: &AppHandle,
+    account: &AccountContext,
     refresh_token: &str,
     access_token: &str,
+    allow_identity_change: bool,
 ) -> Result<(), AppError> {
-    let storage = create_storage_backend(app_handle.clone())?;
+    let lock = refresh_lock(account.id());
+    let _refresh_guard = lock.lock().await;
+    let storage = get_storage(account.app_handle())?;
+
+    if !allow_identity_change {
+        let stored_access = load_token(&*storage, &account.key(ACCESS_TOKEN_KEY)).await?;
+        let stored_refresh = load_token(&*storage, &account.key(REFRESH_TOKEN_KEY)).await?;
+        let current = token_subject(stored_access.as_deref().map(String::as_str), stored_refresh.as_deref().map(String::as_str));
+        let new = token_subject(Some(access_token), Some(refresh_token));
+
+        if let (Some(current), Some(new)) = (current, new) {
+            if current != new {
+                log::warn!("New tokens for account '{}' belong to a different subject", account.id());
+                return Err(AppError::validation(
+                    IDENTITY_CHANGE_FIELD,
+                    "These tokens belong to a different EMAPTA user than the one already signed in to this account",
+                ));
+            }
+        }
+    }
 
     // Store both tokens using fixed keys
-    storage.store(REFRESH_TOKEN_KEY, refresh_token).await?;
-    storage.store(ACCESS_TOKEN_KEY, access_token).await?;
+    storage.store_batch(&[
+        (&account.key(REFRESH_TOKEN_KEY), &token_document(refresh_token)?),
+        (&account.key(ACCESS_TOKEN_KEY), &token_document(access_token)?),
+    ]).await?;
 
-    println!("[TokenManager] Initial tokens saved successfully");
+    set_signed_out(account.id(), false);
+    log::info!("Initial tokens saved successfully for account '{}'", account.id());
     Ok(())
 }
 
+/// Revoke an account's refresh token and remove both tokens from storage.
+///
+/// Holds the account's refresh lock, so a refresh in flight finishes first and cannot write
+/// tokens back afterwards. The account is then marked signed out for this
+/// session (`is_signed_out`), which the proactive refresh task and the wake-up
+/// auto clock-in check before touching it, until `save_initial_tokens` stores
+/// new tokens. Revocation is best effort: the tokens are wiped even when the
+/// server cannot be reached.
+pub async fn logout(account: &AccountContext, provider: &dyn EmaptaProvider) -> Result<LogoutReport, AppError> {
+    let storage = get_storage(account.app_handle())?;
+    sign_out(&*storage, account.id(), provider, account.logger(), |event| account.emit(event)).await
+}
+
+/// `logout` against explicit storage, activity logger and event sink
+async fn sign_out(
+    storage: &dyn Storage,
+    account_id: &str,
+    provider: &dyn EmaptaProvider,
+    logger: Option<ActivityLogger>,
+    emit: impl Fn(&SchedulerEvent),
+) -> Result<LogoutReport, AppError> {
+    let lock = refresh_lock(account_id);
+    let _refresh_guard = lock.lock().await;
+    set_signed_out(account_id, true);
+
+    let refresh_token = load_token(storage, &storage_key(account_id, REFRESH_TOKEN_KEY)).await?;
+    let (revoked, revocation_error) = match refresh_token {
+        Some(refresh_token) => match provider.revoke_refresh_token(&refresh_token).await {
+            Ok(()) => (true, None),
+            Err(e) => {
+                log::warn!("Refresh token revocation failed, removing tokens anyway: {}", e);
+                (false, Some(e.to_string()))
+            }
+        },
+        None => (false, None),
+    };
+
+    storage.delete(&storage_key(account_id, ACCESS_TOKEN_KEY)).await?;
+    storage.delete(&storage_key(account_id, REFRESH_TOKEN_KEY)).await?;
+    log::info!("Signed out of account '{}' (refresh token revoked: {})", account_id, revoked);
+
+    if let Some(logger) = logger {
+        let _ = logger.log_logout(revoked, revocation_error.as_deref()).await;
+    }
+    emit(&SchedulerEvent::SignedOut);
+
+    Ok(LogoutReport { revoked, revocation_error })
+}
+
 /// Universal API call pattern with shared token logic
 ///
 /// This function implements the complete shared token flow:
@@ -100,64 +541,67 @@ pub async fn save_initial_tokens(
 /// 3. Retry API call once with new token
 /// 4. If retry fails: return error (do nothing)
 pub async fn api_with_shared_tokens<T, F, Fut>(
-    app_handle: &AppHandle,
+    account: &AccountContext,
+    provider: &dyn EmaptaProvider,
     operation: F,
     operation_name: &str,
 ) -> Result<T, AppError>
 where
-    F: Fn(String) -> Fut,
-    Fut: Future<Output = Result<T, String>>,
+    F: Fn(Zeroizing<String>) -> Fut,
+    Fut: Future<Output = Result<T, EmaptaApiError>>,
 {
     // 1. Try with saved access token first
-    let access_token = get_saved_access_token(app_handle).await?;
+    let access_token = get_saved_access_token(account).await?;
 
     match operation(access_token.clone()).await {
         Ok(result) => {
-            println!("[TokenManager] {} succeeded with saved token", operation_name);
+            log::info!("{} succeeded with saved token", operation_name);
             Ok(result)
         }
-        Err(error) if is_token_error(&error) => {
-            println!("[TokenManager] {} failed with token error: {}", operation_name, error);
-            println!("[TokenManager] Refreshing tokens and retrying...");
+        Err(error) if error.requires_token_refresh() => {
+            log::warn!("{} failed with token error: {}", operation_name, error);
+            log::info!("Refreshing tokens and retrying...");
 
             // 2. Token error: refresh and save tokens
-            match refresh_and_save_tokens(app_handle).await {
+            match refresh_and_save_tokens(account, provider, Some(access_token.as_str())).await {
                 Ok(new_tokens) => {
                     // 3. Retry once with new token
                     match operation(new_tokens.access_token).await {
                         Ok(result) => {
-                            println!("[TokenManager] {} retry succeeded", operation_name);
+                            log::info!("{} retry succeeded", operation_name);
                             Ok(result)
                         }
                         Err(retry_error) => {
-                            println!("[TokenManager] {} retry failed: {}", operation_name, retry_error);
-                            Err(AppError::api(format!("{} retry failed: {}", operation_name, retry_error), Some(500)))
+                            log::warn!("{} retry failed: {}", operation_name, retry_error);
+                            Err(retry_error.into_app_error(&format!("{} retry failed", operation_name)))
                         }
                     }
                 }
                 Err(refresh_error) => {
-                    println!("[TokenManager] Token refresh failed: {}", refresh_error);
+                    log::warn!("Token refresh failed: {}", refresh_error);
                     Err(refresh_error)
                 }
             }
         }
         Err(error) => {
-            println!("[TokenManager] {} failed with non-token error: {}", operation_name, error);
-            Err(AppError::api(format!("{} failed: {}", operation_name, error), Some(500)))
+            log::warn!("{} failed with non-token error: {}", operation_name, error);
+            Err(error.into_app_error(&format!("{} failed", operation_name)))
         }
     }
 }
 
 /// Wrapper for attendance API using shared token logic
 pub async fn attendance_check_with_shared_tokens(
-    app_handle: &AppHandle,
+    account: &AccountContext,
+    provider: &dyn EmaptaProvider,
 ) -> Result<Option<crate::commands::AttendanceItem>, AppError> {
     let start_time = std::time::Instant::now();
 
     let result = api_with_shared_tokens(
-        app_handle,
+        account,
+        provider,
         |token| async move {
-            crate::commands::get_attendance_status_api(&token).await
+            provider.attendance_status(&token).await
         },
         "attendance_check",
     ).await;
@@ -165,7 +609,7 @@ pub async fn attendance_check_with_shared_tokens(
     let duration_ms = start_time.elapsed().as_millis() as u64;
 
     // Log attendance check
-    if let Some(logger) = crate::logging::get_logger() {
+    if let Some(logger) = account.logger() {
         match &result {
             Ok(_) => {
                 let _ = logger.log_attendance_check(true, Some(duration_ms), None).await;
@@ -180,13 +624,14 @@ pub async fn attendance_check_with_shared_tokens(
 }
 
 /// Wrapper for clock-in API using shared token logic
-pub async fn clock_in_with_shared_tokens(app_handle: &AppHandle) -> Result<bool, AppError> {
+pub async fn clock_in_with_shared_tokens(account: &AccountContext, provider: &dyn EmaptaProvider) -> Result<bool, AppError> {
     let start_time = std::time::Instant::now();
 
     let result = api_with_shared_tokens(
-        app_handle,
+        account,
+        provider,
         |token| async move {
-            crate::commands::clock_in_api(&token).await
+            provider.clock_in(&token).await
         },
         "clock_in",
     ).await;
@@ -194,7 +639,7 @@ pub async fn clock_in_with_shared_tokens(app_handle: &AppHandle) -> Result<bool,
     let duration_ms = start_time.elapsed().as_millis() as u64;
 
     // Log clock-in operation
-    if let Some(logger) = crate::logging::get_logger() {
+    if let Some(logger) = account.logger() {
         match &result {
             Ok(true) => {
                 let _ = logger.log_clock_in(true, "shared_token_api", Some(duration_ms), None).await;
@@ -212,13 +657,14 @@ pub async fn clock_in_with_shared_tokens(app_handle: &AppHandle) -> Result<bool,
 }
 
 /// Wrapper for clock-out API using shared token logic
-pub async fn clock_out_with_shared_tokens(app_handle: &AppHandle) -> Result<bool, AppError> {
+pub async fn clock_out_with_shared_tokens(account: &AccountContext, provider: &dyn EmaptaProvider) -> Result<bool, AppError> {
     let start_time = std::time::Instant::now();
 
     let result = api_with_shared_tokens(
-        app_handle,
+        account,
+        provider,
         |token| async move {
-            crate::commands::clock_out_api(&token).await
+            provider.clock_out(&token).await
         },
         "clock_out",
     ).await;
@@ -226,7 +672,7 @@ pub async fn clock_out_with_shared_tokens(app_handle: &AppHandle) -> Result<bool
     let duration_ms = start_time.elapsed().as_millis() as u64;
 
     // Log clock-out operation
-    if let Some(logger) = crate::logging::get_logger() {
+    if let Some(logger) = account.logger() {
         match &result {
             Ok(true) => {
                 let _ = logger.log_clock_out(true, "shared_token_api", Some(duration_ms), None).await;
@@ -246,16 +692,96 @@ pub async fn clock_out_with_shared_tokens(app_handle: &AppHandle) -> Result<bool
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory_storage::MemoryStorage;
+    use crate::mock_provider::{MockCallCounts, MockEmaptaProvider, MockScript};
+
+    /// Store a token pair for `account_id` as `save_initial_tokens` would
+    async fn store_tokens(storage: &dyn Storage, account_id: &str, refresh_token: &str, access_token: &str) {
+        storage.store_batch(&[
+            (&storage_key(account_id, REFRESH_TOKEN_KEY), &token_document(refresh_token).unwrap()),
+            (&storage_key(account_id, ACCESS_TOKEN_KEY), &token_document(access_token).unwrap()),
+        ]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_refreshes_exchange_once() {
+        let storage = MemoryStorage::new();
+        let mock = MockEmaptaProvider::new(MockScript { latency: Duration::from_millis(50), ..MockScript::default() });
+        store_tokens(&storage, "refresh-race", "refresh-0", "access-0").await;
+
+        // Both callers saw the same rejected access token; the second reuses the first one's pair
+        let (first, second) = tokio::join!(
+            refresh_stored_tokens(&storage, "refresh-race", &mock, Some("access-0"), None),
+            refresh_stored_tokens(&storage, "refresh-race", &mock, Some("access-0"), None),
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(mock.calls(), MockCallCounts { token_exchange: 1, ..MockCallCounts::default() });
+        assert_eq!(first.access_token, second.access_token);
+        assert_eq!(first.refresh_token, second.refresh_token);
+        let stored = load_token(&storage, &storage_key("refresh-race", REFRESH_TOKEN_KEY)).await.unwrap();
+        assert_eq!(stored.as_deref().map(String::as_str), Some(first.refresh_token.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_outage_stays_retryable() {
+        let storage = MemoryStorage::new();
+        store_tokens(&storage, "refresh-outage", "refresh-0", "access-0").await;
+
+        let outage = MockEmaptaProvider::new(MockScript { server_error_calls: 1, ..MockScript::default() });
+        let error = refresh_stored_tokens(&storage, "refresh-outage", &outage, None, None).await.unwrap_err();
+        assert!(error.is_retryable() && !matches!(error, AppError::Authentication { .. }));
+
+        let rejecting = MockEmaptaProvider::new(MockScript { reject_refresh: true, ..MockScript::default() });
+        let error = refresh_stored_tokens(&storage, "refresh-outage", &rejecting, None, None).await.unwrap_err();
+        assert!(matches!(error, AppError::Authentication { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_sign_out_revokes_and_wipes() {
+        let storage = MemoryStorage::new();
+        let mock = MockEmaptaProvider::default();
+        store_tokens(&storage, "logout-ok", "refresh", "access").await;
+
+        let events = Mutex::new(Vec::new());
+        let report = sign_out(&storage, "logout-ok", &mock, None, |event| events.lock().unwrap().push(event.clone())).await.unwrap();
+
+        assert!(report.revoked && report.revocation_error.is_none());
+        assert_eq!(mock.calls(), MockCallCounts { revocation: 1, ..MockCallCounts::default() });
+        assert!(storage.retrieve(&storage_key("logout-ok", REFRESH_TOKEN_KEY)).await.unwrap().is_none());
+        assert!(storage.retrieve(&storage_key("logout-ok", ACCESS_TOKEN_KEY)).await.unwrap().is_none());
+        assert!(matches!(events.lock().unwrap().as_slice(), [SchedulerEvent::SignedOut]));
+        assert!(is_signed_out("logout-ok"));
+    }
+
+    #[tokio::test]
+    async fn test_sign_out_wipes_when_revocation_fails() {
+        let storage = MemoryStorage::new();
+        let mock = MockEmaptaProvider::new(MockScript { server_error_calls: 1, ..MockScript::default() });
+        store_tokens(&storage, "logout-offline", "refresh", "access").await;
+
+        let events = Mutex::new(Vec::new());
+        let report = sign_out(&storage, "logout-offline", &mock, None, |event| events.lock().unwrap().push(event.clone())).await.unwrap();
+
+        assert!(!report.revoked && report.revocation_error.is_some());
+        assert_eq!(mock.calls().revocation, 1);
+        assert!(storage.retrieve(&storage_key("logout-offline", REFRESH_TOKEN_KEY)).await.unwrap().is_none());
+        assert!(storage.retrieve(&storage_key("logout-offline", ACCESS_TOKEN_KEY)).await.unwrap().is_none());
+        assert!(matches!(events.lock().unwrap().as_slice(), [SchedulerEvent::SignedOut]));
+    }
 
     #[test]
-    fn test_is_token_error() {
-        assert!(is_token_error("401 Unauthorized"));
-        assert!(is_token_error("Token expired"));
-        assert!(is_token_error("invalid_token"));
-        assert!(is_token_error("unauthorized"));
-
-        assert!(!is_token_error("500 Internal Server Error"));
-        assert!(!is_token_error("Network connection failed"));
-        assert!(!is_token_error("Parse error"));
+    fn test_token_subject() {
+        use base64::Engine;
+        let token = |payload: &str| format!(
+            "eyJhbGciOiJIUzI1NiJ9.{}.c2lnbmF0dXJl",
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(payload)
+        );
+        let access = token(r#"{"sub":"user-1"}"#);
+        let refresh = token(r#"{"sub":"user-2"}"#);
+
+        assert_eq!(token_subject(Some(&access), Some(&refresh)).as_deref(), Some("user-1"));
+        assert_eq!(token_subject(Some("opaque-access-token"), Some(&refresh)).as_deref(), Some("user-2"));
+        assert_eq!(token_subject(None, Some("opaque-refresh-token")), None);
     }
 }
\ No newline at end of file
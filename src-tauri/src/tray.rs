@@ -14,7 +14,7 @@ use tauri::{
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Manager, Runtime,
 };
-use crate::scheduler::get_scheduler;
+use crate::scheduler::{all_schedulers, BackendScheduler};
 
 /// Create the system tray with context menu
 pub fn create_system_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
@@ -26,8 +26,8 @@ pub fn create_system_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     let menu = Menu::with_items(app, &[&show_hide, &separator, &quit])?;
 
     // Attempt to create tray icon - this may fail on systems without proper GUI support
-    println!("[Tray] Attempting to create system tray icon...");
-    match TrayIconBuilder::new()
+    log::info!("Attempting to create system tray icon...");
+    match TrayIconBuilder::with_id("main")
         .icon(app.default_window_icon().unwrap().clone())
         .tooltip("Black Bird - Clock Automation")
         .menu(&menu)
@@ -40,11 +40,11 @@ pub fn create_system_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
         })
         .build(app) {
         Ok(_tray) => {
-            println!("[Tray] System tray created successfully with context menu");
+            log::info!("System tray created successfully with context menu");
             Ok(())
         }
         Err(e) => {
-            println!("[Tray] Failed to create system tray: {}", e);
+            log::warn!("Failed to create system tray: {}", e);
             Err(e)
         }
     }
@@ -62,11 +62,11 @@ fn handle_tray_event<R: Runtime>(app: &AppHandle<R>, event: TrayIconEvent) {
             if let Some(window) = app.get_webview_window("main") {
                 if window.is_visible().unwrap_or(false) {
                     let _ = window.hide();
-                    println!("[Tray] Window hidden via tray click");
+                    log::info!("Window hidden via tray click");
                 } else {
                     let _ = window.show();
                     let _ = window.set_focus();
-                    println!("[Tray] Window shown via tray click");
+                    log::info!("Window shown via tray click");
                 }
             }
         }
@@ -76,7 +76,7 @@ fn handle_tray_event<R: Runtime>(app: &AppHandle<R>, event: TrayIconEvent) {
             ..
         } => {
             // Right-click will automatically show the context menu (handled by Tauri)
-            println!("[Tray] Right-click detected - context menu should appear");
+            log::info!("Right-click detected - context menu should appear");
         }
         _ => {}
     }
@@ -90,18 +90,18 @@ fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, event: tauri::menu::MenuEve
             if let Some(window) = app.get_webview_window("main") {
                 if window.is_visible().unwrap_or(false) {
                     let _ = window.hide();
-                    println!("[Tray] Window hidden via menu");
+                    log::info!("Window hidden via menu");
                 } else {
                     let _ = window.show();
                     let _ = window.set_focus();
-                    println!("[Tray] Window shown via menu");
+                    log::info!("Window shown via menu");
                 }
             }
         }
         "quit" => {
-            // Quit the application
-            println!("[Tray] Quit requested via menu");
-            app.exit(0);
+            // Run the graceful shutdown workflow instead of exiting immediately
+            log::info!("Quit requested via menu");
+            crate::shutdown::request_shutdown(app);
         }
         _ => {}
     }
@@ -117,32 +117,58 @@ pub fn update_tray_status<R: Runtime>(app: &AppHandle<R>, status_text: &str) {
         // Note: Updating menu items in real-time requires rebuilding the menu in Tauri v2
         // For now, we'll focus on tooltip updates
 
-        println!("[Tray] Status updated: {}", status_text);
+        log::debug!("Status updated: {}", status_text);
     }
 }
 
-/// Get current status text from scheduler
+/// Refresh the tray tooltip after a scheduler or account change.
+///
+/// Runs on a separate task, since callers may hold scheduler state locks.
+pub fn schedule_status_refresh<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        update_tray_status(&app, &get_current_status_text());
+    });
+}
+
+/// Get current status text from the schedulers (one line per account when there are several)
 pub fn get_current_status_text() -> String {
-    if let Some(scheduler) = get_scheduler() {
-        let state = scheduler.get_state();
+    let schedulers = all_schedulers();
+    match schedulers.as_slice() {
+        [] => "Initializing...".to_string(),
+        [scheduler] => scheduler_status_text(scheduler),
+        _ => {
+            let accounts = crate::accounts::list();
+            schedulers.iter()
+                .map(|scheduler| {
+                    let id = scheduler.account().id();
+                    let name = accounts.iter()
+                        .find(|account| account.id == id)
+                        .map_or(id, |account| account.name.as_str());
+                    format!("{}: {}", name, scheduler_status_text(scheduler))
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+}
 
-        if state.current_session.clocked_in {
-            if let Some(clock_in_time) = &state.current_session.clock_in_time {
-                // Parse time and show simple format
-                if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(clock_in_time) {
-                    let local_time = dt.with_timezone(&chrono::Local);
-                    return format!("Clocked in at {}", local_time.format("%I:%M %p"));
-                }
-            }
-            "Currently working".to_string()
-        } else {
-            if state.is_running {
-                "Ready for work".to_string()
-            } else {
-                "Not running".to_string()
+/// Status text of a single account's scheduler
+fn scheduler_status_text(scheduler: &BackendScheduler) -> String {
+    let state = scheduler.get_state();
+
+    if state.current_session.clocked_in {
+        if let Some(clock_in_time) = &state.current_session.clock_in_time {
+            // Parse time and show simple format
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(clock_in_time) {
+                let local_time = dt.with_timezone(&chrono::Local);
+                return format!("Clocked in at {}", local_time.format("%I:%M %p"));
             }
         }
+        "Currently working".to_string()
+    } else if state.is_running {
+        "Ready for work".to_string()
     } else {
-        "Initializing...".to_string()
+        "Not running".to_string()
     }
-}
\ No newline at end of file
+}
@@ -1,12 +1,14 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import "./App.css";
 import TokenSetup from "./components/TokenSetup";
 import StatusScreen from "./components/StatusScreen";
 import ClockControls from "./components/ClockControls";
 import DebugLogs from "./components/DebugLogs";
+import StorageUnlock from "./components/StorageUnlock";
 import { AuthProvider } from "./provider/AuthProvider";
 import { QueryClientProvider, QueryClient } from "@tanstack/react-query";
 import { useAuth } from "./hooks/use-auth";
+import { getStorageStatus, type StorageStatus } from "./services/storage-service";
 
 const queryClient = new QueryClient({
   defaultOptions: {
@@ -17,7 +19,7 @@ const queryClient = new QueryClient({
 });
 
 const AppContent = () => {
-  const { refreshToken, loading } = useAuth();
+  const { refreshToken, loading, signOut } = useAuth();
   const [showEmergencyOverride, setShowEmergencyOverride] = useState(false);
   const [activeScreen, setActiveScreen] = useState<"status" | "setup" | "logs" | null>(
     "status"
@@ -35,8 +37,6 @@ const AppContent = () => {
     );
   }
 
-  console.log({ refreshToken });
-
   // If no refresh token stored, show setup (first time user)
   if (!refreshToken || activeScreen === "setup") {
     return (
@@ -115,6 +115,28 @@ const AppContent = () => {
         >
           🐛 Debug Logs
         </button>
+        <button
+          type="button"
+          onClick={async () => {
+            try {
+              await signOut();
+            } catch (error) {
+              console.error("Sign out failed:", error);
+            }
+          }}
+          style={{
+            padding: "8px 16px",
+            fontSize: "13px",
+            backgroundColor: "#6b7280",
+            color: "white",
+            border: "none",
+            borderRadius: "4px",
+            cursor: "pointer",
+            marginLeft: "10px",
+          }}
+        >
+          Sign out
+        </button>
       </div>
       {/* Emergency manual override section */}
       <div
@@ -172,6 +194,41 @@ const AppContent = () => {
 };
 
 function App() {
+  const [storage, setStorage] = useState<StorageStatus | null>(null);
+
+  useEffect(() => {
+    getStorageStatus()
+      .then(setStorage)
+      .catch((error) => {
+        console.error("Failed to read storage status:", error);
+        setStorage({ unlocked: true, keyMode: null, error: null });
+      });
+  }, []);
+
+  if (!storage) {
+    return (
+      <main className="container">
+        <h1>Black Bird</h1>
+        <div style={{ textAlign: "center", padding: "48px 24px" }}>
+          <p>Loading...</p>
+        </div>
+      </main>
+    );
+  }
+
+  // Nothing can be loaded until passphrase-protected storage is unlocked
+  if (!storage.unlocked) {
+    return (
+      <main className="container">
+        <h1>Black Bird</h1>
+        <StorageUnlock
+          reason={storage.error}
+          onUnlocked={() => setStorage({ ...storage, unlocked: true, error: null })}
+        />
+      </main>
+    );
+  }
+
   return (
     <QueryClientProvider client={queryClient}>
       <AuthProvider>
@@ -15,8 +15,16 @@ interface LogEntry {
   };
 }
 
+interface LogPage {
+  entries: LogEntry[];
+  total: number;
+  offset: number;
+  limit: number;
+}
+
 export default function DebugLogs() {
   const [logs, setLogs] = useState<LogEntry[]>([]);
+  const [search, setSearch] = useState("");
   const [loading, setLoading] = useState(false);
   const [debugInfo, setDebugInfo] = useState<any>(null);
   const [showDebugInfo, setShowDebugInfo] = useState(false);
@@ -48,6 +56,20 @@ export default function DebugLogs() {
     }
   };
 
+  const searchLogs = async () => {
+    setLoading(true);
+    try {
+      const result = await invoke<LogPage>("query_activity_logs", {
+        query: { text: search, limit: 50 }
+      });
+      setLogs(result.entries);
+    } catch (error) {
+      console.error("Failed to search logs:", error);
+    } finally {
+      setLoading(false);
+    }
+  };
+
   const clearLogs = async () => {
     try {
       await invoke("clear_activity_logs");
@@ -130,6 +152,24 @@ export default function DebugLogs() {
         </button>
       </div>
 
+      <div style={{ marginBottom: "20px" }}>
+        <input
+          value={search}
+          onChange={(e) => setSearch(e.target.value)}
+          onKeyDown={(e) => e.key === "Enter" && searchLogs()}
+          placeholder="Search log details"
+          style={{ marginRight: "10px", padding: "8px", width: "240px" }}
+        />
+        <button
+          onClick={searchLogs}
+          type="button"
+          disabled={loading}
+          style={{ padding: "8px 16px" }}
+        >
+          Search
+        </button>
+      </div>
+
       <div style={{ fontSize: "12px" }}>
         <strong>Total logs: {logs.length}</strong>
       </div>
@@ -1,5 +1,5 @@
 import type React from "react";
-import { useEffect, useRef } from "react";
+import { useEffect, useRef, useState } from "react";
 import { useAuth } from "../hooks/use-auth";
 import { useBackendSchedule } from "../hooks/use-backend-schedule";
 import {
@@ -11,6 +11,7 @@ import {
   getCompletedShiftDetails,
 } from "../queries/attendance";
 import { invoke } from "@tauri-apps/api/core";
+import type { TokenIdentity } from "../types/auth";
 
 /**
  * Simplified Status Component
@@ -36,6 +37,20 @@ export const StatusScreen: React.FC = () => {
   const completedShiftDetails = getCompletedShiftDetails(attendanceData ?? null);
 
   const hasInitializedMonitoring = useRef(false);
+  const [identity, setIdentity] = useState<TokenIdentity | null>(null);
+
+  // Show who the stored tokens belong to (decoded locally by the backend)
+  useEffect(() => {
+    if (!refreshToken) {
+      setIdentity(null);
+      return;
+    }
+    invoke<TokenIdentity | null>("token_identity")
+      .then(setIdentity)
+      .catch((error) =>
+        console.error("[StatusScreen] Failed to read token identity:", error)
+      );
+  }, [refreshToken]);
 
   // Initialize background monitoring when app starts
   // The AI keep insisting to run call this in the FE and keeps failing when he tried to run on BE
@@ -312,6 +327,13 @@ export const StatusScreen: React.FC = () => {
           color: "#6b7280",
         }}
       >
+        {identity && (
+          <div style={{ marginBottom: "4px" }}>
+            👤 {identity.name ?? identity.preferredUsername ?? identity.subject}
+            {identity.email && ` <${identity.email}>`}
+            {identity.issuer && ` • ${identity.issuer}`}
+          </div>
+        )}
         <div>
           📅 {new Date().toLocaleDateString()} • Status:{" "}
           {isCurrentlyClockedIn
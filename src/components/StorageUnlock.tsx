@@ -0,0 +1,90 @@
+import { useState, useId } from "react";
+import { unlockStorage } from "../services/storage-service";
+
+/**
+ * StorageUnlock component - asks for the storage passphrase when the app was
+ * started without it, then lets the backend finish starting up
+ */
+export default function StorageUnlock({
+  reason,
+  onUnlocked,
+}: {
+  reason: string | null;
+  onUnlocked: () => void;
+}) {
+  const passphraseId = useId();
+  const [passphrase, setPassphrase] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const [unlocking, setUnlocking] = useState(false);
+
+  async function onSubmit(e: React.FormEvent<HTMLFormElement>) {
+    e.preventDefault();
+    setError(null);
+    setUnlocking(true);
+
+    try {
+      const status = await unlockStorage(passphrase);
+      setPassphrase("");
+      if (status.unlocked) {
+        onUnlocked();
+      } else {
+        setError(status.error ?? "Storage is still locked");
+      }
+    } catch (err: any) {
+      setError(err.message || String(err));
+    } finally {
+      setUnlocking(false);
+    }
+  }
+
+  return (
+    <div className="storage-unlock">
+      <h2>Unlock storage</h2>
+      <p>
+        Your stored data is protected by a passphrase. Enter it to start
+        automation.
+      </p>
+      {reason && (
+        <p style={{ fontSize: "13px", color: "#6b7280" }}>{reason}</p>
+      )}
+
+      <form onSubmit={onSubmit}>
+        <label
+          htmlFor={passphraseId}
+          style={{ display: "block", marginBottom: "8px", fontWeight: "bold" }}
+        >
+          Passphrase:
+        </label>
+        <input
+          id={passphraseId}
+          type="password"
+          value={passphrase}
+          onChange={(e) => setPassphrase(e.target.value)}
+          disabled={unlocking}
+          style={{ width: "400px", padding: "8px", marginBottom: "16px" }}
+        />
+        <div>
+          <button
+            type="submit"
+            disabled={unlocking || !passphrase}
+            style={{
+              padding: "10px 20px",
+              fontSize: "16px",
+              backgroundColor: unlocking ? "#ccc" : "#007bff",
+              color: "white",
+              border: "none",
+              borderRadius: "4px",
+              cursor: unlocking ? "not-allowed" : "pointer",
+            }}
+          >
+            {unlocking ? "Unlocking..." : "Unlock"}
+          </button>
+        </div>
+      </form>
+
+      {error && (
+        <p style={{ marginTop: "16px", color: "#dc2626" }}>{error}</p>
+      )}
+    </div>
+  );
+}
@@ -1,6 +1,34 @@
 import { useState, useId } from "react";
 import { invoke } from '@tauri-apps/api/core';
 import { useAuth } from "../hooks/use-auth";
+import { IDENTITY_CHANGE_FIELD } from "../types/auth";
+
+/**
+ * Run a setup step, asking before replacing tokens of a different user.
+ * `confirm` completes the replacement; `decline` cleans up when the user refuses.
+ */
+async function withIdentityConfirmation<T>(
+  run: () => Promise<T>,
+  confirm: () => Promise<T>,
+  decline?: () => Promise<unknown>
+): Promise<T> {
+  try {
+    return await run();
+  } catch (err) {
+    if (!String(err).includes(IDENTITY_CHANGE_FIELD)) {
+      throw err;
+    }
+    if (
+      window.confirm(
+        "These tokens belong to a different EMAPTA user than the one currently signed in. Replace the stored tokens?"
+      )
+    ) {
+      return await confirm();
+    }
+    await decline?.();
+    throw err;
+  }
+}
 
 /**
  * TokenSetup component - collects refresh and access tokens from user
@@ -16,8 +44,7 @@ export default function TokenSetup({ onSave }: { onSave?: () => void }) {
   const [messageType, setMessageType] = useState<"success" | "error" | "info">(
     "info"
   );
-
-  console.log({ refreshToken });
+  const [signingIn, setSigningIn] = useState(false);
 
   /**
    * Handle form submission for dual token setup and validation
@@ -64,15 +91,49 @@ export default function TokenSetup({ onSave }: { onSave?: () => void }) {
     }
   }
 
+  /**
+   * Sign in through the system browser; the backend stores the tokens
+   */
+  async function onBrowserSignIn() {
+    setSigningIn(true);
+    setMessage("Complete the sign-in in your browser...");
+    setMessageType("info");
+
+    try {
+      // The sign-in is not repeated on confirmation: the backend keeps its tokens pending
+      const result = await withIdentityConfirmation(
+        () => invoke<string>('api_login_with_browser'),
+        () => invoke<string>('api_confirm_browser_login'),
+        () => invoke<string>('api_discard_browser_login')
+      );
+      setMessage(result);
+      setMessageType("success");
+
+      // Reload tokens from storage to update AuthProvider state
+      await reloadTokens();
+
+      onSave?.();
+    } catch (err: any) {
+      setMessage(err.message || String(err));
+      setMessageType("error");
+      console.error("Browser sign-in failed:", err);
+    } finally {
+      setSigningIn(false);
+    }
+  }
+
   /**
    * Authenticate with both refresh and access tokens using Tauri command
    */
   async function authenticateWithBothTokens(refreshToken: string, accessToken: string) {
     // Use the new dual token setup command
-    return await invoke('api_setup_dual_tokens', {
-      refreshToken,
-      accessToken
-    });
+    const setup = (confirmIdentityChange: boolean) =>
+      invoke('api_setup_dual_tokens', {
+        refreshToken,
+        accessToken,
+        confirmIdentityChange
+      });
+    return await withIdentityConfirmation(() => setup(false), () => setup(true));
   }
 
   /**
@@ -100,11 +161,30 @@ export default function TokenSetup({ onSave }: { onSave?: () => void }) {
 
   const isFormValid = refreshTokenValue.trim() && accessTokenValue.trim();
 
-  console.log({ tokenSETUP: refreshToken });
   return (
     <div className="token-setup">
       <h2>Token Setup</h2>
-      <p>Enter both EMAPTA tokens to enable automatic clock automation:</p>
+      <p>Sign in with your EMAPTA account to enable automatic clock automation:</p>
+
+      <button
+        type="button"
+        onClick={onBrowserSignIn}
+        disabled={loading || signingIn}
+        style={{
+          padding: "10px 20px",
+          fontSize: "16px",
+          backgroundColor: signingIn ? "#ccc" : "#007bff",
+          color: "white",
+          border: "none",
+          borderRadius: "4px",
+          cursor: signingIn ? "not-allowed" : "pointer",
+          marginBottom: "24px"
+        }}
+      >
+        {signingIn ? "Waiting for browser sign-in..." : "Sign in with EMAPTA"}
+      </button>
+
+      <p>Or enter both EMAPTA tokens manually:</p>
 
       <form onSubmit={onSubmit}>
         <div style={{ marginBottom: "16px" }}>
@@ -155,7 +235,7 @@ export default function TokenSetup({ onSave }: { onSave?: () => void }) {
 
         <button
           type="submit"
-          disabled={loading || !isFormValid}
+          disabled={loading || signingIn || !isFormValid}
           style={{
             padding: "10px 20px",
             fontSize: "16px",
@@ -1,4 +1,5 @@
 import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import {
   getStoredRefreshToken,
   validateAndStoreRefreshToken,
@@ -9,10 +10,12 @@ export const AuthContext = React.createContext<{
   accessToken: string | null;
   authenticate: (token: string) => Promise<void>;
   reloadTokens: () => Promise<void>;
+  signOut: () => Promise<void>;
   loading: boolean;
 }>({
   authenticate: async () => {},
   reloadTokens: async () => {},
+  signOut: async () => {},
   loading: false,
   refreshToken: null,
   accessToken: null,
@@ -60,9 +63,16 @@ export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({
     }
   }
 
+  async function signOut() {
+    // Backend stops the scheduler, revokes the refresh token and wipes both tokens
+    await invoke("api_logout");
+    setRefreshToken(null);
+    setAccessToken(null);
+  }
+
   return (
     <AuthContext.Provider
-      value={{ refreshToken, accessToken, authenticate, reloadTokens, loading }}
+      value={{ refreshToken, accessToken, authenticate, reloadTokens, signOut, loading }}
     >
       {children}
     </AuthContext.Provider>
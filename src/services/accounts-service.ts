@@ -0,0 +1,60 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/**
+ * Accounts Service
+ *
+ * Manages the named EMAPTA accounts held by the backend. Each account has
+ * its own tokens, schedule, scheduler and activity log; commands that take
+ * an optional `accountId` act on the default account when it is omitted.
+ */
+
+export const DEFAULT_ACCOUNT_ID = "default";
+
+export interface Account {
+  id: string;
+  name: string;
+  createdAt: string;
+}
+
+/**
+ * Name of the event carrying an account's scheduler events
+ */
+export function schedulerEventName(accountId: string): string {
+  return accountId === DEFAULT_ACCOUNT_ID
+    ? "scheduler_event"
+    : `scheduler_event:${accountId}`;
+}
+
+/**
+ * List registered accounts (default account first)
+ */
+export async function listAccounts(): Promise<Account[]> {
+  return invoke<Account[]>("list_accounts");
+}
+
+/**
+ * Add an account; the ID is derived from the name when not given
+ */
+export async function addAccount(
+  name: string,
+  accountId?: string
+): Promise<Account> {
+  return invoke<Account>("add_account", { name, accountId });
+}
+
+/**
+ * Change an account's display name
+ */
+export async function renameAccount(
+  accountId: string,
+  name: string
+): Promise<Account> {
+  return invoke<Account>("rename_account", { accountId, name });
+}
+
+/**
+ * Sign out and remove an account with all of its stored data
+ */
+export async function removeAccount(accountId: string): Promise<string> {
+  return invoke<string>("remove_account", { accountId });
+}
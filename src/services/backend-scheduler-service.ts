@@ -1,6 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { WorkSchedule } from "../types/schedule";
+import type { OperationStatus, WorkSchedule } from "../types/schedule";
 
 /**
  * Backend Scheduler Service
@@ -17,6 +17,14 @@ export interface SchedulerEvent {
   clock_out_succeeded?: { operation_id: string; actual_time: string };
   clock_in_failed?: { operation_id: string; error: string };
   clock_out_failed?: { operation_id: string; error: string };
+  operation_status_changed?: {
+    operation_id: string;
+    operation_type: "clockIn" | "clockOut";
+    from: OperationStatus;
+    to: OperationStatus;
+  };
+  refresh_token_expiring?: { expires_at: string; expires_in_secs: number };
+  signed_out?: {};
   error?: { message: string };
 }
 
@@ -29,12 +37,18 @@ export interface SchedulerState {
   };
   pendingOperations: Array<{
     id: string;
-    operationType: "ClockIn" | "ClockOut";
+    operationType: "clockIn" | "clockOut";
     scheduledTime: string;
-    status: string;
+    status: OperationStatus;
     actualTime?: string;
     errorMessage?: string;
   }>;
+  clockSkew?: {
+    offsetMs: number;
+    sampleCount: number;
+    lastMeasuredAt: string;
+    exceedsThreshold: boolean;
+  };
 }
 
 /**
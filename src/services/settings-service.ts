@@ -0,0 +1,48 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/**
+ * Settings Service
+ *
+ * Backup and restore of the app's settings as one bundle file: preferences,
+ * every account's schedule and optionally its activity log. Tokens are only
+ * written when a passphrase is given, encrypted with it, and restored only
+ * with the same passphrase. Without a `path` the backend opens a file
+ * dialog; `null` means the user cancelled it.
+ */
+
+export interface SettingsExportReport {
+  path: string;
+  accounts: number;
+  logEntries: number;
+  tokensIncluded: boolean;
+}
+
+export interface SettingsImportReport {
+  accountsAdded: string[];
+  schedules: number;
+  logEntries: number;
+  tokensImported: number;
+  /** The bundle has tokens but no passphrase was given */
+  tokensSkipped: boolean;
+}
+
+/**
+ * Export settings to a bundle file
+ */
+export async function exportSettings(options: {
+  path?: string;
+  includeLogs?: boolean;
+  passphrase?: string;
+} = {}): Promise<SettingsExportReport | null> {
+  return invoke<SettingsExportReport | null>("export_settings", options);
+}
+
+/**
+ * Validate a bundle file and apply it
+ */
+export async function importSettings(options: {
+  path?: string;
+  passphrase?: string;
+} = {}): Promise<SettingsImportReport | null> {
+  return invoke<SettingsImportReport | null>("import_settings", options);
+}
@@ -1,4 +1,5 @@
 import type { RefreshTokenStore } from "../types/auth";
+import type { WorkSchedule } from "../types/schedule";
 import { encryptString, decryptString } from "../utils/crypto";
 import { invoke } from "@tauri-apps/api/core";
 
@@ -236,61 +237,97 @@ export async function loadRefreshTokenMetadata(): Promise<RefreshTokenStore | nu
 }
 
 /**
- * Save schedule configuration securely
+ * Error returned by backend commands that report typed errors
+ */
+export interface BackendError {
+  type: string;
+  details: { field?: string; message: string };
+}
+
+function describeError(error: unknown): string {
+  if (error instanceof Error) return error.message;
+  if (typeof error === "string") return error;
+  const backend = error as Partial<BackendError> | null;
+  if (backend?.details?.message) {
+    return backend.details.field
+      ? `${backend.details.field}: ${backend.details.message}`
+      : backend.details.message;
+  }
+  return "Unknown error";
+}
+
+/**
+ * Save schedule configuration
  *
- * Stores the schedule configuration object as JSON using Tauri secure storage
- * or localStorage fallback. The schedule data is not encrypted since it's not
- * sensitive like authentication tokens.
+ * In Tauri the backend validates the schedule, stores it and applies it to
+ * the scheduler; invalid fields are rejected with a typed validation error.
+ * Outside Tauri it is kept in localStorage for development.
  *
  * @param schedule Schedule configuration object
  */
-export async function saveSchedule(schedule: any): Promise<void> {
+export async function saveSchedule(schedule: WorkSchedule): Promise<void> {
   try {
-    const scheduleJson = JSON.stringify(schedule);
-
     if (isTauriEnvironment()) {
-      // Use Tauri secure storage
-      await invoke("set_schedule", { scheduleJson });
-      console.log("Schedule saved securely via Tauri");
+      await invoke("set_schedule", { schedule });
+      console.log("Schedule saved via backend");
     } else {
       // Fallback to localStorage for development
-      localStorage.setItem("bb_schedule", scheduleJson);
+      localStorage.setItem("bb_schedule", JSON.stringify(schedule));
       console.log("Schedule saved via localStorage (development mode)");
     }
   } catch (error) {
     console.error("Failed to save schedule:", error);
-    throw new Error(
-      `Failed to save schedule: ${
-        error instanceof Error ? error.message : "Unknown error"
-      }`
-    );
+    throw new Error(`Failed to save schedule: ${describeError(error)}`);
   }
 }
 
 /**
  * Load schedule configuration
  *
- * Retrieves the schedule configuration from Tauri secure storage or
- * localStorage fallback and parses it as JSON. Returns null if no schedule
- * exists or parsing fails.
+ * Retrieves the saved schedule from the backend (or localStorage outside
+ * Tauri). Returns null if no schedule exists or it cannot be read.
  *
  * @returns Schedule configuration object or null if not found/invalid
  */
-export async function loadSchedule(): Promise<any | null> {
+export async function loadSchedule(): Promise<WorkSchedule | null> {
   try {
-    let scheduleJson: string | null = null;
-
     if (isTauriEnvironment()) {
-      // Use Tauri secure storage
-      scheduleJson = await invoke("get_schedule");
-    } else {
-      // Fallback to localStorage for development
-      scheduleJson = localStorage.getItem("bb_schedule");
+      return await invoke<WorkSchedule | null>("get_schedule");
     }
-
+    // Fallback to localStorage for development
+    const scheduleJson = localStorage.getItem("bb_schedule");
     return scheduleJson ? JSON.parse(scheduleJson) : null;
   } catch (error) {
-    console.warn("Failed to load schedule:", error);
+    console.warn("Failed to load schedule:", describeError(error));
     return null;
   }
 }
+
+/**
+ * Backend storage state. Storage protected by a passphrase stays locked
+ * when the app was launched without `BLACK_BIRD_PASSPHRASE` (e.g. autostart)
+ * until `unlockStorage` is called.
+ */
+export interface StorageStatus {
+  unlocked: boolean;
+  keyMode: "key_file" | "passphrase" | null;
+  /** Why the storage is locked */
+  error: string | null;
+}
+
+/**
+ * Get the backend storage state (always unlocked outside Tauri)
+ */
+export async function getStorageStatus(): Promise<StorageStatus> {
+  if (!isTauriEnvironment()) {
+    return { unlocked: true, keyMode: null, error: null };
+  }
+  return invoke<StorageStatus>("get_storage_status");
+}
+
+/**
+ * Unlock passphrase-protected storage; rejects with the reason on a wrong passphrase
+ */
+export async function unlockStorage(passphrase: string): Promise<StorageStatus> {
+  return invoke<StorageStatus>("unlock_storage", { passphrase });
+}
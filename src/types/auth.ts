@@ -8,6 +8,25 @@ export interface TokenResponse {
   token_type?: string;
 }
 
+/**
+ * Owner of the stored access token, decoded locally from its JWT claims
+ */
+export interface TokenIdentity {
+  subject?: string;
+  name?: string;
+  email?: string;
+  preferredUsername?: string;
+  issuer?: string;
+  expiresAt?: string;
+  expired: boolean;
+}
+
+/**
+ * Error field reported when new tokens belong to a different user;
+ * pass `confirmIdentityChange: true` to save them anyway
+ */
+export const IDENTITY_CHANGE_FIELD = "confirmIdentityChange";
+
 /**
  * Stored refresh token metadata
  */
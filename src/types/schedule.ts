@@ -10,8 +10,22 @@ export interface WorkSchedule {
   autoScheduleEnabled: boolean;
   /** Minimum work duration in minutes (default: 550 = 9 hours 10 minutes) */
   minWorkDurationMinutes: number;
+  /** Shift scheduled deadlines by the measured EMAPTA server clock skew */
+  correctClockSkew?: boolean;
 }
 
+/**
+ * Lifecycle state of a scheduled operation (mirrors the backend OperationStatus)
+ */
+export type OperationStatus =
+  | "pending"
+  | "running"
+  | "retrying"
+  | "completed"
+  | "unverified"
+  | "failed"
+  | "cancelled";
+
 /**
  * Scheduled clock operation
  */
@@ -19,7 +33,7 @@ export interface ScheduledOperation {
   id: string;
   type: "clock-in" | "clock-out";
   scheduledTime: string; // ISO timestamp
-  status: "pending" | "completed" | "failed" | "cancelled";
+  status: OperationStatus;
   actualTime?: string; // ISO timestamp when completed
   errorMessage?: string;
 }